@@ -0,0 +1,294 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use dancing_links::{
+    batch::solve_batch,
+    bitset::BitsetSolver,
+    grid::{Grid, IndexWidth},
+    latin_square::{self, LatinSquare},
+    queens::NQueens,
+    sudoku::Sudoku,
+    ColumnHeuristic, ExactCover, Learning, Solver,
+};
+use std::hint::black_box;
+
+/// Parse a 9x9 sudoku from a string of 81 digits, where 0 is an empty box.
+fn parse_sudoku(input: &str) -> Sudoku {
+    let filled = input.chars().enumerate().filter_map(|(index, c)| {
+        let value = c.to_digit(10).unwrap() as usize;
+
+        (value != 0).then_some(latin_square::Possibility {
+            row: index / 9,
+            column: index % 9,
+            value,
+        })
+    });
+
+    Sudoku::new(3, filled)
+}
+
+fn solving(c: &mut Criterion) {
+    let sudoku = parse_sudoku(
+        "006008047000607200304009060003100005010020480740005009020930600081000034905006170",
+    );
+    c.bench_function("sudoku 9x9 all solutions", |b| {
+        b.iter(|| Solver::new(black_box(&sudoku)).count())
+    });
+
+    let queens = NQueens::new(8, []);
+    c.bench_function("8 queens all solutions", |b| {
+        b.iter(|| Solver::new(black_box(&queens)).count())
+    });
+
+    let latin = LatinSquare::new(4, []);
+    c.bench_function("latin square 4x4 all solutions", |b| {
+        b.iter(|| Solver::new(black_box(&latin)).count())
+    });
+
+    let empty_sudoku = Sudoku::new(3, []);
+    c.bench_function("sudoku 9x9 first 100 solutions", |b| {
+        b.iter(|| black_box(&empty_sudoku).solver().take(100).count())
+    });
+}
+
+criterion_group!(benches, solving);
+
+fn construction(c: &mut Criterion) {
+    let empty_sudoku = Sudoku::new(3, []);
+    c.bench_function("sudoku 9x9 build solver", |b| {
+        b.iter(|| Solver::new(black_box(&empty_sudoku)))
+    });
+}
+criterion_group!(construction_benches, construction);
+
+/// Build the grid for a problem, with the links stored using the given width.
+fn build_grid<E: ExactCover>(problem: &E, width: IndexWidth) -> Grid {
+    let constraints = problem.constraints();
+    let optional_columns = constraints
+        .iter()
+        .enumerate()
+        .filter(|(_, cons)| problem.is_optional(cons))
+        .map(|(column, _)| column + 1);
+    let coordinates = problem
+        .possibilities()
+        .iter()
+        .enumerate()
+        .flat_map(|(row, poss)| {
+            constraints
+                .iter()
+                .enumerate()
+                .filter(move |(_, cons)| problem.satisfies(poss, cons))
+                .map(move |(column, _)| (row + 1, column + 1))
+        });
+
+    Grid::with_optional_columns(constraints.len(), optional_columns, coordinates)
+        .with_index_width(width)
+}
+
+/// Count up to `limit` solutions with a plain recursive Algorithm X, so that
+/// the work is dominated by traversing the grid. The grid is left in its
+/// original state.
+fn count_solutions(grid: &mut Grid, limit: usize) -> usize {
+    let Some(column) = grid
+        .uncovered_primary_columns()
+        .min_by_key(|column| grid.column_size(*column))
+    else {
+        return 1;
+    };
+
+    let mut count = 0;
+    let rows: Vec<_> = grid.column_nodes(column).collect();
+    for node in rows {
+        if count >= limit {
+            break;
+        }
+
+        grid.select_row(node);
+        count += count_solutions(grid, limit - count);
+        grid.unselect_row(node);
+    }
+
+    count
+}
+
+fn index_widths(c: &mut Criterion) {
+    let mut group = c.benchmark_group("index width");
+
+    // The 25x25 grid has 62,500 nodes, which is 1MB of links with 32-bit indices
+    // and 2MB with 64-bit indices.
+    let problems = [
+        (
+            "10 queens all solutions",
+            build_grid(&NQueens::new(10, []), IndexWidth::U32),
+            usize::MAX,
+        ),
+        (
+            "sudoku 25x25 first 10 solutions",
+            build_grid(&Sudoku::new(5, []), IndexWidth::U32),
+            10,
+        ),
+    ];
+
+    for (name, grid, limit) in problems {
+        for width in [IndexWidth::U32, IndexWidth::Usize] {
+            let mut grid = grid.clone().with_index_width(width);
+            group.bench_function(BenchmarkId::new(name, format!("{width:?}")), |b| {
+                b.iter(|| count_solutions(black_box(&mut grid), limit))
+            });
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(index_width_benches, index_widths);
+
+fn backends(c: &mut Criterion) {
+    let mut group = c.benchmark_group("backend");
+
+    let queens = NQueens::new(8, []);
+    group.bench_function("8 queens all solutions/grid", |b| {
+        b.iter(|| Solver::new(black_box(&queens)).count())
+    });
+    group.bench_function("8 queens all solutions/bitset", |b| {
+        b.iter(|| {
+            BitsetSolver::<_, 1>::new(black_box(&queens))
+                .unwrap()
+                .count()
+        })
+    });
+
+    let latin = LatinSquare::new(4, []);
+    group.bench_function("latin square 4x4 all solutions/grid", |b| {
+        b.iter(|| Solver::new(black_box(&latin)).count())
+    });
+    group.bench_function("latin square 4x4 all solutions/bitset", |b| {
+        b.iter(|| {
+            BitsetSolver::<_, 1>::new(black_box(&latin))
+                .unwrap()
+                .count()
+        })
+    });
+
+    let sudoku = Sudoku::new(2, []);
+    group.bench_function("sudoku 4x4 all solutions/grid", |b| {
+        b.iter(|| Solver::new(black_box(&sudoku)).count())
+    });
+    group.bench_function("sudoku 4x4 all solutions/bitset", |b| {
+        b.iter(|| {
+            BitsetSolver::<_, 1>::new(black_box(&sudoku))
+                .unwrap()
+                .count()
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(backend_benches, backends);
+
+/// Build a 6x6 latin square completion problem from `(row, column, value)`
+/// triples.
+fn latin_completion(filled: &[(usize, usize, usize)]) -> LatinSquare {
+    let filled = filled
+        .iter()
+        .map(|&(row, column, value)| latin_square::Possibility { row, column, value });
+
+    LatinSquare::new(6, filled)
+}
+
+fn learning(c: &mut Criterion) {
+    let mut group = c.benchmark_group("learning");
+
+    // Completions without any solution, where the whole tree is searched
+    let instances = [
+        (
+            "infeasible latin square 6x6 a",
+            latin_completion(&[
+                (2, 1, 2),
+                (1, 3, 1),
+                (5, 4, 3),
+                (4, 5, 3),
+                (3, 3, 5),
+                (3, 1, 3),
+                (2, 5, 4),
+                (2, 2, 3),
+                (1, 0, 2),
+            ]),
+        ),
+        (
+            "infeasible latin square 6x6 b",
+            latin_completion(&[
+                (1, 1, 4),
+                (4, 2, 5),
+                (5, 2, 6),
+                (2, 3, 2),
+                (2, 5, 5),
+                (3, 2, 1),
+                (2, 1, 6),
+                (2, 0, 3),
+                (4, 4, 1),
+            ]),
+        ),
+    ];
+
+    for (name, latin) in &instances {
+        group.bench_function(BenchmarkId::new(*name, "off"), |b| {
+            b.iter(|| Solver::new(black_box(latin)).count())
+        });
+        group.bench_function(BenchmarkId::new(*name, "on"), |b| {
+            b.iter(|| {
+                Solver::new(black_box(latin))
+                    .with_learning(Learning::default())
+                    .count()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(learning_benches, learning);
+
+fn batch(c: &mut Criterion) {
+    // Small puzzles, where building the solver takes a large part of the time
+    let sudokus: Vec<_> = (0..100)
+        .map(|index| {
+            let filled = [(0, 0), (1, 2), (2, 1), (3, 3)]
+                .into_iter()
+                .enumerate()
+                .map(|(offset, (row, column))| latin_square::Possibility {
+                    row,
+                    column,
+                    value: (index + offset) % 4 + 1,
+                });
+            Sudoku::new(2, filled)
+        })
+        .collect();
+
+    let mut group = c.benchmark_group("batch of 4x4 sudokus");
+    group.bench_function("new solvers", |b| {
+        b.iter(|| {
+            black_box(&sudokus)
+                .iter()
+                .map(|sudoku| Solver::new(sudoku).count())
+                .collect::<Vec<_>>()
+        })
+    });
+    group.bench_function("reused solver", |b| {
+        b.iter(|| {
+            solve_batch(black_box(&sudokus), ColumnHeuristic::default(), |solver| {
+                solver.count()
+            })
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(batch_benches, batch);
+criterion_main!(
+    benches,
+    construction_benches,
+    index_width_benches,
+    backend_benches,
+    learning_benches,
+    batch_benches
+);
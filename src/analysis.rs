@@ -0,0 +1,313 @@
+//! Structural statistics of an exact cover problem, to check an encoding
+//! before solving it.
+//!
+//! [`analyze`] builds the same grid as the [`Solver`], and reports how large
+//! and how dense it is, along with a few common mistakes in an encoding:
+//! primary constraints that no possibility satisfies, possibilities that
+//! can never be chosen, and possibilities that are duplicates of each other.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{analysis::analyze, text::TextProblem};
+//!
+//! // `e` can't be covered, and the last two options are the same
+//! let problem = TextProblem::parse("a b c d e\na b\nc d\nc d\n").unwrap();
+//! let analysis = analyze(&problem);
+//!
+//! assert_eq!(analysis.num_primary, 5);
+//! assert_eq!(analysis.empty_primary, [4]);
+//! assert_eq!(analysis.duplicates, [vec![1, 2]]);
+//! assert!(analysis.is_trivially_unsatisfiable());
+//! ```
+
+use crate::{ColumnHeuristic, ExactCover, Solver};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Structural statistics of an exact cover problem, returned by [`analyze`].
+///
+/// Possibilities and constraints are identified by their index in
+/// [`ExactCover::possibilities`] and [`ExactCover::constraints`].
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Analysis {
+    /// The number of possibilities.
+    pub num_possibilities: usize,
+    /// The number of primary constraints, which must be satisfied exactly
+    /// once.
+    pub num_primary: usize,
+    /// The number of optional constraints, which may be satisfied at most
+    /// once.
+    pub num_optional: usize,
+    /// The number of pairs of a possibility and a constraint it satisfies.
+    pub num_nonzeros: usize,
+    /// The fraction of all pairs of a possibility and a constraint where the
+    /// possibility satisfies the constraint, or 0 if there are none.
+    pub density: f64,
+    /// The number of constraints satisfied by each number of possibilities,
+    /// where `column_sizes[k]` counts the constraints satisfied by exactly `k`
+    /// possibilities. The last entry is not zero, unless there are no
+    /// constraints.
+    pub column_sizes: Vec<usize>,
+    /// The possibilities which don't satisfy any primary constraint. The
+    /// solver never chooses them.
+    pub optional_only: Vec<usize>,
+    /// The primary constraints which no possibility satisfies. If there are
+    /// any, the problem has no solutions.
+    pub empty_primary: Vec<usize>,
+    /// The groups of possibilities which satisfy exactly the same
+    /// constraints, each in increasing order, ordered by their first
+    /// possibility.
+    pub duplicates: Vec<Vec<usize>>,
+    /// The constraint chosen first by
+    /// [`ColumnHeuristic::MinimumRemainingValues`], and the number of
+    /// possibilities that satisfy it, or `None` if there are no primary
+    /// constraints.
+    pub initial_branching: Option<(usize, usize)>,
+}
+
+impl Analysis {
+    /// Return true if the problem has no solutions because some primary
+    /// constraint can't be satisfied, or because there are no primary
+    /// constraints at all.
+    pub fn is_trivially_unsatisfiable(&self) -> bool {
+        self.num_primary == 0 || !self.empty_primary.is_empty()
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "possibilities: {}", self.num_possibilities)?;
+        writeln!(
+            f,
+            "constraints: {} primary, {} optional",
+            self.num_primary, self.num_optional
+        )?;
+        writeln!(
+            f,
+            "nonzeros: {} (density {:.4})",
+            self.num_nonzeros, self.density
+        )?;
+
+        write!(f, "column sizes:")?;
+        for (size, count) in self.column_sizes.iter().enumerate() {
+            if *count > 0 {
+                write!(f, " {size}: {count},")?;
+            }
+        }
+        writeln!(f)?;
+
+        writeln!(
+            f,
+            "possibilities without primary constraints: {:?}",
+            self.optional_only
+        )?;
+        writeln!(f, "empty primary constraints: {:?}", self.empty_primary)?;
+        writeln!(f, "duplicate possibilities: {:?}", self.duplicates)?;
+        match self.initial_branching {
+            Some((constraint, size)) => write!(
+                f,
+                "initial branching: constraint {constraint} with {size} possibilities"
+            ),
+            None => write!(f, "initial branching: none"),
+        }
+    }
+}
+
+/// Compute the structural statistics of the given problem.
+pub fn analyze<E: ExactCover>(problem: &E) -> Analysis {
+    let grid = Solver::populate_grid(problem);
+    // The grid has no rows after the last one which satisfies a constraint
+    let num_possibilities = problem.possibilities().len();
+    let num_constraints = grid.num_columns();
+
+    let num_optional = (1..=num_constraints)
+        .filter(|column| grid.is_column_optional(*column))
+        .count();
+    let num_nonzeros = (1..=num_constraints)
+        .map(|column| grid.column_size(column))
+        .sum();
+    let density = if num_nonzeros == 0 {
+        0.0
+    } else {
+        num_nonzeros as f64 / (num_possibilities as f64 * num_constraints as f64)
+    };
+
+    let mut column_sizes = Vec::new();
+    for column in 1..=num_constraints {
+        let size = grid.column_size(column);
+        if size >= column_sizes.len() {
+            column_sizes.resize(size + 1, 0);
+        }
+        column_sizes[size] += 1;
+    }
+
+    let empty_primary = (1..=num_constraints)
+        .filter(|column| !grid.is_column_optional(*column) && grid.column_size(*column) == 0)
+        .map(|column| column - 1)
+        .collect();
+
+    // The columns of each row, which are in increasing order
+    let rows: Vec<Vec<usize>> = (1..=num_possibilities)
+        .map(|row| {
+            grid.row_nodes(row)
+                .map(|node| grid.node_column(node))
+                .collect()
+        })
+        .collect();
+    let optional_only = rows
+        .iter()
+        .enumerate()
+        .filter(|(_, columns)| {
+            columns
+                .iter()
+                .all(|column| grid.is_column_optional(*column))
+        })
+        .map(|(row_index, _)| row_index)
+        .collect();
+
+    // Sorting by the columns puts duplicates next to each other, and the sort
+    // is stable so each group is in increasing order
+    let mut order: Vec<_> = (0..num_possibilities).collect();
+    order.sort_by(|a, b| rows[*a].cmp(&rows[*b]));
+    let mut duplicates: Vec<Vec<usize>> = order
+        .chunk_by(|a, b| rows[*a] == rows[*b])
+        .filter(|group| group.len() > 1)
+        .map(<[usize]>::to_vec)
+        .collect();
+    duplicates.sort_unstable();
+
+    let initial_branching = ColumnHeuristic::MinimumRemainingValues
+        .choose_column(&grid)
+        .map(|column| (column - 1, grid.column_size(column)));
+
+    Analysis {
+        num_possibilities,
+        num_primary: num_constraints - num_optional,
+        num_optional,
+        num_nonzeros,
+        density,
+        column_sizes,
+        optional_only,
+        empty_primary,
+        duplicates,
+        initial_branching,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        queens::NQueens,
+        sudoku::Sudoku,
+        testing::{random_problem, RandomProblemConfig},
+        text::TextProblem,
+    };
+
+    #[test]
+    fn text_problem_report() {
+        // `f` is optional, the option `f` only covers it, and `e` can't be covered
+        let problem = TextProblem::parse("a b c d e | f\na b\nc d\nb a\nf\nc d f\n").unwrap();
+        let analysis = analyze(&problem);
+
+        assert_eq!(
+            analysis,
+            Analysis {
+                num_possibilities: 5,
+                num_primary: 5,
+                num_optional: 1,
+                num_nonzeros: 10,
+                density: 10.0 / 30.0,
+                column_sizes: vec![1, 0, 5],
+                optional_only: vec![3],
+                empty_primary: vec![4],
+                duplicates: vec![vec![0, 2]],
+                initial_branching: Some((4, 0)),
+            }
+        );
+        assert!(analysis.is_trivially_unsatisfiable());
+        assert_eq!(
+            analysis.to_string(),
+            "possibilities: 5\n\
+             constraints: 5 primary, 1 optional\n\
+             nonzeros: 10 (density 0.3333)\n\
+             column sizes: 0: 1, 2: 5,\n\
+             possibilities without primary constraints: [3]\n\
+             empty primary constraints: [4]\n\
+             duplicate possibilities: [[0, 2]]\n\
+             initial branching: constraint 4 with 0 possibilities"
+        );
+    }
+
+    #[test]
+    fn puzzles() {
+        let queens = analyze(&NQueens::new(8, []));
+        assert_eq!(queens.num_possibilities, 64);
+        assert_eq!((queens.num_primary, queens.num_optional), (16, 30));
+        assert_eq!(queens.num_nonzeros, 4 * 64);
+        // The rows and columns have 8 squares, the diagonals 1 to 8
+        assert_eq!(queens.column_sizes, [0, 4, 4, 4, 4, 4, 4, 4, 18]);
+        assert_eq!(queens.initial_branching, Some((0, 8)));
+        assert!(queens.duplicates.is_empty());
+        assert!(!queens.is_trivially_unsatisfiable());
+
+        let sudoku = analyze(&Sudoku::new(3, []));
+        assert_eq!(sudoku.num_possibilities, 729);
+        assert_eq!(sudoku.num_primary, 324);
+        assert_eq!(sudoku.density, 4.0 / 324.0);
+        assert_eq!(sudoku.column_sizes.len(), 10);
+        assert_eq!(sudoku.column_sizes[9], 324);
+        assert!(sudoku.optional_only.is_empty());
+    }
+
+    #[test]
+    fn random_problems_are_consistent() {
+        for seed in 0..if cfg!(miri) { 20 } else { 300 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let analysis = analyze(&problem);
+
+            assert_eq!(
+                analysis.num_primary + analysis.num_optional,
+                problem.constraints.len()
+            );
+            assert_eq!(
+                analysis.column_sizes.iter().sum::<usize>(),
+                problem.constraints.len()
+            );
+            let nonzeros: usize = analysis
+                .column_sizes
+                .iter()
+                .enumerate()
+                .map(|(size, count)| size * count)
+                .sum();
+            assert_eq!(nonzeros, analysis.num_nonzeros, "seed {seed}");
+
+            for group in &analysis.duplicates {
+                for pair in group.windows(2) {
+                    assert!(pair[0] < pair[1]);
+                    let items = |index: usize| &problem.possibilities[index].items;
+                    assert_eq!(items(pair[0]), items(pair[1]), "seed {seed}");
+                }
+            }
+            let in_groups: usize = analysis.duplicates.iter().map(Vec::len).sum();
+            let mut distinct: Vec<_> = problem
+                .possibilities
+                .iter()
+                .map(|poss| &poss.items)
+                .collect();
+            distinct.sort();
+            distinct.dedup();
+            assert_eq!(
+                distinct.len(),
+                problem.possibilities.len() - in_groups + analysis.duplicates.len(),
+                "seed {seed}"
+            );
+
+            if !analysis.empty_primary.is_empty() {
+                assert_eq!(problem.solver().count(), 0, "seed {seed}");
+            }
+        }
+    }
+}
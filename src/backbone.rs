@@ -0,0 +1,215 @@
+//! Finding which possibilities are part of every solution of an exact cover
+//! problem, of some solutions, or of none.
+//!
+//! The possibilities that are part of every solution are called the backbone
+//! of the problem. [`backbone`] classifies every possibility without
+//! enumerating all of the solutions: it searches for a solution which
+//! contains a given possibility, or one which leaves it out, and every
+//! solution found along the way settles the question for all of the other
+//! possibilities too.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{
+//!     backbone::{backbone, Usage},
+//!     text::TextProblem,
+//! };
+//!
+//! // The solutions are `a b` + `c d`, and `a b` + `c` + `d`
+//! let problem = TextProblem::parse("a b c d\na b\nc d\nc\nd\na c\n").unwrap();
+//!
+//! assert_eq!(
+//!     backbone(&problem),
+//!     [
+//!         Usage::Always,
+//!         Usage::Sometimes,
+//!         Usage::Sometimes,
+//!         Usage::Sometimes,
+//!         Usage::Never,
+//!     ]
+//! );
+//! ```
+
+use crate::{ColumnHeuristic, ExactCover, Solver};
+use alloc::{vec, vec::Vec};
+use core::ops::ControlFlow;
+
+/// How a possibility is used by the solutions of a problem.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Usage {
+    /// The possibility is part of every solution, and there is at least one
+    /// solution.
+    Always,
+    /// The possibility is part of some solutions, but not all of them.
+    Sometimes,
+    /// The possibility is not part of any solution.
+    Never,
+}
+
+/// Classify every possibility of the given problem by how it is used in the
+/// solutions, returning one [`Usage`] per index of
+/// [`ExactCover::possibilities`].
+///
+/// If the problem has no solutions, every possibility is
+/// [`Usage::Never`]. The search respects [`ExactCover::is_viable`].
+pub fn backbone<E: ExactCover>(problem: &E) -> Vec<Usage> {
+    let grid = Solver::populate_grid(problem);
+    let num_rows = problem.possibilities().len();
+
+    // Whether some solution found so far contains or leaves out each row
+    let mut included = vec![false; num_rows];
+    let mut excluded = vec![false; num_rows];
+    let Some(first) = first_solution(Solver::with_grid(
+        problem,
+        grid.clone(),
+        ColumnHeuristic::default(),
+    )) else {
+        return vec![Usage::Never; num_rows];
+    };
+    record(&first, &mut included, &mut excluded);
+
+    for row_index in 0..num_rows {
+        // Look for a solution with the row, unless one was already found. Rows
+        // without a primary column are never chosen by the solver.
+        let has_primary = grid
+            .row_nodes(row_index + 1)
+            .any(|node| !grid.is_column_optional(grid.node_column(node)));
+        if !included[row_index] && has_primary {
+            let solver = Solver::with_grid(problem, grid.clone(), ColumnHeuristic::default())
+                .force_rows(&[row_index]);
+            if let Some(solution) = solver.and_then(first_solution) {
+                record(&solution, &mut included, &mut excluded);
+            }
+        }
+
+        // Look for a solution without the row, unless one was already found
+        if included[row_index] && !excluded[row_index] {
+            let mut without = grid.clone();
+            without.hide_row(row_index + 1);
+            let solver = Solver::with_grid(problem, without, ColumnHeuristic::default());
+            if let Some(solution) = first_solution(solver) {
+                record(&solution, &mut included, &mut excluded);
+            }
+        }
+    }
+
+    included
+        .into_iter()
+        .zip(excluded)
+        .map(|(included, excluded)| match (included, excluded) {
+            (true, true) => Usage::Sometimes,
+            (true, false) => Usage::Always,
+            (false, _) => Usage::Never,
+        })
+        .collect()
+}
+
+/// Mark the rows of the solution as included, and the other rows as excluded.
+fn record(solution: &[usize], included: &mut [bool], excluded: &mut [bool]) {
+    let mut in_solution = vec![false; included.len()];
+    for row_index in solution {
+        in_solution[*row_index] = true;
+    }
+
+    for (row_index, in_solution) in in_solution.into_iter().enumerate() {
+        if in_solution {
+            included[row_index] = true;
+        } else {
+            excluded[row_index] = true;
+        }
+    }
+}
+
+/// Return the possibility indices of the first solution found by the solver.
+fn first_solution<E: ExactCover>(mut solver: Solver<'_, E>) -> Option<Vec<usize>> {
+    let mut found = None;
+    let _ = solver.solve_with(|solution| {
+        found = Some(solution.row_indices().to_vec());
+        ControlFlow::Break(())
+    });
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        latin_square,
+        queens::NQueens,
+        sudoku::Sudoku,
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+    };
+
+    #[test]
+    fn unique_sudoku() {
+        // Some of the cells of this solution
+        // 1 2 3 4
+        // 3 4 1 2
+        // 2 1 4 3
+        // 4 3 2 1
+        let filled = [
+            (0, 0, 1),
+            (0, 1, 2),
+            (1, 0, 3),
+            (1, 2, 1),
+            (2, 2, 4),
+            (2, 3, 3),
+            (3, 1, 3),
+            (3, 3, 1),
+        ]
+        .map(|(row, column, value)| latin_square::Possibility { row, column, value });
+        let sudoku = Sudoku::new(2, filled);
+        let solutions: Vec<_> = sudoku.solver().collect();
+        assert_eq!(solutions.len(), 1);
+
+        let usage = backbone(&sudoku);
+        for (poss, usage) in sudoku.possibilities.iter().zip(usage) {
+            let expected = if solutions[0].contains(&poss) {
+                Usage::Always
+            } else {
+                Usage::Never
+            };
+            assert_eq!(usage, expected, "{poss:?}");
+        }
+    }
+
+    #[test]
+    fn queens_have_no_backbone() {
+        let queens = NQueens::new(6, []);
+        let usage = backbone(&queens);
+
+        // The four solutions use 24 different squares, and never the corners
+        let sometimes = usage.iter().filter(|usage| **usage == Usage::Sometimes);
+        assert_eq!(sometimes.count(), 24);
+        assert!(!usage.contains(&Usage::Always));
+        assert_eq!(usage[0], Usage::Never);
+    }
+
+    #[test]
+    fn random_problems_match_reference() {
+        for seed in 0..if cfg!(miri) { 20 } else { 300 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let solutions = reference_solutions(&problem);
+
+            let expected: Vec<_> = (0..problem.possibilities.len())
+                .map(|index| {
+                    let count = solutions
+                        .iter()
+                        .filter(|solution| solution.possibilities.contains(&index))
+                        .count();
+
+                    match count {
+                        0 => Usage::Never,
+                        count if count == solutions.len() => Usage::Always,
+                        _ => Usage::Sometimes,
+                    }
+                })
+                .collect();
+
+            assert_eq!(backbone(&problem), expected, "seed {seed}");
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! Solving many problems one after the other, reusing the memory of a single
+//! [`Solver`].
+//!
+//! Creating a `Solver` allocates its grid and the state of its search, which
+//! can take longer than the search itself for small problems. [`solve_batch`]
+//! creates one `Solver` for the first problem, and moves it on to each of the
+//! next problems with [`Solver::reuse_for`].
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{batch::solve_batch, queens::NQueens, ColumnHeuristic};
+//!
+//! let boards: Vec<_> = (1..=8).map(|n| NQueens::new(n, [])).collect();
+//! let counts = solve_batch(&boards, ColumnHeuristic::default(), |solver| {
+//!     solver.count()
+//! });
+//!
+//! assert_eq!(counts, [1, 0, 0, 2, 10, 4, 40, 92]);
+//! ```
+
+use crate::{ColumnHeuristic, ExactCover, Solver};
+use alloc::vec::Vec;
+
+/// Call `solve` with a `Solver` for each of the given problems, and return
+/// its results in the same order as the problems.
+///
+/// Every solver searches with the given heuristic, and starts from scratch.
+/// The memory of each solver is reused for the next problem, so that solving
+/// many small problems doesn't allocate a new `Solver` every time.
+pub fn solve_batch<'e, E, R>(
+    problems: impl IntoIterator<Item = &'e E>,
+    heuristic: ColumnHeuristic,
+    mut solve: impl FnMut(&mut Solver<'e, E>) -> R,
+) -> Vec<R>
+where
+    E: ExactCover + 'e,
+{
+    let problems = problems.into_iter();
+    let mut results = Vec::with_capacity(problems.size_hint().0);
+    let mut solver: Option<Solver<'e, E>> = None;
+
+    for problem in problems {
+        let mut next = match solver.take() {
+            Some(solver) => solver.reuse_for(problem),
+            None => Solver::with_heuristic(problem, heuristic),
+        };
+        results.push(solve(&mut next));
+        solver = Some(next);
+    }
+
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        latin_square::{self, LatinSquare},
+        sudoku::Sudoku,
+        testing::{random_problem, RandomProblemConfig},
+        text, Learning,
+    };
+
+    #[test]
+    fn sudokus_match_fresh_solvers() {
+        // Each puzzle fills the top row of a 4x4 sudoku differently
+        let puzzles: Vec<_> = [[1, 2, 3, 4], [2, 1, 4, 3], [4, 3, 2, 1]]
+            .into_iter()
+            .map(|values| {
+                let filled = values.into_iter().enumerate().map(|(column, value)| {
+                    latin_square::Possibility {
+                        row: 0,
+                        column,
+                        value,
+                    }
+                });
+                Sudoku::new(2, filled)
+            })
+            .collect();
+
+        let solutions = solve_batch(&puzzles, ColumnHeuristic::FirstUncovered, |solver| {
+            let solutions: Vec<_> = solver.by_ref().collect();
+            (solutions, solver.stats())
+        });
+        for (puzzle, (batch, stats)) in puzzles.iter().zip(solutions) {
+            let mut solver = Solver::with_heuristic(puzzle, ColumnHeuristic::FirstUncovered);
+            assert_eq!(batch, solver.by_ref().collect::<Vec<_>>());
+            assert_eq!(stats, solver.stats());
+        }
+    }
+
+    #[test]
+    fn random_problems_match_fresh_solvers() {
+        fn indices(solution: Vec<&text::Possibility>) -> Vec<usize> {
+            solution
+                .iter()
+                .map(|possibility| possibility.index)
+                .collect()
+        }
+
+        let problems: Vec<_> = (0..if cfg!(miri) { 20 } else { 300 })
+            .map(|seed| random_problem(seed, &RandomProblemConfig::random(seed)))
+            .collect();
+
+        let orders = solve_batch(&problems, ColumnHeuristic::default(), |solver| {
+            solver.by_ref().map(indices).collect::<Vec<_>>()
+        });
+        for (problem, order) in problems.iter().zip(orders) {
+            let expected: Vec<_> = problem.solver().map(indices).collect();
+            assert_eq!(order, expected);
+        }
+    }
+
+    #[test]
+    fn reuse_keeps_learning_and_observer() {
+        #[derive(Debug, Default)]
+        struct CountSolutions(usize);
+
+        impl crate::SolverObserver for CountSolutions {
+            fn on_solution(&mut self, _: &[usize]) {
+                self.0 += 1;
+            }
+        }
+
+        let small = LatinSquare::new(3, []);
+        let large = LatinSquare::new(4, []);
+        let mut solver = Solver::new(&small)
+            .with_observer(CountSolutions::default())
+            .with_learning(Learning::default());
+        assert_eq!(solver.by_ref().count(), 12);
+
+        let mut solver = solver.reuse_for(&large);
+        assert_eq!(solver.stats().nodes, 0);
+        assert_eq!(solver.by_ref().count(), 576);
+        assert_eq!(solver.observer().0, 12 + 576);
+    }
+}
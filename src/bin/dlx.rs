@@ -0,0 +1,204 @@
+//! Solve exact cover problems described in the `dancing_links::text` format.
+//!
+//! Usage:
+//!
+//! ```bash
+//! cargo run --release --bin dlx -- [OPTIONS] [FILE]
+//! ```
+//!
+//! The problem is read from `FILE`, or from stdin if no file (or `-`) is given.
+//! Each solution is printed on its own line, as the indices of the chosen
+//! options in increasing order.
+
+use core::ops::ControlFlow;
+use dancing_links::{text::TextProblem, ColumnHeuristic, Solver};
+use std::{
+    fs,
+    io::{self, BufWriter, Read, Write},
+    process,
+};
+
+const USAGE: &str = "\
+Usage: dlx [OPTIONS] [FILE]
+
+Solve the exact cover problem in FILE (or stdin, if FILE is missing or `-`).
+
+Options:
+  -c, --count             Print only the number of solutions
+  -n, --max <N>           Stop after finding N solutions
+  -s, --stats             Print search statistics to stderr
+      --heuristic <NAME>  Column choice heuristic: `mrv` (default) or `first`
+      --names             Print each option as its item names instead of its index
+  -h, --help              Print this help message
+";
+
+#[derive(Debug, Default)]
+struct Args {
+    input: Option<String>,
+    count: bool,
+    max_solutions: Option<u64>,
+    stats: bool,
+    heuristic: ColumnHeuristic,
+    names: bool,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-c" | "--count" => parsed.count = true,
+            "-s" | "--stats" => parsed.stats = true,
+            "--names" => parsed.names = true,
+            "-n" | "--max" => {
+                let value = args.next().ok_or("missing value for `--max`")?;
+                let max = value
+                    .parse()
+                    .map_err(|_| format!("invalid value for `--max`: [{value}]"))?;
+                parsed.max_solutions = Some(max);
+            }
+            "--heuristic" => {
+                let value = args.next().ok_or("missing value for `--heuristic`")?;
+                parsed.heuristic = match value.as_str() {
+                    "mrv" => ColumnHeuristic::MinimumRemainingValues,
+                    "first" => ColumnHeuristic::FirstUncovered,
+                    _ => return Err(format!("unknown heuristic [{value}]")),
+                };
+            }
+            "-h" | "--help" => {
+                print!("{USAGE}");
+                process::exit(0);
+            }
+            _ if arg.starts_with('-') && arg != "-" => {
+                return Err(format!("unknown option [{arg}]"));
+            }
+            _ => {
+                if parsed.input.replace(arg).is_some() {
+                    return Err("more than one input file given".into());
+                }
+            }
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn read_input(path: Option<&str>) -> io::Result<String> {
+    match path {
+        None | Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+        Some(path) => fs::read_to_string(path),
+    }
+}
+
+fn write_solution(
+    out: &mut impl Write,
+    problem: &TextProblem,
+    solution: &[usize],
+    names: bool,
+) -> io::Result<()> {
+    for (idx, poss) in solution
+        .iter()
+        .map(|index| &problem.possibilities[*index])
+        .enumerate()
+    {
+        if names {
+            if idx != 0 {
+                write!(out, ", ")?;
+            }
+            for (item_idx, name) in problem.item_names(poss).enumerate() {
+                if item_idx != 0 {
+                    write!(out, " ")?;
+                }
+                write!(out, "{name}")?;
+            }
+        } else {
+            if idx != 0 {
+                write!(out, " ")?;
+            }
+            write!(out, "{}", poss.index)?;
+        }
+    }
+
+    writeln!(out)
+}
+
+/// Convert an error from writing the output, exiting quietly if the reader on
+/// the other end of a pipe has gone away (e.g. `dlx problem.txt | head`).
+fn output_error(err: io::Error) -> String {
+    if err.kind() == io::ErrorKind::BrokenPipe {
+        process::exit(0);
+    }
+
+    err.to_string()
+}
+
+fn run(args: &Args) -> Result<(), String> {
+    let input = read_input(args.input.as_deref()).map_err(|err| err.to_string())?;
+    let problem = TextProblem::parse(&input).map_err(|err| err.to_string())?;
+
+    let mut solver = Solver::with_heuristic(&problem, args.heuristic);
+    let max_solutions = args.max_solutions.unwrap_or(u64::MAX);
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+
+    let mut count = 0u64;
+    let mut sorted_solution = Vec::new();
+    let mut result = Ok(());
+    if max_solutions > 0 {
+        let _ = solver.solve_with(|solution| {
+            count += 1;
+
+            if !args.count {
+                sorted_solution.clear();
+                sorted_solution.extend_from_slice(solution.row_indices());
+                sorted_solution.sort_unstable();
+
+                result = write_solution(&mut out, &problem, &sorted_solution, args.names);
+                if result.is_err() {
+                    return ControlFlow::Break(());
+                }
+            }
+
+            if count < max_solutions {
+                ControlFlow::Continue(())
+            } else {
+                ControlFlow::Break(())
+            }
+        });
+    }
+    result.map_err(output_error)?;
+
+    if args.count {
+        writeln!(out, "{count}").map_err(output_error)?;
+    }
+    out.flush().map_err(output_error)?;
+
+    if args.stats {
+        let stats = solver.stats();
+        eprintln!("solutions: {}", stats.solutions);
+        eprintln!("nodes: {}", stats.nodes);
+        eprintln!("dead ends: {}", stats.dead_ends);
+    }
+
+    Ok(())
+}
+
+fn main() {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("error: {err}\n\n{USAGE}");
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = run(&args) {
+        eprintln!("error: {err}");
+        process::exit(1);
+    }
+}
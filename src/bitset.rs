@@ -0,0 +1,487 @@
+//! Solver for exact cover problems with few constraints, where the constraints
+//! satisfied by each possibility are stored as a fixed-width bitmask.
+//!
+//! Instead of unlinking nodes, covering the constraints of a possibility is a
+//! bitwise OR into the set of covered constraints, and removing the
+//! possibilities that conflict with it is a bitwise AND over the set of
+//! remaining possibilities. Undoing a step restores both sets from a copy, so
+//! there is no per-node bookkeeping. For problems with at most a few hundred
+//! constraints this is usually much faster than the linked [`Grid`].
+//!
+//! [`Grid`]: crate::grid::Grid
+
+use crate::{solver::SolutionContext, ColumnHeuristic, ExactCover, PartialSolution, SolverStats};
+use alloc::{vec, vec::Vec};
+use core::ops::ControlFlow;
+
+/// A set of constraints, with bit `i % 64` of word `i / 64` set for the
+/// constraint at index `i`.
+type Mask<const WORDS: usize> = [u64; WORDS];
+
+/// Solver that iteratively returns solutions to exact cover problems with at
+/// most `64 * WORDS` constraints.
+///
+/// The solutions, and the order they are returned in, are exactly the same as
+/// for a [`Solver`](crate::Solver) using the same [`ColumnHeuristic`]. The
+/// statistics returned by [`BitsetSolver::stats`] also match.
+///
+/// # Example
+///
+/// ```
+/// use dancing_links::{bitset::BitsetSolver, latin_square::LatinSquare, ExactCover};
+///
+/// // A 4x4 latin square has 48 constraints, which fit into one word
+/// let latin = LatinSquare::new(4, []);
+/// let solver = BitsetSolver::<_, 1>::new(&latin).unwrap();
+///
+/// assert!(solver.eq(latin.solver()));
+/// ```
+#[derive(Debug, Clone)]
+pub struct BitsetSolver<'e, E: ExactCover, const WORDS: usize> {
+    problem: &'e E,
+
+    // The constraints satisfied by each possibility
+    masks: Vec<Mask<WORDS>>,
+    // The possibilities which satisfy constraint `c` are the set bits of
+    // `column_rows[c * row_words..(c + 1) * row_words]`
+    column_rows: Vec<u64>,
+    row_words: usize,
+    primary: Mask<WORDS>,
+
+    // Values used to track the state of solving
+    covered: Mask<WORDS>,
+    live_rows: Vec<u64>,
+    // The remaining possibilities before the row of each frame was selected, with
+    // `row_words` words per frame
+    saved_live_rows: Vec<u64>,
+    partial_solution: Vec<usize>,
+    stack: Vec<Frame<WORDS>>,
+    started: bool,
+
+    heuristic: ColumnHeuristic,
+    stats: SolverStats,
+}
+
+#[derive(Debug, Clone)]
+struct Frame<const WORDS: usize> {
+    column: usize,
+    // The next possibility to consider for the column
+    next_row: usize,
+    // The constraints covered before the row of this frame was selected
+    covered: Mask<WORDS>,
+    // True if the last entry of the partial solution belongs to this frame
+    selected: bool,
+}
+
+impl<'e, E, const WORDS: usize> BitsetSolver<'e, E, WORDS>
+where
+    E: ExactCover,
+{
+    /// Create a new `BitsetSolver` with the given instance of an exact cover
+    /// problem, or return `None` if it has more than `64 * WORDS`
+    /// constraints.
+    pub fn new(problem: &'e E) -> Option<Self> {
+        Self::with_heuristic(problem, ColumnHeuristic::default())
+    }
+
+    /// Create a new `BitsetSolver` with the given instance of an exact cover
+    /// problem, which will use the given heuristic to choose columns, or
+    /// return `None` if it has more than `64 * WORDS` constraints.
+    pub fn with_heuristic(problem: &'e E, heuristic: ColumnHeuristic) -> Option<Self> {
+        let possibilities = problem.possibilities();
+        let constraints = problem.constraints();
+        if constraints.len() > 64 * WORDS {
+            return None;
+        }
+
+        let row_words = possibilities.len().div_ceil(64);
+        let mut masks = vec![[0; WORDS]; possibilities.len()];
+        let mut column_rows = vec![0; constraints.len() * row_words];
+        let mut primary = [0; WORDS];
+
+        for (column, cons) in constraints.iter().enumerate() {
+            if !problem.is_optional(cons) {
+                set_bit(&mut primary, column);
+            }
+
+            for (row, poss) in possibilities.iter().enumerate() {
+                if problem.satisfies(poss, cons) {
+                    set_bit(&mut masks[row], column);
+                    set_bit(&mut column_rows[(column * row_words)..], row);
+                }
+            }
+        }
+
+        Some(Self {
+            problem,
+
+            masks,
+            column_rows,
+            row_words,
+            primary,
+
+            covered: [0; WORDS],
+            live_rows: all_rows(possibilities.len()),
+            saved_live_rows: Vec::new(),
+            partial_solution: Vec::new(),
+            stack: Vec::new(),
+            started: false,
+
+            heuristic,
+            stats: SolverStats::default(),
+        })
+    }
+
+    /// Reset all solver state except for the stored possibilities and
+    /// constraints.
+    pub fn reset(&mut self) {
+        self.covered = [0; WORDS];
+        self.live_rows = all_rows(self.masks.len());
+        self.saved_live_rows.clear();
+        self.partial_solution.clear();
+        self.stack.clear();
+        self.started = false;
+        self.stats = SolverStats::default();
+    }
+
+    /// Return statistics about the search performed so far.
+    pub fn stats(&self) -> SolverStats {
+        self.stats
+    }
+
+    /// Return all possible solutions.
+    pub fn all_solutions(&mut self) -> Vec<Vec<&'e E::Possibility>> {
+        self.collect()
+    }
+
+    /// Compute up to the next solution, returning `None` if there are no more.
+    pub fn next_solution(&mut self) -> Option<Vec<&'e E::Possibility>> {
+        if self.advance() {
+            Some(
+                self.partial_solution
+                    .iter()
+                    .map(|row_index| &self.problem.possibilities()[*row_index])
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// Call `visit` with each remaining solution, until there are no more
+    /// solutions or `visit` returns [`ControlFlow::Break`].
+    ///
+    /// See [`Solver::solve_with`](crate::Solver::solve_with).
+    pub fn solve_with<F>(&mut self, mut visit: F) -> ControlFlow<()>
+    where
+        F: FnMut(&SolutionContext<'_, 'e, E>) -> ControlFlow<()>,
+    {
+        while self.advance() {
+            visit(&SolutionContext::new(self.problem, &self.partial_solution))?;
+        }
+
+        ControlFlow::Continue(())
+    }
+
+    fn column_rows(&self, column: usize) -> &[u64] {
+        &self.column_rows[(column * self.row_words)..((column + 1) * self.row_words)]
+    }
+
+    /// Return the number of remaining possibilities which satisfy the given
+    /// constraint.
+    fn column_size(&self, column: usize) -> u32 {
+        self.column_rows(column)
+            .iter()
+            .zip(&self.live_rows)
+            .map(|(rows, live)| (rows & live).count_ones())
+            .sum()
+    }
+
+    /// Choose the uncovered primary column to branch on next, the same way as
+    /// `ColumnHeuristic::choose_column` does for a `Grid`.
+    fn choose_column(&self) -> Option<usize> {
+        let mut uncovered = self.primary;
+        for (word, covered) in uncovered.iter_mut().zip(&self.covered) {
+            *word &= !covered;
+        }
+        let mut columns = bits(&uncovered);
+
+        match self.heuristic {
+            ColumnHeuristic::MinimumRemainingValues => {
+                let mut best: Option<(usize, u32)> = None;
+                for column in columns {
+                    let size = self.column_size(column);
+                    if best.map_or(true, |(_, best_size)| size < best_size) {
+                        best = Some((column, size));
+                        if size == 0 {
+                            break;
+                        }
+                    }
+                }
+
+                best.map(|(column, _)| column)
+            }
+            ColumnHeuristic::FirstUncovered => columns.next(),
+        }
+    }
+
+    /// Choose a column and push a frame to branch on it, returning false if
+    /// every primary constraint is covered.
+    fn push_frame(&mut self) -> bool {
+        let Some(column) = self.choose_column() else {
+            return false;
+        };
+
+        if self.column_size(column) == 0 {
+            self.stats.dead_ends += 1;
+        } else {
+            self.saved_live_rows.extend_from_slice(&self.live_rows);
+            self.stack.push(Frame {
+                column,
+                next_row: 0,
+                covered: self.covered,
+                selected: false,
+            });
+        }
+
+        true
+    }
+
+    /// Run the search until the next solution is found, returning `true` with
+    /// the solution left in `partial_solution`, or `false` if there are no more
+    /// solutions.
+    fn advance(&mut self) -> bool {
+        if !self.started {
+            self.started = true;
+            // Like `Solver`, a problem without primary constraints has no solutions
+            self.push_frame();
+        }
+
+        while !self.stack.is_empty() {
+            let saved_start = (self.stack.len() - 1) * self.row_words;
+            let frame = self.stack.last_mut().unwrap();
+            let saved_live_rows = &self.saved_live_rows[saved_start..];
+
+            if frame.selected {
+                frame.selected = false;
+                self.partial_solution.pop();
+                self.covered = frame.covered;
+                self.live_rows.copy_from_slice(saved_live_rows);
+            }
+
+            let column_rows =
+                &self.column_rows[(frame.column * self.row_words)..][..self.row_words];
+            let Some(row) = next_bit(column_rows, saved_live_rows, frame.next_row) else {
+                self.stack.pop();
+                self.saved_live_rows.truncate(saved_start);
+                continue;
+            };
+            frame.next_row = row + 1;
+            frame.selected = true;
+
+            self.partial_solution.push(row);
+            self.stats.nodes += 1;
+
+            let mask = self.masks[row];
+            for (covered, word) in self.covered.iter_mut().zip(&mask) {
+                *covered |= word;
+            }
+            for column in bits(&mask) {
+                let column_rows = &self.column_rows[(column * self.row_words)..];
+                for (live, rows) in self.live_rows.iter_mut().zip(column_rows) {
+                    *live &= !rows;
+                }
+            }
+
+            let remaining = |column: usize| {
+                let covered = self.covered[column / 64] & (1 << (column % 64)) != 0;
+                (!covered).then(|| self.column_size(column) as usize)
+            };
+            let partial =
+                PartialSolution::from_fn(self.problem, &self.partial_solution, &remaining);
+            if !self.problem.is_viable(&partial) {
+                self.stats.pruned += 1;
+                continue;
+            }
+
+            if !self.push_frame() {
+                self.stats.solutions += 1;
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+impl<'e, E, const WORDS: usize> Iterator for BitsetSolver<'e, E, WORDS>
+where
+    E: ExactCover,
+{
+    type Item = Vec<&'e E::Possibility>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_solution()
+    }
+}
+
+fn set_bit(words: &mut [u64], index: usize) {
+    words[index / 64] |= 1 << (index % 64);
+}
+
+fn all_rows(num_rows: usize) -> Vec<u64> {
+    let mut rows = vec![u64::MAX; num_rows.div_ceil(64)];
+    if num_rows % 64 != 0 {
+        if let Some(last) = rows.last_mut() {
+            *last = (1 << (num_rows % 64)) - 1;
+        }
+    }
+
+    rows
+}
+
+/// Return an iterator over the indices of the set bits, in increasing order.
+fn bits(words: &[u64]) -> impl Iterator<Item = usize> + '_ {
+    words.iter().enumerate().flat_map(|(idx, &word)| {
+        let mut word = word;
+
+        core::iter::from_fn(move || {
+            (word != 0).then(|| {
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+
+                idx * 64 + bit
+            })
+        })
+    })
+}
+
+/// Return the index of the first bit which is set in both `first` and
+/// `second`, starting from `start`.
+fn next_bit(first: &[u64], second: &[u64], start: usize) -> Option<usize> {
+    let mut idx = start / 64;
+    let mut mask = u64::MAX.checked_shl((start % 64) as u32).unwrap_or(0);
+
+    while idx < first.len() {
+        let word = first[idx] & second[idx] & mask;
+        if word != 0 {
+            return Some(idx * 64 + word.trailing_zeros() as usize);
+        }
+
+        idx += 1;
+        mask = u64::MAX;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        latin_square::LatinSquare,
+        polyomino::{PShape, Polyomino, ShapeTransform},
+        queens::NQueens,
+        sudoku::Sudoku,
+        text::TextProblem,
+        Solver,
+    };
+
+    /// Check that both solvers return the same solutions in the same order,
+    /// with the same statistics.
+    fn assert_same_as_grid<E: ExactCover, const WORDS: usize>(problem: &E) {
+        for heuristic in [
+            ColumnHeuristic::MinimumRemainingValues,
+            ColumnHeuristic::FirstUncovered,
+        ] {
+            let mut solver = Solver::with_heuristic(problem, heuristic);
+            let mut bitset = BitsetSolver::<_, WORDS>::with_heuristic(problem, heuristic).unwrap();
+
+            let mut expected = Vec::new();
+            let _ = solver.solve_with(|solution| {
+                expected.push(solution.row_indices().to_vec());
+                ControlFlow::Continue(())
+            });
+            let mut solutions = Vec::new();
+            let _ = bitset.solve_with(|solution| {
+                solutions.push(solution.row_indices().to_vec());
+                ControlFlow::Continue(())
+            });
+            assert_eq!(solutions, expected, "{heuristic:?}");
+            assert_eq!(bitset.stats(), solver.stats(), "{heuristic:?}");
+        }
+    }
+
+    #[test]
+    fn same_solutions_as_grid() {
+        assert_same_as_grid::<_, 1>(&LatinSquare::new(4, []));
+        assert_same_as_grid::<_, 1>(&NQueens::new(6, []));
+        assert_same_as_grid::<_, 1>(&Sudoku::new(2, []));
+        assert_same_as_grid::<_, 2>(&NQueens::new(8, []));
+        assert_same_as_grid::<_, 4>(&LatinSquare::new(4, []));
+        assert_same_as_grid::<_, 1>(
+            &TextProblem::parse("a b c | x y\na x\nb x\nc y\na b\nb c y\nc\na y\n").unwrap(),
+        );
+        assert_same_as_grid::<_, 1>(&TextProblem::parse("a b c\na b\nb c\n").unwrap());
+        assert_same_as_grid::<_, 1>(&TextProblem::parse("a | x\n").unwrap());
+        assert_same_as_grid::<_, 1>(&TextProblem::parse("| x\nx\n").unwrap());
+    }
+
+    #[test]
+    fn same_solutions_as_grid_polyomino() {
+        let shapes = vec![
+            PShape::from([[1, 1], [1, 0], [1, 0]]),
+            PShape::from([[0, 1], [1, 1], [1, 0]]),
+            PShape::from([[1, 1, 1, 1]]),
+        ];
+        let polyomino = Polyomino::new((3, 4), shapes, ShapeTransform::FullSymmetry);
+
+        assert_same_as_grid::<_, 1>(&polyomino);
+    }
+
+    #[test]
+    fn many_possibilities() {
+        // More than 64 possibilities spread the set of remaining possibilities across
+        // words
+        assert_same_as_grid::<_, 1>(&NQueens::new(10, []));
+    }
+
+    #[test]
+    fn too_many_constraints() {
+        // 324 constraints
+        let sudoku = Sudoku::new(3, []);
+
+        assert!(BitsetSolver::<_, 5>::new(&sudoku).is_none());
+        assert!(BitsetSolver::<_, 6>::new(&sudoku).is_some());
+    }
+
+    #[test]
+    fn resume_and_reset() {
+        let queens = NQueens::new(6, []);
+        let mut solver = BitsetSolver::<_, 1>::new(&queens).unwrap();
+
+        let mut count = 0;
+        let flow = solver.solve_with(|solution| {
+            assert_eq!(solution.depth(), 6);
+            count += 1;
+            ControlFlow::Break(())
+        });
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert_eq!(count, 1);
+        assert_eq!(solver.by_ref().count(), 3);
+
+        solver.reset();
+        assert_eq!(solver.all_solutions(), queens.solver().all_solutions());
+    }
+
+    #[test]
+    fn bit_helpers() {
+        assert!(bits(&[0b1010, 1 << 63, 0, 1]).eq([1, 3, 127, 192]));
+        assert_eq!(all_rows(3), vec![0b111]);
+        assert_eq!(all_rows(64), vec![u64::MAX]);
+        assert_eq!(all_rows(65), vec![u64::MAX, 1]);
+        assert_eq!(next_bit(&[0b1011, 1], &[u64::MAX, 1], 2), Some(3));
+        assert_eq!(next_bit(&[0b1011, 1], &[u64::MAX, 1], 4), Some(64));
+        assert_eq!(next_bit(&[0b1011, 1], &[u64::MAX, 0], 4), None);
+    }
+}
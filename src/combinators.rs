@@ -0,0 +1,672 @@
+//! Adapters which build a new exact cover problem out of existing ones,
+//! without re-implementing them.
+//!
+//! - [`Restrict`] forbids some of the possibilities of a problem.
+//! - [`MakeOptional`] makes some of the constraints of a problem optional.
+//! - [`WithExtraConstraints`] adds constraints to a problem.
+//! - [`Product`] combines two independent problems, so that a solution is a
+//!   solution to each of them side by side.
+//!
+//! The adapters take the wrapped problem by value, and since `&E` implements
+//! [`ExactCover`] whenever `E` does, they can also borrow it. They forward
+//! [`ExactCover::is_viable`] to the wrapped problems, with a
+//! [`PartialSolution`] which only contains the possibilities that belong to
+//! each of them.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{
+//!     combinators::{MakeOptional, Product, Restrict},
+//!     queens::{Constraint, NQueens},
+//!     ExactCover,
+//! };
+//!
+//! let queens = NQueens::new(6, []);
+//! assert_eq!(queens.solver().count(), 4);
+//!
+//! // Place three queens on the top half of the board, where every row needs
+//! // one queen but the columns may stay empty
+//! let top_half = MakeOptional::new(
+//!     Restrict::new(&queens, |poss| poss.row < 3),
+//!     |cons| match cons {
+//!         Constraint::Row { index } => *index >= 3,
+//!         _ => true,
+//!     },
+//! );
+//! assert!(top_half
+//!     .solver()
+//!     .all(|solution| solution.len() == 3 && solution.iter().all(|poss| poss.row < 3)));
+//!
+//! // Solve two boards side by side
+//! let product = Product::new(&queens, NQueens::new(5, []));
+//! assert_eq!(product.solver().count(), 4 * 10);
+//! ```
+
+use crate::{ExactCover, PartialSolution};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// A value which comes from one of two problems.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Either<L, R> {
+    /// A value from the first problem.
+    Left(L),
+    /// A value from the second problem.
+    Right(R),
+}
+
+impl<L, R> Either<L, R> {
+    /// Return the value from the first problem, if this is one.
+    pub fn left(&self) -> Option<&L> {
+        match self {
+            Either::Left(left) => Some(left),
+            Either::Right(_) => None,
+        }
+    }
+
+    /// Return the value from the second problem, if this is one.
+    pub fn right(&self) -> Option<&R> {
+        match self {
+            Either::Left(_) => None,
+            Either::Right(right) => Some(right),
+        }
+    }
+}
+
+/// A problem which only keeps the possibilities of another problem that
+/// match a predicate.
+///
+/// The other possibilities are still listed by
+/// [`possibilities`](ExactCover::possibilities), so that the indices of the
+/// possibilities don't change, but they don't satisfy any constraint and are
+/// never part of a solution.
+pub struct Restrict<E: ExactCover, F> {
+    inner: E,
+    keep: F,
+}
+
+impl<E, F> Restrict<E, F>
+where
+    E: ExactCover,
+    F: Fn(&E::Possibility) -> bool,
+{
+    /// Create a new problem which only keeps the possibilities of `inner` for
+    /// which `keep` returns true.
+    pub fn new(inner: E, keep: F) -> Self {
+        Self { inner, keep }
+    }
+
+    /// Return the wrapped problem.
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Return the wrapped problem, consuming this one.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E, F> ExactCover for Restrict<E, F>
+where
+    E: ExactCover,
+    F: Fn(&E::Possibility) -> bool,
+{
+    type Constraint = E::Constraint;
+    type Possibility = E::Possibility;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        (self.keep)(poss) && self.inner.satisfies(poss, cons)
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        self.inner.is_optional(cons)
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        self.inner.possibilities()
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        self.inner.constraints()
+    }
+
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+        self.inner.is_viable(&partial.part(&self.inner, 0, 0))
+    }
+}
+
+impl<E: ExactCover + fmt::Debug, F> fmt::Debug for Restrict<E, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Restrict")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A problem which makes the constraints of another problem that match a
+/// predicate optional.
+pub struct MakeOptional<E: ExactCover, F> {
+    inner: E,
+    optional: F,
+}
+
+impl<E, F> MakeOptional<E, F>
+where
+    E: ExactCover,
+    F: Fn(&E::Constraint) -> bool,
+{
+    /// Create a new problem where the constraints of `inner` for which
+    /// `optional` returns true are optional, along with the ones that were
+    /// already optional.
+    pub fn new(inner: E, optional: F) -> Self {
+        Self { inner, optional }
+    }
+
+    /// Return the wrapped problem.
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Return the wrapped problem, consuming this one.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E, F> ExactCover for MakeOptional<E, F>
+where
+    E: ExactCover,
+    F: Fn(&E::Constraint) -> bool,
+{
+    type Constraint = E::Constraint;
+    type Possibility = E::Possibility;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        self.inner.satisfies(poss, cons)
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        self.inner.is_optional(cons) || (self.optional)(cons)
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        self.inner.possibilities()
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        self.inner.constraints()
+    }
+
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+        self.inner.is_viable(&partial.part(&self.inner, 0, 0))
+    }
+}
+
+impl<E: ExactCover + fmt::Debug, F> fmt::Debug for MakeOptional<E, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MakeOptional")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A problem which adds constraints to another problem.
+///
+/// The constraints of the wrapped problem come first, as [`Either::Left`],
+/// followed by the extra constraints, as [`Either::Right`]. The extra
+/// constraints are primary; wrap the problem in [`MakeOptional`] to make some
+/// of them optional.
+pub struct WithExtraConstraints<E: ExactCover, C, F> {
+    inner: E,
+    constraints: Vec<Either<E::Constraint, C>>,
+    satisfies: F,
+}
+
+impl<E, C, F> WithExtraConstraints<E, C, F>
+where
+    E: ExactCover,
+    E::Constraint: Clone,
+    C: fmt::Debug,
+    F: Fn(&E::Possibility, &C) -> bool,
+{
+    /// Create a new problem with the constraints of `inner` and the given extra
+    /// constraints, where `satisfies` returns true if a possibility satisfies
+    /// one of the extra constraints.
+    ///
+    /// The constraints of `inner` are cloned, since they are listed together
+    /// with the extra ones.
+    pub fn new(inner: E, extra: impl IntoIterator<Item = C>, satisfies: F) -> Self {
+        let constraints = inner
+            .constraints()
+            .iter()
+            .cloned()
+            .map(Either::Left)
+            .chain(extra.into_iter().map(Either::Right))
+            .collect();
+
+        Self {
+            inner,
+            constraints,
+            satisfies,
+        }
+    }
+
+    /// Return the wrapped problem.
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    /// Return the wrapped problem, consuming this one.
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+impl<E, C, F> ExactCover for WithExtraConstraints<E, C, F>
+where
+    E: ExactCover,
+    E::Constraint: Clone,
+    C: fmt::Debug,
+    F: Fn(&E::Possibility, &C) -> bool,
+{
+    type Constraint = Either<E::Constraint, C>;
+    type Possibility = E::Possibility;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        match cons {
+            Either::Left(cons) => self.inner.satisfies(poss, cons),
+            Either::Right(cons) => (self.satisfies)(poss, cons),
+        }
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        match cons {
+            Either::Left(cons) => self.inner.is_optional(cons),
+            Either::Right(_) => false,
+        }
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        self.inner.possibilities()
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        &self.constraints
+    }
+
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+        self.inner.is_viable(&partial.part(&self.inner, 0, 0))
+    }
+}
+
+impl<E: ExactCover + fmt::Debug, C: fmt::Debug, F> fmt::Debug for WithExtraConstraints<E, C, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WithExtraConstraints")
+            .field("inner", &self.inner)
+            .field("constraints", &self.constraints)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The disjoint product of two problems, whose solutions are the pairs of a
+/// solution to each of them.
+///
+/// The possibilities and constraints of the first problem come first, as
+/// [`Either::Left`], followed by the ones of the second problem, as
+/// [`Either::Right`]. They are cloned, since they are listed together.
+#[derive(Debug)]
+pub struct Product<A: ExactCover, B: ExactCover> {
+    left: A,
+    right: B,
+    possibilities: Vec<Either<A::Possibility, B::Possibility>>,
+    constraints: Vec<Either<A::Constraint, B::Constraint>>,
+}
+
+impl<A, B> Product<A, B>
+where
+    A: ExactCover,
+    B: ExactCover,
+    A::Possibility: Clone,
+    A::Constraint: Clone,
+    B::Possibility: Clone,
+    B::Constraint: Clone,
+{
+    /// Create the product of the two problems.
+    pub fn new(left: A, right: B) -> Self {
+        let possibilities = left
+            .possibilities()
+            .iter()
+            .cloned()
+            .map(Either::Left)
+            .chain(right.possibilities().iter().cloned().map(Either::Right))
+            .collect();
+        let constraints = left
+            .constraints()
+            .iter()
+            .cloned()
+            .map(Either::Left)
+            .chain(right.constraints().iter().cloned().map(Either::Right))
+            .collect();
+
+        Self {
+            left,
+            right,
+            possibilities,
+            constraints,
+        }
+    }
+
+    /// Return the first problem.
+    pub fn left(&self) -> &A {
+        &self.left
+    }
+
+    /// Return the second problem.
+    pub fn right(&self) -> &B {
+        &self.right
+    }
+
+    /// Return both problems, consuming the product.
+    pub fn into_parts(self) -> (A, B) {
+        (self.left, self.right)
+    }
+}
+
+impl<A, B> ExactCover for Product<A, B>
+where
+    A: ExactCover,
+    B: ExactCover,
+{
+    type Constraint = Either<A::Constraint, B::Constraint>;
+    type Possibility = Either<A::Possibility, B::Possibility>;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        match (poss, cons) {
+            (Either::Left(poss), Either::Left(cons)) => self.left.satisfies(poss, cons),
+            (Either::Right(poss), Either::Right(cons)) => self.right.satisfies(poss, cons),
+            _ => false,
+        }
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        match cons {
+            Either::Left(cons) => self.left.is_optional(cons),
+            Either::Right(cons) => self.right.is_optional(cons),
+        }
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        &self.possibilities
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        &self.constraints
+    }
+
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+        // Only the last possibility is new, so only the problem it belongs to
+        // has anything new to check
+        match partial.row_indices().last() {
+            None => true,
+            Some(row_index) if row_index < self.left.possibilities().len() => {
+                self.left.is_viable(&partial.part(&self.left, 0, 0))
+            }
+            Some(_) => self.right.is_viable(&partial.part(
+                &self.right,
+                self.left.possibilities().len(),
+                self.left.constraints().len(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        queens::NQueens,
+        sudoku::{self, Sudoku},
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+        text::{self, TextProblem},
+        ColumnHeuristic, Solution, Solver,
+    };
+    use alloc::vec;
+    use core::cell::RefCell;
+
+    const SEEDS: u64 = if cfg!(miri) { 20 } else { 300 };
+
+    /// Return the solutions found by the solver, as sorted possibility
+    /// indices, in increasing order.
+    fn solutions<E: ExactCover>(problem: &E) -> Vec<Solution> {
+        let mut solver = problem.solver();
+        let mut solutions = Vec::new();
+        let _ = solver.solve_with(|solution| {
+            solutions.push(solution.to_solution());
+            core::ops::ControlFlow::Continue(())
+        });
+        solutions.sort();
+
+        solutions
+    }
+
+    #[test]
+    fn restrict_random_problems() {
+        for seed in 0..SEEDS {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let keep = |poss: &text::Possibility| (poss.index + seed as usize) % 3 != 0;
+
+            let expected: Vec<_> = reference_solutions(&problem)
+                .into_iter()
+                .filter(|solution| {
+                    solution
+                        .possibilities
+                        .iter()
+                        .all(|index| keep(&problem.possibilities[*index]))
+                })
+                .collect();
+
+            assert_eq!(
+                solutions(&Restrict::new(&problem, keep)),
+                expected,
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn make_optional_random_problems() {
+        for seed in 0..SEEDS {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let optional = |cons: &text::Constraint| cons.index % 4 == 1;
+
+            let mut expected = random_problem(seed, &RandomProblemConfig::random(seed));
+            for cons in &mut expected.constraints {
+                cons.optional |= optional(cons);
+            }
+
+            assert_eq!(
+                solutions(&MakeOptional::new(&problem, optional)),
+                reference_solutions(&expected),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn extra_diagonal_constraints() {
+        let sudoku = Sudoku::new(2, []);
+        let on_diagonal = |solution: &Solution| {
+            let mut values: Vec<_> = solution
+                .possibilities
+                .iter()
+                .map(|index| sudoku.possibilities[*index])
+                .filter(|poss| poss.row == poss.column)
+                .map(|poss| poss.value)
+                .collect();
+            values.sort();
+            values == [1, 2, 3, 4]
+        };
+        let expected: Vec<_> = solutions(&sudoku).into_iter().filter(on_diagonal).collect();
+        assert!(!expected.is_empty());
+
+        // Every value must appear once on the main diagonal
+        let diagonal =
+            WithExtraConstraints::new(&sudoku, 1..=4, |poss: &sudoku::Possibility, value| {
+                poss.row == poss.column && poss.value == *value
+            });
+        assert_eq!(diagonal.constraints().len(), sudoku.constraints.len() + 4);
+        assert_eq!(
+            diagonal.constraints()[0],
+            Either::Left(sudoku.constraints[0])
+        );
+        assert_eq!(solutions(&diagonal), expected);
+
+        // With optional extra constraints, each value appears at most once on the
+        // diagonal, which has as many cells as there are values
+        let optional = MakeOptional::new(&diagonal, |cons| cons.right().is_some());
+        assert_eq!(solutions(&optional), expected);
+    }
+
+    #[test]
+    fn product_of_problems() {
+        let queens = NQueens::new(5, []);
+        let problem = TextProblem::parse("a b c\na b\nc\na\nb c\n").unwrap();
+        let product = Product::new(&queens, &problem);
+        assert_eq!(
+            product.possibilities().len(),
+            queens.possibilities.len() + problem.possibilities.len()
+        );
+
+        let mut queens_solutions: Vec<_> = queens
+            .solver()
+            .map(|mut solution| {
+                solution.sort();
+                solution
+            })
+            .collect();
+        queens_solutions.sort();
+
+        let mut pairs: Vec<_> = product
+            .solver()
+            .map(|solution| {
+                let mut left: Vec<_> = solution.iter().filter_map(|poss| poss.left()).collect();
+                left.sort();
+                let mut right: Vec<_> = solution
+                    .iter()
+                    .filter_map(|poss| poss.right())
+                    .map(|poss| poss.index)
+                    .collect();
+                right.sort();
+                (left, right)
+            })
+            .collect();
+        pairs.sort();
+
+        let expected: Vec<_> = queens_solutions
+            .iter()
+            .flat_map(|left| [(left.clone(), vec![0, 1]), (left.clone(), vec![2, 3])])
+            .collect();
+        assert_eq!(pairs.len(), 10 * 2);
+        assert_eq!(pairs, expected);
+    }
+
+    /// A problem which rejects every partial solution with both of the given
+    /// possibilities, if any, and records the partial solutions it is asked
+    /// about.
+    struct RejectPair {
+        problem: TextProblem,
+        pair: Option<[usize; 2]>,
+        calls: RefCell<Vec<Vec<usize>>>,
+    }
+
+    impl RejectPair {
+        fn new(input: &str, pair: Option<[usize; 2]>) -> Self {
+            Self {
+                problem: TextProblem::parse(input).unwrap(),
+                pair,
+                calls: RefCell::new(Vec::new()),
+            }
+        }
+    }
+
+    impl ExactCover for RejectPair {
+        type Constraint = text::Constraint;
+        type Possibility = text::Possibility;
+
+        fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+            self.problem.satisfies(poss, cons)
+        }
+
+        fn is_optional(&self, cons: &Self::Constraint) -> bool {
+            self.problem.is_optional(cons)
+        }
+
+        fn possibilities(&self) -> &[Self::Possibility] {
+            self.problem.possibilities()
+        }
+
+        fn constraints(&self) -> &[Self::Constraint] {
+            self.problem.constraints()
+        }
+
+        fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+            self.calls
+                .borrow_mut()
+                .push(partial.row_indices().collect());
+
+            !self.pair.is_some_and(|pair| {
+                pair.iter()
+                    .all(|index| partial.row_indices().any(|row_index| row_index == *index))
+            })
+        }
+    }
+
+    #[test]
+    fn product_asks_the_side_of_each_row() {
+        let left = RejectPair::new("a b\na\nb\n", Some([0, 1]));
+        let right = RejectPair::new("c d\nc\nd\n", None);
+        let product = Product::new(&left, &right);
+
+        // Force a row of the left problem, then both rows of the right one. Each
+        // problem is only asked when one of its own rows is added.
+        let solver =
+            Solver::with_forced_rows(&product, ColumnHeuristic::default(), &[0, 2, 3]).unwrap();
+        assert_eq!(*left.calls.borrow(), [vec![0]]);
+        assert_eq!(*right.calls.borrow(), [vec![0], vec![0, 1]]);
+
+        // The search adds the other row of the left problem, which rejects it
+        assert_eq!(solver.count(), 0);
+        assert_eq!(*left.calls.borrow(), [vec![0], vec![0, 1]]);
+        assert_eq!(*right.calls.borrow(), [vec![0], vec![0, 1]]);
+
+        assert_eq!(product.solver().count(), 0);
+    }
+
+    #[test]
+    fn product_random_problems() {
+        for seed in 0..(SEEDS / 3) {
+            let left = random_problem(seed, &RandomProblemConfig::random(seed));
+            let right = random_problem(seed + SEEDS, &RandomProblemConfig::random(seed + SEEDS));
+            let product = Product::new(&left, &right);
+
+            let num_left = reference_solutions(&left).len();
+            let num_right = reference_solutions(&right).len();
+            let has_primary =
+                |problem: &TextProblem| problem.constraints.iter().any(|cons| !cons.optional);
+            // A side without primary constraints has no solutions on its own, but
+            // in the product it is satisfied by choosing nothing from it
+            let expected = match (has_primary(&left), has_primary(&right)) {
+                (true, true) => num_left * num_right,
+                (true, false) => num_left,
+                (false, true) => num_right,
+                (false, false) => 0,
+            };
+
+            assert_eq!(solutions(&product).len(), expected, "seed {seed}");
+            assert_eq!(reference_solutions(&product).len(), expected, "seed {seed}");
+        }
+    }
+}
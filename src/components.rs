@@ -0,0 +1,429 @@
+//! Splitting an exact cover problem into independent parts, which are solved
+//! separately.
+//!
+//! Two constraints are connected if some possibility satisfies both of them.
+//! When the constraints fall into several groups that are not connected to
+//! each other, like the separate regions of a board, the solutions of the
+//! problem are all the combinations of one solution from each group. A
+//! [`Solver`] explores every combination in its search tree, while a
+//! [`ComponentSolver`] solves each group on its own, and then combines their
+//! solutions.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{components::ComponentSolver, text::TextProblem, ExactCover};
+//!
+//! // `a b` and `c d` are never covered by the same option
+//! let problem = TextProblem::parse("a b c d\na b\na\nb\nc d\nc\nd\n").unwrap();
+//!
+//! let solver = ComponentSolver::new(&problem);
+//! assert_eq!(solver.components().len(), 2);
+//! assert_eq!(solver.num_solutions(), 4);
+//! assert_eq!(solver.count(), problem.solver().count());
+//! ```
+
+use crate::{grid::Grid, ColumnHeuristic, ExactCover, Solver};
+use alloc::vec::Vec;
+
+/// A group of constraints which are connected to each other, and the
+/// possibilities which satisfy them.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Component {
+    /// The indices (into [`ExactCover::possibilities`]) of the possibilities
+    /// in this component, in increasing order.
+    pub possibilities: Vec<usize>,
+    /// The indices (into [`ExactCover::constraints`]) of the constraints in
+    /// this component, in increasing order.
+    pub constraints: Vec<usize>,
+}
+
+/// Split the given problem into its connected components, in order of their
+/// first constraint.
+///
+/// Possibilities which only satisfy optional constraints are never part of a
+/// solution, so they are left out, along with the optional constraints which
+/// are only satisfied by them. Every primary constraint belongs to a
+/// component, even if no possibility satisfies it.
+pub fn components<E: ExactCover>(problem: &E) -> Vec<Component> {
+    connected_components(&Solver::populate_grid(problem))
+}
+
+fn connected_components(grid: &Grid) -> Vec<Component> {
+    // Union-find over the columns, indexed like the grid
+    let mut parent: Vec<_> = (0..=grid.num_columns()).collect();
+    fn find(parent: &mut [usize], mut column: usize) -> usize {
+        while parent[column] != column {
+            parent[column] = parent[parent[column]];
+            column = parent[column];
+        }
+        column
+    }
+
+    let rows: Vec<_> = (1..=grid.max_row())
+        .filter(|row| {
+            grid.row_nodes(*row)
+                .any(|node| !grid.is_column_optional(grid.node_column(node)))
+        })
+        .collect();
+    let mut used = alloc::vec![false; grid.num_columns() + 1];
+    for row in &rows {
+        let mut columns = grid.row_nodes(*row).map(|node| grid.node_column(node));
+        let first = columns.next().unwrap();
+        used[first] = true;
+        for column in columns {
+            used[column] = true;
+            let (a, b) = (find(&mut parent, first), find(&mut parent, column));
+            // Keep the smallest column as the root, so components are ordered by it
+            parent[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut components: Vec<Component> = Vec::new();
+    // The position in `components` of each root column
+    let mut positions = alloc::vec![usize::MAX; grid.num_columns() + 1];
+    let columns = (1..=grid.num_columns())
+        .filter(|column| used[*column] || !grid.is_column_optional(*column));
+    for column in columns {
+        let root = find(&mut parent, column);
+        if positions[root] == usize::MAX {
+            positions[root] = components.len();
+            components.push(Component {
+                possibilities: Vec::new(),
+                constraints: Vec::new(),
+            });
+        }
+        components[positions[root]].constraints.push(column - 1);
+    }
+    for row in rows {
+        let column = grid.node_column(grid.row_nodes(row).next().unwrap());
+        let root = find(&mut parent, column);
+        components[positions[root]].possibilities.push(row - 1);
+    }
+
+    components
+}
+
+/// Solver that returns the solutions of an exact cover problem by solving
+/// each of its connected components separately.
+///
+/// Each component is searched by its own [`Solver`], and the solutions are
+/// the combinations of one solution of every component, where the last
+/// component changes fastest. The solutions of each component are kept as
+/// they are found, so the memory used grows with the sum of the number of
+/// solutions of the components, not with their product.
+///
+/// [`ExactCover::is_viable`] is called with the partial solutions of each
+/// component on their own, where the constraints of the other components
+/// count as already satisfied. This is only correct if the hook never rejects
+/// a combination of partial solutions that it accepts separately.
+#[derive(Debug)]
+pub struct ComponentSolver<'e, E: ExactCover> {
+    problem: &'e E,
+    components: Vec<Component>,
+    // The search of each component
+    parts: Vec<Part<'e, E>>,
+    // The position in `solutions` of the current solution of each part, or `None`
+    // before the first solution has been returned
+    positions: Option<Vec<usize>>,
+    done: bool,
+}
+
+#[derive(Debug)]
+struct Part<'e, E: ExactCover> {
+    solver: Solver<'e, E>,
+    solutions: Vec<Vec<usize>>,
+    exhausted: bool,
+}
+
+impl<E: ExactCover> Part<'_, E> {
+    /// Return true if the part has a solution at the given position, finding
+    /// more solutions if needed.
+    fn has_solution(&mut self, position: usize) -> bool {
+        while self.solutions.len() <= position && !self.exhausted {
+            let mut found = None;
+            let _ = self.solver.solve_with(|solution| {
+                found = Some(solution.row_indices().to_vec());
+                core::ops::ControlFlow::Break(())
+            });
+
+            match found {
+                Some(solution) => self.solutions.push(solution),
+                None => self.exhausted = true,
+            }
+        }
+
+        position < self.solutions.len()
+    }
+}
+
+impl<'e, E> ComponentSolver<'e, E>
+where
+    E: ExactCover,
+{
+    /// Create a new `ComponentSolver` with the given instance of an exact
+    /// cover problem.
+    pub fn new(problem: &'e E) -> Self {
+        Self::with_heuristic(problem, ColumnHeuristic::default())
+    }
+
+    /// Create a new `ComponentSolver` with the given instance of an exact
+    /// cover problem, whose components will use the given heuristic to choose
+    /// columns.
+    pub fn with_heuristic(problem: &'e E, heuristic: ColumnHeuristic) -> Self {
+        let grid = Solver::populate_grid(problem);
+        let components = connected_components(&grid);
+
+        // Every component has a primary constraint, since optional constraints are
+        // only included when they share a possibility with one
+        let parts: Vec<_> = components
+            .iter()
+            .map(|component| {
+                // Keep the rows and columns numbered like the full grid, so that the
+                // solver reports the right indices, and cover the other columns so that
+                // they count as satisfied
+                let grid = &grid;
+                let coordinates = component.possibilities.iter().flat_map(move |row_index| {
+                    grid.row_nodes(row_index + 1)
+                        .map(move |node| (row_index + 1, grid.node_column(node)))
+                });
+                let optional_columns =
+                    (1..=grid.num_columns()).filter(|column| grid.is_column_optional(*column));
+                let mut component_grid =
+                    Grid::with_optional_columns(grid.num_columns(), optional_columns, coordinates);
+
+                let mut own = component.constraints.iter().peekable();
+                for column in 1..=grid.num_columns() {
+                    if own.next_if(|cons| **cons + 1 == column).is_none() {
+                        component_grid.cover_column(column);
+                    }
+                }
+
+                Part {
+                    solver: Solver::with_grid(problem, component_grid, heuristic),
+                    solutions: Vec::new(),
+                    exhausted: false,
+                }
+            })
+            .collect();
+
+        Self {
+            problem,
+            components,
+            done: parts.is_empty(),
+            parts,
+            positions: None,
+        }
+    }
+
+    /// Return the connected components of the problem, as returned by
+    /// [`components`].
+    pub fn components(&self) -> &[Component] {
+        &self.components
+    }
+
+    /// Return the total number of solutions of the problem, which is the
+    /// product of the number of solutions of each component, saturating at
+    /// `u128::MAX`.
+    ///
+    /// This searches each component from the start, and doesn't change the
+    /// solutions returned by the iterator.
+    pub fn num_solutions(&self) -> u128 {
+        // Like `Solver`, a problem without primary constraints has no solutions
+        if self.parts.is_empty() {
+            return 0;
+        }
+
+        self.parts
+            .iter()
+            .map(|part| {
+                let mut solver = part.solver.clone();
+                solver.reset();
+                solver.count() as u128
+            })
+            .fold(1, u128::saturating_mul)
+    }
+
+    /// Move to the next combination of component solutions, returning false
+    /// if there are no more.
+    fn advance(&mut self) -> bool {
+        if self.done {
+            return false;
+        }
+
+        let Some(positions) = &mut self.positions else {
+            let found = self.parts.iter_mut().all(|part| part.has_solution(0));
+            self.positions = Some(alloc::vec![0; self.parts.len()]);
+            self.done = !found;
+            return found;
+        };
+
+        // Count up like an odometer, where the last part changes fastest
+        for (part, position) in self.parts.iter_mut().zip(positions.iter_mut()).rev() {
+            if part.has_solution(*position + 1) {
+                *position += 1;
+                return true;
+            }
+            *position = 0;
+        }
+
+        self.done = true;
+        false
+    }
+}
+
+impl<'e, E> Iterator for ComponentSolver<'e, E>
+where
+    E: ExactCover,
+{
+    type Item = Vec<&'e E::Possibility>;
+
+    /// Return the next solution, with the possibilities of each component in
+    /// the order of the components.
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.advance() {
+            return None;
+        }
+
+        let possibilities = self.problem.possibilities();
+        let positions = self.positions.as_ref().unwrap();
+
+        Some(
+            self.parts
+                .iter()
+                .zip(positions)
+                .flat_map(|(part, position)| &part.solutions[*position])
+                .map(|row_index| &possibilities[*row_index])
+                .collect(),
+        )
+    }
+}
+
+impl<E: ExactCover> core::iter::FusedIterator for ComponentSolver<'_, E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        combinators::Product,
+        queens::NQueens,
+        sudoku::Sudoku,
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+        text::TextProblem,
+        Solution,
+    };
+    use alloc::vec;
+
+    #[test]
+    fn split_text_problem() {
+        let problem = TextProblem::parse("a b c d | x y\na b\nc\nd x\nb\ny\n").unwrap();
+
+        assert_eq!(
+            components(&problem),
+            [
+                Component {
+                    possibilities: vec![0, 3],
+                    constraints: vec![0, 1],
+                },
+                Component {
+                    possibilities: vec![1],
+                    constraints: vec![2],
+                },
+                Component {
+                    possibilities: vec![2],
+                    constraints: vec![3, 4],
+                },
+            ]
+        );
+        assert_eq!(ComponentSolver::new(&problem).num_solutions(), 1);
+
+        let problem = TextProblem::parse("| x\nx\n").unwrap();
+        assert!(components(&problem).is_empty());
+        assert_eq!(ComponentSolver::new(&problem).num_solutions(), 0);
+        assert_eq!(ComponentSolver::new(&problem).count(), 0);
+    }
+
+    #[test]
+    fn product_of_sudokus() {
+        let sudoku = Sudoku::new(2, []);
+        let product = Product::new(Product::new(&sudoku, &sudoku), &sudoku);
+
+        let mut solver = ComponentSolver::new(&product);
+        assert_eq!(solver.components().len(), 3);
+        assert_eq!(solver.num_solutions(), 288 * 288 * 288);
+
+        // The first solutions only differ in the last component
+        let first: Vec<_> = solver.by_ref().take(3).collect();
+        assert!(first.iter().all(|solution| solution.len() == 3 * 16));
+        assert_eq!(first[0][..32], first[1][..32]);
+        assert_eq!(first[1][..32], first[2][..32]);
+        assert_ne!(first[0][32..], first[1][32..]);
+    }
+
+    #[test]
+    fn product_of_queens() {
+        let product = Product::new(NQueens::new(5, []), NQueens::new(6, []));
+        let solver = ComponentSolver::new(&product);
+        assert_eq!(solver.num_solutions(), 10 * 4);
+
+        let mut solutions: Vec<_> = solver
+            .map(|mut solution| {
+                solution.sort();
+                solution
+            })
+            .collect();
+        solutions.sort();
+        let mut expected: Vec<_> = product
+            .solver()
+            .map(|mut solution| {
+                solution.sort();
+                solution
+            })
+            .collect();
+        expected.sort();
+        assert_eq!(solutions, expected);
+    }
+
+    #[test]
+    fn random_problems() {
+        for seed in 0..if cfg!(miri) { 20 } else { 300 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let expected = reference_solutions(&problem);
+
+            // Every primary constraint and every possibility with a primary constraint
+            // is in exactly one component
+            let components = components(&problem);
+            for cons in problem.constraints.iter().filter(|cons| !cons.optional) {
+                let count = components
+                    .iter()
+                    .filter(|component| component.constraints.contains(&cons.index))
+                    .count();
+                assert_eq!(count, 1, "seed {seed}");
+            }
+            for poss in &problem.possibilities {
+                let is_primary = poss
+                    .items
+                    .iter()
+                    .any(|item| !problem.constraints[*item].optional);
+                let count = components
+                    .iter()
+                    .filter(|component| component.possibilities.contains(&poss.index))
+                    .count();
+                assert_eq!(count, usize::from(is_primary), "seed {seed}");
+            }
+
+            let solver = ComponentSolver::new(&problem);
+            assert_eq!(
+                solver.num_solutions(),
+                expected.len() as u128,
+                "seed {seed}"
+            );
+            let mut solutions: Vec<_> = solver
+                .map(|solution| Solution::new(solution.iter().map(|poss| poss.index)))
+                .collect();
+            solutions.sort();
+            assert_eq!(solutions, expected, "seed {seed}");
+        }
+    }
+}
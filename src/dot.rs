@@ -0,0 +1,536 @@
+//! Rendering of grids and search trees in the [DOT](https://graphviz.org/doc/info/lang.html)
+//! language used by Graphviz, for teaching and debugging.
+//!
+//! The output is only readable for small instances, with at most a few dozen
+//! columns and rows.
+
+use crate::{
+    grid::{Grid, Node},
+    ColumnHeuristic, ExactCover, Solver, SolverObserver,
+};
+use alloc::{collections::BTreeSet, format, string::String, vec::Vec};
+use core::fmt::{self, Write};
+
+/// Render the current state of a `Grid` as a DOT graph.
+///
+/// Column headers are drawn along the top with their current size, and
+/// optional columns have rounded corners. Covered columns and rows are drawn
+/// with dashed lines. The right links between uncovered column headers, the
+/// down links of every column, and the right links of every row are drawn as
+/// edges, wrapping around as in the grid itself.
+pub fn grid_to_dot(grid: &Grid) -> String {
+    let mut output = String::new();
+    write_grid(&mut output, grid).expect("writing to a String cannot fail");
+
+    output
+}
+
+fn write_grid(out: &mut impl Write, grid: &Grid) -> fmt::Result {
+    fn node_id(grid: &Grid, node: Node) -> String {
+        format!("n{}_{}", grid.node_row(node), grid.node_column(node))
+    }
+
+    let num_columns = grid.num_columns();
+    let visible: BTreeSet<_> = grid
+        .all_columns()
+        .flat_map(|column| grid.column_nodes(column))
+        .collect();
+
+    writeln!(out, "digraph grid {{")?;
+    writeln!(out, "  node [shape=box];")?;
+    writeln!(out, "  h0 [label=\"root\"];")?;
+    writeln!(out, "  h{} [label=\"optional root\"];", num_columns + 1)?;
+
+    for column in grid.all_columns() {
+        let mut style = Vec::new();
+        if grid.is_column_optional(column) {
+            style.push("rounded");
+        }
+        if grid.is_column_covered(column) {
+            style.push("dashed");
+        }
+
+        writeln!(
+            out,
+            "  h{column} [label=\"c{column}\\nsize {}\", style=\"{}\"];",
+            grid.column_size(column),
+            style.join(",")
+        )?;
+    }
+
+    write!(out, "  {{ rank=same; h0;")?;
+    for column in grid.all_columns() {
+        write!(out, " h{column};")?;
+    }
+    writeln!(out, " h{}; }}", num_columns + 1)?;
+
+    for row in 1..=grid.max_row() {
+        let nodes: Vec<_> = grid.row_nodes(row).collect();
+        if nodes.is_empty() {
+            continue;
+        }
+
+        write!(out, "  {{ rank=same;")?;
+        for node in &nodes {
+            write!(out, " {};", node_id(grid, *node))?;
+        }
+        writeln!(out, " }}")?;
+
+        for node in &nodes {
+            let style = if visible.contains(node) {
+                "solid"
+            } else {
+                "dashed"
+            };
+            writeln!(
+                out,
+                "  {} [label=\"r{row} c{}\", style={style}];",
+                node_id(grid, *node),
+                grid.node_column(*node)
+            )?;
+        }
+
+        if nodes.len() > 1 {
+            for (idx, node) in nodes.iter().enumerate() {
+                let next = nodes[(idx + 1) % nodes.len()];
+                writeln!(
+                    out,
+                    "  {} -> {} [color=gray, constraint=false];",
+                    node_id(grid, *node),
+                    node_id(grid, next)
+                )?;
+            }
+        }
+    }
+
+    let header_lists = [
+        (0, grid.uncovered_primary_columns().collect::<Vec<_>>()),
+        (
+            num_columns + 1,
+            grid.uncovered_columns()
+                .filter(|column| grid.is_column_optional(*column))
+                .collect(),
+        ),
+    ];
+    for (root, columns) in header_lists {
+        let mut prev = root;
+        for column in columns {
+            writeln!(out, "  h{prev} -> h{column} [color=blue];")?;
+            prev = column;
+        }
+        writeln!(out, "  h{prev} -> h{root} [color=blue];")?;
+    }
+
+    for column in grid.all_columns() {
+        let mut prev = format!("h{column}");
+        for node in grid.column_nodes(column) {
+            let id = node_id(grid, node);
+            writeln!(out, "  {prev} -> {id};")?;
+            prev = id;
+        }
+        writeln!(out, "  {prev} -> h{column};")?;
+    }
+
+    writeln!(out, "}}")
+}
+
+/// The outcome of a single node in a [`SearchTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SearchNodeKind {
+    /// The search chose the constraint at the given index, and tried each of
+    /// the possibilities that satisfy it as children of this node.
+    Branch {
+        /// The index of the chosen constraint.
+        constraint: usize,
+    },
+    /// The search chose the constraint at the given index, but no remaining
+    /// possibilities could satisfy it.
+    DeadEnd {
+        /// The index of the chosen constraint.
+        constraint: usize,
+    },
+    /// All primary constraints were satisfied, so the path to this node is a
+    /// solution.
+    Solution,
+    /// The partial solution at this node was rejected by
+    /// [`ExactCover::is_viable`], or by a nogood when
+    /// [learning](Solver::with_learning) is enabled.
+    Pruned,
+}
+
+/// A single node in a [`SearchTree`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SearchNode {
+    /// The index of the parent node, or `None` for the root.
+    pub parent: Option<usize>,
+    /// The index of the possibility which was added to the partial solution
+    /// to reach this node, or `None` for the root.
+    pub possibility: Option<usize>,
+    /// What the search did at this node.
+    pub kind: SearchNodeKind,
+}
+
+/// A record of the search tree explored by a `Solver` while enumerating every
+/// solution of a problem.
+///
+/// The tree is built from the events of the search, so it shows exactly what
+/// the `Solver` did, including the effects of forced possibilities and
+/// learning. Use [`SearchTree::record`] for a plain `Solver`, or observe any
+/// `Solver` with a [`SearchTreeRecorder`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct SearchTree {
+    nodes: Vec<SearchNode>,
+    truncated: bool,
+    // The node whose child was the first one left out, if any
+    truncated_at: Option<usize>,
+}
+
+impl SearchTree {
+    /// Explore the entire search tree of a problem, choosing columns with the
+    /// given heuristic, and stopping after recording `max_nodes` nodes.
+    pub fn record<E: ExactCover>(
+        problem: &E,
+        heuristic: ColumnHeuristic,
+        max_nodes: usize,
+    ) -> Self {
+        let mut recorder = SearchTreeRecorder::new(max_nodes);
+        let mut solver = Solver::with_heuristic(problem, heuristic).with_observer(&mut recorder);
+
+        // Every row tried adds a node below the root, so the search can stop
+        // once it has tried as many rows as there are nodes to record
+        let node_limit = u64::try_from(max_nodes).unwrap_or(u64::MAX);
+        while solver.advance_within(node_limit) == Some(true) {}
+
+        recorder.into_tree()
+    }
+
+    /// Return the nodes of the tree, in the order they were visited. The root
+    /// is the first node, if there are any.
+    pub fn nodes(&self) -> &[SearchNode] {
+        &self.nodes
+    }
+
+    /// Return true if the search stopped early because it reached the maximum
+    /// number of nodes.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Render the tree as a DOT graph, labelling constraints and possibilities
+    /// with their `Debug` representation.
+    ///
+    /// The problem must be the same one the tree was recorded from.
+    pub fn to_dot<E: ExactCover>(&self, problem: &E) -> String {
+        let mut output = String::new();
+        self.write_dot(&mut output, problem)
+            .expect("writing to a String cannot fail");
+
+        output
+    }
+
+    fn write_dot<E: ExactCover>(&self, out: &mut impl Write, problem: &E) -> fmt::Result {
+        fn escape(label: impl fmt::Debug) -> String {
+            format!("{label:?}")
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+        }
+
+        writeln!(out, "digraph search_tree {{")?;
+        writeln!(out, "  node [shape=box];")?;
+
+        for (id, node) in self.nodes.iter().enumerate() {
+            match node.kind {
+                SearchNodeKind::Branch { constraint } => writeln!(
+                    out,
+                    "  t{id} [label=\"{}\"];",
+                    escape(&problem.constraints()[constraint])
+                )?,
+                SearchNodeKind::DeadEnd { constraint } => writeln!(
+                    out,
+                    "  t{id} [label=\"dead end\\n{}\", color=red];",
+                    escape(&problem.constraints()[constraint])
+                )?,
+                SearchNodeKind::Solution => {
+                    writeln!(out, "  t{id} [label=\"solution\", color=green];")?
+                }
+                SearchNodeKind::Pruned => {
+                    writeln!(out, "  t{id} [label=\"pruned\", color=orange];")?
+                }
+            }
+
+            if let (Some(parent), Some(possibility)) = (node.parent, node.possibility) {
+                writeln!(
+                    out,
+                    "  t{parent} -> t{id} [label=\"{}\"];",
+                    escape(&problem.possibilities()[possibility])
+                )?;
+            }
+        }
+
+        if self.truncated {
+            writeln!(out, "  truncated [label=\"...\", shape=plaintext];")?;
+            if let Some(parent) = self.truncated_at {
+                writeln!(out, "  t{parent} -> truncated [style=dashed];")?;
+            }
+        }
+
+        writeln!(out, "}}")
+    }
+}
+
+/// A [`SolverObserver`] which records the search tree of the `Solver` it
+/// observes, as a [`SearchTree`].
+///
+/// The recorder stops recording after `max_nodes` nodes, but it can't stop
+/// the search itself.
+///
+/// # Example
+///
+/// ```
+/// use dancing_links::{dot::SearchTreeRecorder, queens::NQueens, ExactCover, Learning};
+///
+/// let queens = NQueens::new(4, []);
+/// let mut recorder = SearchTreeRecorder::new(100);
+/// let solver = queens
+///     .solver()
+///     .with_learning(Learning::default())
+///     .with_observer(&mut recorder);
+/// assert_eq!(solver.count(), 2);
+///
+/// let tree = recorder.into_tree();
+/// assert!(!tree.is_truncated());
+/// assert!(tree.to_dot(&queens).starts_with("digraph search_tree {"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct SearchTreeRecorder {
+    tree: SearchTree,
+    max_nodes: usize,
+    // The nodes from the root to the current node of the search
+    path: Vec<usize>,
+}
+
+impl SearchTreeRecorder {
+    /// Create a recorder which keeps at most `max_nodes` nodes.
+    pub fn new(max_nodes: usize) -> Self {
+        Self {
+            tree: SearchTree::default(),
+            max_nodes,
+            path: Vec::new(),
+        }
+    }
+
+    /// Return the tree recorded so far.
+    pub fn tree(&self) -> &SearchTree {
+        &self.tree
+    }
+
+    /// Return the recorded tree.
+    pub fn into_tree(self) -> SearchTree {
+        self.tree
+    }
+
+    /// Add a child of the current node, or the root if there is none, and
+    /// make it the current node. Returns false if the tree is full.
+    fn push_node(&mut self, possibility: Option<usize>, kind: SearchNodeKind) -> bool {
+        if self.tree.truncated {
+            return false;
+        }
+        if self.tree.nodes.len() >= self.max_nodes {
+            self.tree.truncated = true;
+            self.tree.truncated_at = self.path.last().copied();
+            return false;
+        }
+
+        self.path.push(self.tree.nodes.len());
+        self.tree.nodes.push(SearchNode {
+            parent: possibility.and(self.path.iter().rev().nth(1).copied()),
+            possibility,
+            kind,
+        });
+
+        true
+    }
+
+    /// Set the kind of the current node, creating the root if the search
+    /// hasn't added any node yet.
+    fn set_kind(&mut self, kind: SearchNodeKind) {
+        if self.tree.truncated {
+            return;
+        }
+        match self.path.last() {
+            Some(&current) => self.tree.nodes[current].kind = kind,
+            None => {
+                self.push_node(None, kind);
+            }
+        }
+    }
+}
+
+impl SolverObserver for SearchTreeRecorder {
+    fn on_choose_column(&mut self, constraint: usize, num_rows: usize) {
+        let _ = num_rows;
+        self.set_kind(SearchNodeKind::Branch { constraint });
+    }
+
+    fn on_try_row(&mut self, possibility: usize, depth: usize) {
+        let _ = depth;
+        // A node which is neither a branch, a dead end nor a solution when the
+        // search backtracks from it was pruned
+        self.push_node(Some(possibility), SearchNodeKind::Pruned);
+    }
+
+    fn on_backtrack(&mut self, possibility: usize, depth: usize) {
+        let _ = (possibility, depth);
+        if !self.tree.truncated {
+            self.path.pop();
+        }
+    }
+
+    fn on_dead_end(&mut self, constraint: usize) {
+        self.set_kind(SearchNodeKind::DeadEnd { constraint });
+    }
+
+    fn on_solution(&mut self, row_indices: &[usize]) {
+        let _ = row_indices;
+        self.set_kind(SearchNodeKind::Solution);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{latin_square::LatinSquare, text::TextProblem, Learning};
+
+    #[test]
+    fn render_grid() {
+        let mut grid = Grid::with_optional_columns(3, [3], vec![(1, 1), (1, 3), (2, 2), (3, 1)]);
+        let node = grid.column_nodes(1).next().unwrap();
+        grid.select_row(node);
+
+        let dot = grid_to_dot(&grid);
+
+        assert!(dot.starts_with("digraph grid {\n"));
+        assert!(dot.ends_with("}\n"));
+        // Covered columns and the rows they removed are dashed
+        assert!(dot.contains("  h1 [label=\"c1\\nsize 2\", style=\"dashed\"];\n"));
+        assert!(dot.contains("  h2 [label=\"c2\\nsize 1\", style=\"\"];\n"));
+        assert!(dot.contains("  h3 [label=\"c3\\nsize 0\", style=\"rounded,dashed\"];\n"));
+        assert!(dot.contains("  n3_1 [label=\"r3 c1\", style=solid];\n"));
+        assert!(dot.contains("  n1_3 [label=\"r1 c3\", style=dashed];\n"));
+        // Only the uncovered column is left in the header list
+        assert!(dot.contains("  h0 -> h2 [color=blue];\n  h2 -> h0 [color=blue];\n"));
+        assert!(dot.contains("  h4 -> h4 [color=blue];\n"));
+        // Rows and columns wrap around
+        assert!(dot.contains("  n1_1 -> n1_3 [color=gray, constraint=false];\n"));
+        assert!(dot.contains("  n1_3 -> n1_1 [color=gray, constraint=false];\n"));
+        assert!(dot.contains("  h1 -> n1_1;\n  n1_1 -> n3_1;\n  n3_1 -> h1;\n"));
+    }
+
+    #[test]
+    fn record_search_tree() {
+        let problem = TextProblem::parse("a b c\na b\nc\na\nb c\na c\n").unwrap();
+        let tree = SearchTree::record(&problem, ColumnHeuristic::default(), 100);
+
+        assert!(!tree.is_truncated());
+        assert_eq!(
+            tree.nodes(),
+            &[
+                SearchNode {
+                    parent: None,
+                    possibility: None,
+                    kind: SearchNodeKind::Branch { constraint: 1 }
+                },
+                SearchNode {
+                    parent: Some(0),
+                    possibility: Some(0),
+                    kind: SearchNodeKind::Branch { constraint: 2 }
+                },
+                SearchNode {
+                    parent: Some(1),
+                    possibility: Some(1),
+                    kind: SearchNodeKind::Solution
+                },
+                SearchNode {
+                    parent: Some(0),
+                    possibility: Some(3),
+                    kind: SearchNodeKind::Branch { constraint: 0 }
+                },
+                SearchNode {
+                    parent: Some(3),
+                    possibility: Some(2),
+                    kind: SearchNodeKind::Solution
+                },
+            ]
+        );
+
+        let dot = tree.to_dot(&problem);
+        assert!(dot.contains(
+            "  t0 [label=\"Constraint { index: 1, name: \\\"b\\\", optional: false }\"];\n"
+        ));
+        assert!(dot.contains("  t0 -> t1 [label=\"Possibility { index: 0, items: [0, 1] }\"];\n"));
+        assert!(dot.contains("  t2 [label=\"solution\", color=green];\n"));
+    }
+
+    #[test]
+    fn record_dead_ends_and_truncation() {
+        let problem = TextProblem::parse("a b c\na b\nb c\n").unwrap();
+
+        let tree = SearchTree::record(&problem, ColumnHeuristic::default(), 100);
+        assert_eq!(
+            tree.nodes()
+                .iter()
+                .map(|node| node.kind)
+                .collect::<Vec<_>>(),
+            vec![
+                SearchNodeKind::Branch { constraint: 0 },
+                SearchNodeKind::DeadEnd { constraint: 2 }
+            ]
+        );
+        assert!(tree
+            .to_dot(&problem)
+            .contains("  t1 [label=\"dead end\\nConstraint { index: 2, name: \\\"c\\\", optional: false }\", color=red];\n"));
+
+        let tree = SearchTree::record(&problem, ColumnHeuristic::default(), 1);
+        assert_eq!(tree.nodes().len(), 1);
+        assert!(tree.is_truncated());
+        let dot = tree.to_dot(&problem);
+        assert!(dot.contains("  truncated [label=\"...\", shape=plaintext];\n"));
+        // The cut off part of the tree hangs from the root
+        assert!(dot.contains("  t0 -> truncated [style=dashed];\n"));
+    }
+
+    #[test]
+    fn recorder_follows_solver() {
+        let latin = LatinSquare::new(4, []);
+        let forced = [0, 5];
+
+        let mut recorder = SearchTreeRecorder::new(usize::MAX);
+        let mut solver = Solver::with_forced_rows(&latin, ColumnHeuristic::default(), &forced)
+            .unwrap()
+            .with_learning(Learning::default())
+            .with_observer(&mut recorder);
+        solver.by_ref().for_each(drop);
+        let stats = solver.stats();
+
+        let tree = recorder.into_tree();
+        let count = |kind: fn(&SearchNodeKind) -> bool| {
+            tree.nodes().iter().filter(|node| kind(&node.kind)).count() as u64
+        };
+        // Every row tried by the solver is a node below the root
+        assert_eq!(tree.nodes().len() as u64, stats.nodes + 1);
+        assert_eq!(tree.nodes()[0].parent, None);
+        assert_eq!(
+            count(|kind| *kind == SearchNodeKind::Solution),
+            stats.solutions
+        );
+        assert_eq!(count(|kind| *kind == SearchNodeKind::Pruned), stats.pruned);
+        assert_eq!(
+            count(|kind| matches!(kind, SearchNodeKind::DeadEnd { .. })),
+            stats.dead_ends
+        );
+        // The forced possibilities are above the root, so they're never tried
+        assert!(tree.nodes().iter().all(|node| node
+            .possibility
+            .map_or(true, |poss| !forced.contains(&poss))));
+    }
+}
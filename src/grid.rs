@@ -1,26 +1,188 @@
-#![allow(clippy::not_unsafe_ptr_arg_deref)]
-
 //! Dancing links `Grid` implementation for use in the `Solver`.
 
-mod base_node;
-
-use base_node::BaseNode;
-use core::{iter::once, ptr};
-use std::collections::VecDeque;
+use alloc::{boxed::Box, vec, vec::Vec};
+use core::fmt;
 
 /// Dancing links grid, support efficient removal of rows and columns.
-#[derive(Debug)]
+///
+/// The links between nodes are stored as indices into arrays, following the
+/// layout of Knuth's `DLX` programs. Every column has a header node, and the
+/// nodes of each row are stored next to each other, so only the vertical links
+/// need to be stored for the non-header nodes.
+///
+/// The links are stored as 32-bit integers whenever the grid is small enough,
+/// which halves the memory used per node on 64-bit targets. See
+/// [`Grid::with_index_width`] to choose the width explicitly.
+///
+/// Columns are either primary or optional. The uncovered columns of each kind
+/// are kept in separate lists, so that the solver only has to look at the
+/// primary columns when choosing a column to cover.
+///
+/// Cloning a `Grid` produces an independent copy, including which columns and
+/// rows are currently covered.
+#[derive(Debug, Clone)]
 pub struct Grid {
-    // This node only left-right neighbors, no children
-    root: *mut Column,
+    // Indexed by column. Index 0 is the root of the list of primary columns, and
+    // index `num_columns + 1` is the root of the list of optional columns.
+    headers: Vec<Header>,
+
+    // Indexed by node. The first `num_columns + 2` nodes are the column headers (and
+    // roots), followed by the nodes of every row in order.
+    nodes: NodeLinks,
 
-    arena: bumpalo::Bump,
-    columns: Vec<*mut Column>,
+    // The nodes of row `r` are `row_start[r - 1]..row_start[r]`.
+    row_start: Vec<usize>,
+
+    // Every cover operation that has not been undone yet, in the order they
+    // were performed, with the number of operations performed before it.
+    trail: Vec<(Operation, u64)>,
+    // The number of operations ever pushed to the trail, which identifies each
+    // entry so that snapshots can tell if the entries before them changed
+    pushed: u64,
 
     num_columns: usize,
-    max_row: usize,
 }
 
+/// The integer type used to store the links between the nodes of a `Grid`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexWidth {
+    /// 32-bit indices, which limit the grid to around 4 billion nodes and rows.
+    U32,
+    /// Pointer-sized indices.
+    Usize,
+}
+
+impl IndexWidth {
+    /// Return the narrowest width that can index every node and row of a grid.
+    fn narrowest(num_nodes: usize, max_row: usize) -> Self {
+        if num_nodes <= <u32 as LinkIndex>::MAX && max_row <= <u32 as LinkIndex>::MAX {
+            IndexWidth::U32
+        } else {
+            IndexWidth::Usize
+        }
+    }
+}
+
+trait LinkIndex: Copy + fmt::Debug {
+    // The largest `usize` value that can be converted to this type
+    const MAX: usize;
+
+    fn from_usize(value: usize) -> Self;
+
+    fn to_usize(self) -> usize;
+}
+
+impl LinkIndex for u32 {
+    const MAX: usize = if usize::BITS > u32::BITS {
+        u32::MAX as usize
+    } else {
+        usize::MAX
+    };
+
+    #[inline]
+    fn from_usize(value: usize) -> Self {
+        debug_assert!(value <= <Self as LinkIndex>::MAX);
+
+        value as u32
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+}
+
+impl LinkIndex for usize {
+    const MAX: usize = usize::MAX;
+
+    #[inline]
+    fn from_usize(value: usize) -> Self {
+        value
+    }
+
+    #[inline]
+    fn to_usize(self) -> usize {
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+enum NodeLinks {
+    U32(Vec<Links<u32>>),
+    Usize(Vec<Links<usize>>),
+}
+
+// Evaluate the same expression for either width of links, with `$links` bound to
+// the vector of links. The match happens once per grid operation, and the loops
+// inside are compiled separately for each width.
+macro_rules! with_links {
+    ($nodes:expr, $links:ident => $body:expr) => {
+        match $nodes {
+            NodeLinks::U32($links) => $body,
+            NodeLinks::Usize($links) => $body,
+        }
+    };
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Operation {
+    CoverColumn(usize),
+    CoverRow(Node),
+    HideRow(usize),
+}
+
+/// A record of which columns and rows of a `Grid` were covered at some point,
+/// created by [`Grid::snapshot`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Snapshot {
+    trail_len: usize,
+    // The identifier of the last entry of the trail, if any
+    last: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    left: usize,
+    right: usize,
+    size: usize,
+    is_covered: bool,
+    is_optional: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Links<I> {
+    up: I,
+    down: I,
+    column: I,
+    row: I,
+}
+
+impl<I: LinkIndex> Links<I> {
+    fn new(up: usize, down: usize, column: usize, row: usize) -> Self {
+        Links {
+            up: I::from_usize(up),
+            down: I::from_usize(down),
+            column: I::from_usize(column),
+            row: I::from_usize(row),
+        }
+    }
+
+    fn convert<J: LinkIndex>(self) -> Links<J> {
+        Links::new(
+            self.up.to_usize(),
+            self.down.to_usize(),
+            self.column.to_usize(),
+            self.row.to_usize(),
+        )
+    }
+}
+
+/// A handle to a coordinate inside of a `Grid`.
+///
+/// A `Node` is only meaningful for the `Grid` that produced it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Node(usize);
+
 impl Grid {
     /// Create a new grid with a specified number of columns, and the given
     /// coordinates filled.
@@ -28,159 +190,201 @@ impl Grid {
     /// Rows and columns are based 1 indexed for this grid, matching the
     /// indexing notation for matrices in general.
     pub fn new(num_columns: usize, coordinates: impl IntoIterator<Item = (usize, usize)>) -> Self {
-        let arena = bumpalo::Bump::new();
-        let root = Column::new(&arena, 0);
-        let columns = once(root)
-            .chain((1..=num_columns).map(|idx| Column::new(&arena, idx)))
-            .collect::<Vec<_>>();
-
-        // Chain all the columns together, including the sentinel root column.
-        for idx in 0..columns.len() {
-            let next_idx = (idx + 1) % columns.len();
-            let column = columns[idx];
-            let next_column = columns[next_idx];
-
-            Column::add_right(column, next_column);
-        }
+        Self::with_optional_columns(num_columns, [], coordinates)
+    }
 
+    /// Create a new grid with a specified number of columns, where the given
+    /// columns are optional, and the given coordinates filled.
+    ///
+    /// Rows and columns are based 1 indexed for this grid, matching the
+    /// indexing notation for matrices in general.
+    pub fn with_optional_columns(
+        num_columns: usize,
+        optional_columns: impl IntoIterator<Item = usize>,
+        coordinates: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Self {
         let mut grid = Grid {
-            root,
-            columns,
-            arena,
-            num_columns,
-            max_row: 0,
+            headers: Vec::new(),
+            nodes: NodeLinks::U32(Vec::new()),
+            row_start: Vec::new(),
+            trail: Vec::new(),
+            pushed: 0,
+            num_columns: 0,
         };
-
-        grid.add_all_coordinates(coordinates);
+        grid.rebuild(num_columns, optional_columns, coordinates);
 
         grid
     }
 
-    fn add_all_coordinates(&mut self, coordinates: impl IntoIterator<Item = (usize, usize)>) {
-        // Deduct one for the sentinel column
-        let mut columns_data: Vec<Vec<_>> =
-            (0..(self.columns.len() - 1)).map(|_| Vec::new()).collect();
-
-        for (row, column) in coordinates {
-            debug_assert!(
-                row != 0 && column != 0,
-                "row or column should not equal zero [{:?}].",
-                (row, column)
-            );
+    /// Replace the contents of the grid with the given columns and
+    /// coordinates, as if it was created by [`Grid::with_optional_columns`].
+    ///
+    /// The memory used by the grid is reused, so rebuilding a grid for many
+    /// problems of a similar size allocates less than creating a new grid for
+    /// each one. Every `Node` and `Snapshot` of the old contents is
+    /// invalidated.
+    pub fn rebuild(
+        &mut self,
+        num_columns: usize,
+        optional_columns: impl IntoIterator<Item = usize>,
+        coordinates: impl IntoIterator<Item = (usize, usize)>,
+    ) {
+        self.headers.clear();
+        self.headers.resize(
+            num_columns + 2,
+            Header {
+                left: 0,
+                right: 0,
+                size: 0,
+                is_covered: false,
+                is_optional: false,
+            },
+        );
+        for column in optional_columns {
             debug_assert!(
-                column <= columns_data.len(),
-                "column idx should be in bounds [{column:?}]",
+                column != 0 && column <= num_columns,
+                "optional column idx should be in bounds [{column:?}]",
             );
 
-            columns_data[column - 1].push((row, column));
-
-            if self.max_row < row {
-                self.max_row = row
-            }
+            self.headers[column].is_optional = true;
         }
+        self.headers[num_columns + 1].is_optional = true;
+
+        let coordinates = Self::sorted_coordinates(num_columns, coordinates);
+        let num_nodes = num_columns + 2 + coordinates.len();
+        let max_row = coordinates.last().map_or(0, |(row, _)| *row);
+
+        let width = IndexWidth::narrowest(num_nodes, max_row);
+        if width == self.index_width() {
+            with_links!(&mut self.nodes, links => {
+                links.clear();
+                links.reserve(num_nodes);
+            });
+        } else {
+            self.nodes = match width {
+                IndexWidth::U32 => NodeLinks::U32(Vec::with_capacity(num_nodes)),
+                IndexWidth::Usize => NodeLinks::Usize(Vec::with_capacity(num_nodes)),
+            };
+        }
+        self.row_start.clear();
+        self.trail.clear();
+        self.num_columns = num_columns;
+
+        self.link_headers();
+        with_links!(&mut self.nodes, links => {
+            add_all_coordinates(
+                links,
+                &mut self.headers,
+                &mut self.row_start,
+                num_columns,
+                &coordinates,
+            )
+        });
+    }
 
-        for column_data in &mut columns_data {
-            column_data.sort_unstable_by_key(|(k, _)| *k);
+    /// Convert the grid to store its links with the given width of integer.
+    ///
+    /// # Panics
+    ///  - If the grid has too many nodes or rows to be indexed by the given
+    ///    width.
+    pub fn with_index_width(mut self, width: IndexWidth) -> Self {
+        if width == self.index_width() {
+            return self;
         }
 
-        // Map all the data into nodes
-        let mut nodes: Vec<VecDeque<*mut Node>> = columns_data
-            .into_iter()
-            .map(|column_data| {
-                column_data
-                    .into_iter()
-                    .map(|(row_idx, column_idx)| {
-                        let column = self.columns[column_idx];
-
-                        Node::new(&self.arena, row_idx, column)
-                    })
-                    .collect()
-            })
-            .collect();
+        self.nodes = match (self.nodes, width) {
+            (NodeLinks::Usize(links), IndexWidth::U32) => {
+                assert_eq!(
+                    IndexWidth::narrowest(links.len(), self.row_start.len() - 1),
+                    IndexWidth::U32,
+                    "The grid is too large to be indexed by 32-bit integers."
+                );
 
-        // Then, add all the vertical connections, without wrapping around. Skip the
-        // first (sentinel) column.
-        for (node_column, column_header) in nodes.iter_mut().zip(self.columns.iter().skip(1)) {
-            let pair_it = node_column.iter().zip(node_column.iter().skip(1));
-            for (current_node, next_node) in pair_it {
-                BaseNode::add_below(current_node.cast(), next_node.cast());
+                NodeLinks::U32(links.into_iter().map(Links::convert).collect())
+            }
+            (NodeLinks::U32(links), IndexWidth::Usize) => {
+                NodeLinks::Usize(links.into_iter().map(Links::convert).collect())
             }
+            (nodes, _) => nodes,
+        };
 
-            // Connect first and last to header
-            if let Some(first) = node_column.front() {
-                BaseNode::add_below(column_header.cast(), first.cast());
+        self
+    }
 
-                if let Some(last) = node_column.back() {
-                    BaseNode::add_above(column_header.cast(), last.cast());
+    /// Return the width of integer used to store the links of the grid.
+    pub fn index_width(&self) -> IndexWidth {
+        match self.nodes {
+            NodeLinks::U32(_) => IndexWidth::U32,
+            NodeLinks::Usize(_) => IndexWidth::Usize,
+        }
+    }
+
+    fn primary_root(&self) -> usize {
+        0
+    }
+
+    fn optional_root(&self) -> usize {
+        self.num_columns + 1
+    }
+
+    /// Chain all the columns of each kind together, including the root of each
+    /// list.
+    fn link_headers(&mut self) {
+        for root in [self.primary_root(), self.optional_root()] {
+            let is_optional = self.headers[root].is_optional;
+            let mut prev = root;
+
+            for column in 1..=self.num_columns {
+                if self.headers[column].is_optional == is_optional {
+                    self.headers[prev].right = column;
+                    self.headers[column].left = prev;
+                    prev = column;
                 }
             }
+
+            self.headers[prev].right = root;
+            self.headers[root].left = prev;
         }
+    }
 
-        // Then, add all horizontal connections, with wrap around
-        //
-        // To do this we need to select all nodes which have the same row value
-        // and then chain them together. The column data is in sorted order from
-        // before.
-        //
-        // For each column, collect a list with the top (least row value) node. Then,
-        // for each value in the list, collect a subset that contains all the nodes with
-        // the same least row value. They should also be in column order. This
-        // collection will be linked together with wraparound. Then all those nodes that
-        // were selected for the least subset will be replaced from the list with the
-        // next relevant node from the column.
-
-        let mut top_nodes: Vec<Option<(usize, *mut Node)>> = nodes
-            .iter_mut()
-            .map(|column_data| {
-                let node = column_data.pop_front();
-
-                node.map(|node| unsafe { (ptr::read(node).row, node) })
+    fn sorted_coordinates(
+        num_columns: usize,
+        coordinates: impl IntoIterator<Item = (usize, usize)>,
+    ) -> Vec<(usize, usize)> {
+        let mut coordinates: Vec<_> = coordinates
+            .into_iter()
+            .inspect(|&(row, column)| {
+                debug_assert!(
+                    row != 0 && column != 0,
+                    "row or column should not equal zero [{:?}].",
+                    (row, column)
+                );
+                debug_assert!(
+                    column <= num_columns,
+                    "column idx should be in bounds [{column:?}]",
+                );
             })
             .collect();
 
-        let mut least_nodes = Vec::<(usize, *mut Node)>::with_capacity(top_nodes.len());
-
-        while top_nodes.iter().any(Option::is_some) {
-            let mut least_row = usize::MAX;
-
-            // Select the subcollection of least row nodes
-            for (idx, row_node_pair) in top_nodes.iter().enumerate() {
-                if let Some((row, node)) = row_node_pair {
-                    use core::cmp::Ordering;
-
-                    match row.cmp(&least_row) {
-                        Ordering::Equal => {
-                            least_nodes.push((idx, *node));
-                        }
-                        Ordering::Less => {
-                            least_nodes.clear();
-                            least_row = *row;
-                            least_nodes.push((idx, *node));
-                        }
-                        Ordering::Greater => {}
-                    }
-                }
-            }
+        // Sorting by row then column lays out each row contiguously, and makes sure
+        // each column is linked in increasing row order.
+        coordinates.sort_unstable();
+        debug_assert!(
+            coordinates.windows(2).all(|pair| pair[0] != pair[1]),
+            "coordinates should not be repeated."
+        );
 
-            // Link all the least row nodes together
-            //
-            // This is fine for the case of (least_nodes.len() == 1) bc all nodes started
-            // already linked to themselves.
-            for (idx, (_, node)) in least_nodes.iter().enumerate() {
-                let next_node_idx = (idx + 1) % least_nodes.len();
-                let (_, next_node) = least_nodes[next_node_idx];
+        coordinates
+    }
 
-                BaseNode::add_right(node.cast(), next_node.cast());
-            }
+    /// Return the number of columns in the grid, covered or uncovered.
+    pub fn num_columns(&self) -> usize {
+        self.num_columns
+    }
 
-            // Replace the least row nodes with the next values from their respective
-            // columns.
-            for (column_idx, _) in least_nodes.drain(..) {
-                top_nodes[column_idx] = nodes[column_idx]
-                    .pop_front()
-                    .map(|node| unsafe { (ptr::read(node).row, node) });
-            }
-        }
+    /// Return the largest row index in the grid.
+    pub fn max_row(&self) -> usize {
+        self.row_start.len() - 1
     }
 
     /// Convert the grid to a dense representation.
@@ -188,12 +392,12 @@ impl Grid {
     /// This takes the original size of the grid, and only put `true` values for
     /// locations that are still present in the grid (not covered).
     pub fn to_dense(&self) -> Box<[Box<[bool]>]> {
-        let seen_coords = self.uncovered_columns().flat_map(|column_ptr| {
-            let column_idx = Column::index(column_ptr);
-            Column::row_indices(column_ptr).map(move |row_idx| (row_idx, column_idx))
+        let seen_coords = self.uncovered_columns().flat_map(|column_idx| {
+            self.column_row_indices(column_idx)
+                .map(move |row_idx| (row_idx, column_idx))
         });
 
-        let mut output = vec![false; self.num_columns * self.max_row];
+        let mut output = vec![false; self.num_columns * self.max_row()];
 
         for (row_idx, column_idx) in seen_coords {
             output[(row_idx - 1) * self.num_columns + (column_idx - 1)] = true
@@ -212,267 +416,398 @@ impl Grid {
         }
     }
 
-    /// Return an iterator of pointers to columns that are uncovered.
-    pub fn uncovered_columns(&self) -> impl Iterator<Item = *const Column> {
-        base_node::iter::right(self.root.cast(), Some(self.root.cast()))
-            .map(|base_ptr| base_ptr.cast::<Column>())
-    }
+    fn list(&self, root: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut current = root;
 
-    /// Return an iterator of mut pointers to columns that are uncovered.
-    pub fn uncovered_columns_mut(&mut self) -> impl Iterator<Item = *mut Column> {
-        base_node::iter::right_mut(self.root.cast(), Some(self.root.cast()))
-            .map(|base_ptr| base_ptr.cast::<Column>())
+        core::iter::from_fn(move || {
+            current = self.headers[current].right;
+
+            (current != root).then_some(current)
+        })
     }
 
-    /// Return an iterator over all columns that are in the grid (covered and
-    /// uncovered).
-    pub fn all_columns_mut(&mut self) -> impl DoubleEndedIterator<Item = *mut Column> + '_ {
-        self.columns
-            .iter()
-            .copied()
-            // Skip the sentinel
-            .skip(1)
+    /// Return an iterator over the indices of all uncovered columns, primary
+    /// columns first.
+    pub fn uncovered_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        self.uncovered_primary_columns()
+            .chain(self.list(self.optional_root()))
     }
 
-    /// Return a pointer to a specific `Column`, if it exists.
-    pub fn get_column(&self, index: usize) -> Option<*const Column> {
-        self.columns
-            .get(index)
-            .copied()
-            .map(|column_ptr| column_ptr as *const _)
+    /// Return an iterator over the indices of all uncovered primary columns.
+    pub fn uncovered_primary_columns(&self) -> impl Iterator<Item = usize> + '_ {
+        self.list(self.primary_root())
     }
 
-    /// Return a mut pointer to a specific `Column`, if it exists.
-    pub fn get_column_mut(&mut self, index: usize) -> Option<*mut Column> {
-        self.columns.get(index).copied()
+    /// Return an iterator over the indices of all columns that are in the grid
+    /// (covered and uncovered).
+    pub fn all_columns(&self) -> impl DoubleEndedIterator<Item = usize> {
+        1..=self.num_columns
     }
 
     /// Return true if there are no uncovered columns in the grid.
     pub fn is_empty(&self) -> bool {
-        unsafe {
-            let column = ptr::read(self.root);
-
-            std::ptr::eq(column.base.right, self.root.cast())
-        }
+        self.uncovered_columns().next().is_none()
     }
-}
-
-/// A coordinate inside of a `Grid`.
-#[derive(Debug, PartialEq, Eq, Hash)]
-#[repr(C)]
-pub struct Node {
-    base: BaseNode,
-
-    row: usize,
-    column: *mut Column,
-}
 
-impl Node {
-    fn new(arena: &bumpalo::Bump, row: usize, column: *mut Column) -> *mut Self {
-        Column::increment_size(column);
-
-        let node = arena.alloc(Node {
-            base: BaseNode::new(),
-
-            row,
-            column,
-        });
+    /// Return true if there are no uncovered primary columns in the grid.
+    pub fn is_primary_empty(&self) -> bool {
+        self.uncovered_primary_columns().next().is_none()
+    }
 
-        node.base.set_self_ptr();
+    /// Return the number of uncovered nodes in the given column.
+    pub fn column_size(&self, column: usize) -> usize {
+        self.headers[column].size
+    }
 
-        node
+    /// Return true if the given column is covered.
+    pub fn is_column_covered(&self, column: usize) -> bool {
+        self.headers[column].is_covered
     }
 
-    /// Cover every `Node` that is horizontally adjacent to this `Node`.
-    ///
-    /// This `Node` is not covered.
-    pub fn cover_row(self_ptr: *mut Node) {
-        // Skip over the originating node in the row so that it can be recovered from
-        // the column.
-        base_node::iter::right_mut(self_ptr.cast(), Some(self_ptr.cast())).for_each(
-            |base_ptr| unsafe {
-                let node = ptr::read(base_ptr.cast::<Node>());
-
-                Column::decrement_size(node.column);
-                BaseNode::cover_vertical(base_ptr);
-            },
-        )
+    /// Return true if the given column is optional.
+    pub fn is_column_optional(&self, column: usize) -> bool {
+        self.headers[column].is_optional
     }
 
-    /// Uncover every `Node` that is horizontally adjacent to this `Node`.
-    ///
-    /// This `Node` is not uncovered.
-    pub fn uncover_row(self_ptr: *mut Self) {
-        let base_ptr = self_ptr.cast::<BaseNode>();
+    /// Return an iterator over all uncovered `Node`s in the given column, in
+    /// increasing row order.
+    pub fn column_nodes(&self, column: usize) -> impl Iterator<Item = Node> + '_ {
+        let mut current = column;
 
-        base_node::iter::left_mut(base_ptr, Some(base_ptr)).for_each(|base_ptr| unsafe {
-            let node = ptr::read(base_ptr.cast::<Node>());
+        core::iter::from_fn(move || {
+            current = with_links!(&self.nodes, links => links[current].down.to_usize());
 
-            Column::increment_size(node.column);
-            BaseNode::uncover_vertical(base_ptr);
+            (current != column).then_some(Node(current))
         })
     }
 
-    /// Return the row index of this `Node`.
-    pub fn row_index(self_ptr: *const Self) -> usize {
-        unsafe { ptr::read(self_ptr).row }
+    /// Return an iterator over the row indices of all uncovered `Node`s in the
+    /// given column.
+    pub fn column_row_indices(&self, column: usize) -> impl Iterator<Item = usize> + '_ {
+        self.column_nodes(column).map(|node| self.node_row(node))
     }
 
-    /// Return the column index of this `Node`.
-    pub fn column_index(self_ptr: *const Self) -> usize {
-        unsafe {
-            let node = ptr::read(self_ptr);
-            let column = ptr::read(node.column);
+    /// Return the row index of the given `Node`.
+    pub fn node_row(&self, node: Node) -> usize {
+        with_links!(&self.nodes, links => links[node.0].row.to_usize())
+    }
 
-            column.index
-        }
+    /// Return the column index of the given `Node`.
+    pub fn node_column(&self, node: Node) -> usize {
+        with_links!(&self.nodes, links => links[node.0].column.to_usize())
     }
 
-    /// Return a mut pointer to the `Column` of this `Node`.
-    pub fn column_ptr(self_ptr: *const Self) -> *mut Column {
-        unsafe {
-            let node = ptr::read(self_ptr);
+    /// Return an iterator over all the `Node`s in the same row as the given
+    /// `Node`, not including the given `Node`.
+    ///
+    /// The iteration starts from the `Node` after the given one, and wraps
+    /// around to the start of the row.
+    pub fn row_neighbors(&self, node: Node) -> impl DoubleEndedIterator<Item = Node> {
+        let (start, end) = row_range(&self.row_start, self.node_row(node));
 
-            node.column
-        }
+        neighbors(node.0, start, end).map(Node)
     }
 
-    /// Return an iterator over all `Node`s that are adjacent to this `Node`.
-    pub fn neighbors(self_ptr: *const Self) -> impl Iterator<Item = *const Node> {
-        base_node::iter::left(self_ptr.cast(), None).map(|base_ptr| base_ptr.cast())
+    /// Return an iterator over all the `Node`s in the given row, in increasing
+    /// column order.
+    pub fn row_nodes(&self, row: usize) -> impl DoubleEndedIterator<Item = Node> {
+        let start = self.row_start.get(row.wrapping_sub(1)).copied();
+        let end = self.row_start.get(row).copied();
+
+        match (start, end) {
+            (Some(start), Some(end)) => (start..end).map(Node),
+            _ => (0..0).map(Node),
+        }
     }
-}
 
-/// A column inside of a `Grid`.
-#[derive(Debug, PartialEq, Eq, Hash)]
-#[repr(C)]
-pub struct Column {
-    base: BaseNode,
+    /// Cover entire column, and any rows that that appear in this column.
+    ///
+    /// # Panics
+    ///  - If the column is already covered.
+    pub fn cover_column(&mut self, column: usize) {
+        let Header {
+            left,
+            right,
+            is_covered,
+            ..
+        } = self.headers[column];
+        assert!(!is_covered);
+
+        self.headers[left].right = right;
+        self.headers[right].left = left;
+
+        with_links!(&mut self.nodes, links => {
+            let mut current = links[column].down.to_usize();
+            while current != column {
+                hide_neighbors(links, &mut self.headers, &self.row_start, current);
+                current = links[current].down.to_usize();
+            }
+        });
 
-    size: usize,
-    index: usize,
-    is_covered: bool,
-}
+        self.headers[column].is_covered = true;
+        self.push_to_trail(Operation::CoverColumn(column));
+    }
 
-impl Column {
-    fn new(arena: &bumpalo::Bump, index: usize) -> *mut Self {
-        let column = arena.alloc(Column {
-            base: BaseNode::new(),
-            size: 0,
-            is_covered: false,
-            index,
+    /// Uncover entire column, and any rows that appear in this column.
+    ///
+    /// # Panics
+    ///  - If the column is not covered.
+    pub fn uncover_column(&mut self, column: usize) {
+        let Header {
+            left,
+            right,
+            is_covered,
+            ..
+        } = self.headers[column];
+        assert!(is_covered);
+
+        with_links!(&mut self.nodes, links => {
+            let mut current = links[column].up.to_usize();
+            while current != column {
+                unhide_neighbors(links, &mut self.headers, &self.row_start, current);
+                current = links[current].up.to_usize();
+            }
         });
 
-        column.base.set_self_ptr();
+        self.headers[left].right = column;
+        self.headers[right].left = column;
 
-        column
+        self.headers[column].is_covered = false;
+        self.remove_from_trail(Operation::CoverColumn(column));
     }
 
-    fn increment_size(self_ptr: *mut Self) {
-        unsafe {
-            let mut column = ptr::read(self_ptr);
+    /// Cover every `Node` that is horizontally adjacent to this `Node`.
+    ///
+    /// This `Node` is not covered.
+    pub fn cover_row(&mut self, node: Node) {
+        with_links!(&mut self.nodes, links => {
+            hide_neighbors(links, &mut self.headers, &self.row_start, node.0)
+        });
+        self.push_to_trail(Operation::CoverRow(node));
+    }
 
-            column.size += 1;
+    /// Uncover every `Node` that is horizontally adjacent to this `Node`.
+    ///
+    /// This `Node` is not uncovered.
+    pub fn uncover_row(&mut self, node: Node) {
+        with_links!(&mut self.nodes, links => {
+            unhide_neighbors(links, &mut self.headers, &self.row_start, node.0)
+        });
+        self.remove_from_trail(Operation::CoverRow(node));
+    }
 
-            ptr::write(self_ptr, column);
-        }
+    /// Remove every `Node` of the given row from its column, so that the row
+    /// can no longer be selected.
+    ///
+    /// The row must be fully present in the grid, which means that none of its
+    /// columns are covered and it has not been hidden already.
+    pub fn hide_row(&mut self, row: usize) {
+        debug_assert!(self
+            .row_nodes(row)
+            .all(|node| !self.is_column_covered(self.node_column(node))));
+
+        with_links!(&mut self.nodes, links => {
+            hide_row(links, &mut self.headers, &self.row_start, row)
+        });
+        self.push_to_trail(Operation::HideRow(row));
     }
 
-    fn decrement_size(self_ptr: *mut Self) {
-        unsafe {
-            let mut column = ptr::read(self_ptr);
+    /// Put every `Node` of the given row back into its column, undoing
+    /// [`Grid::hide_row`].
+    pub fn unhide_row(&mut self, row: usize) {
+        with_links!(&mut self.nodes, links => {
+            unhide_row(links, &mut self.headers, &self.row_start, row)
+        });
+        self.remove_from_trail(Operation::HideRow(row));
+    }
 
-            column.size -= 1;
+    fn push_to_trail(&mut self, operation: Operation) {
+        self.trail.push((operation, self.pushed));
+        self.pushed += 1;
+    }
 
-            ptr::write(self_ptr, column);
+    fn remove_from_trail(&mut self, operation: Operation) {
+        // Operations are almost always undone in the reverse order, so only
+        // search the rest of the trail when the last entry doesn't match
+        match self.trail.last() {
+            Some((last, _)) if *last == operation => {
+                self.trail.pop();
+            }
+            _ => {
+                if let Some(position) = self.trail.iter().rposition(|(op, _)| *op == operation) {
+                    self.trail.remove(position);
+                }
+            }
         }
     }
 
-    /// Cover entire column, and any rows that that appear in this column.
-    pub fn cover(self_ptr: *mut Self) {
-        let mut column = unsafe { ptr::read(self_ptr) };
-        assert!(!column.is_covered);
-
-        let base_ptr = self_ptr.cast::<BaseNode>();
-
-        BaseNode::cover_horizontal(base_ptr);
-
-        base_node::iter::down_mut(base_ptr, Some(base_ptr))
-            .for_each(|base_ptr| Node::cover_row(base_ptr.cast()));
-
-        column.is_covered = true;
-        unsafe {
-            ptr::write(self_ptr, column);
+    /// Record which columns and rows are currently covered, so that the grid
+    /// can be returned to this state with [`Grid::restore`].
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            trail_len: self.trail.len(),
+            last: self.trail.last().map(|(_, id)| *id),
         }
     }
 
-    /// Uncover entire column, and any rows that appear in this column.
-    pub fn uncover(self_ptr: *mut Self) {
-        let mut column = unsafe { ptr::read(self_ptr) };
-        assert!(column.is_covered);
-
-        let base_ptr = self_ptr.cast::<BaseNode>();
-
-        base_node::iter::up_mut(base_ptr, Some(base_ptr))
-            .for_each(|base_ptr| Node::uncover_row(base_ptr.cast()));
-
-        BaseNode::uncover_horizontal(base_ptr);
+    /// Undo every cover operation performed since the given `Snapshot` was
+    /// taken, in reverse order.
+    ///
+    /// # Panics
+    ///  - If any operation in effect when the `Snapshot` was taken has been
+    ///    undone since, which means the state it recorded no longer exists.
+    pub fn restore(&mut self, snapshot: Snapshot) {
+        // Every entry has a different identifier, so the entries up to the
+        // snapshot are unchanged if the last of them is the same one
+        let unchanged = match snapshot.trail_len.checked_sub(1) {
+            Some(index) => self.trail.get(index).map(|(_, id)| *id) == snapshot.last,
+            None => true,
+        };
+        assert!(
+            unchanged,
+            "The grid has been uncovered past the given snapshot."
+        );
 
-        column.is_covered = false;
-        unsafe {
-            ptr::write(self_ptr, column);
+        while self.trail.len() > snapshot.trail_len {
+            match self.trail[self.trail.len() - 1].0 {
+                Operation::CoverColumn(column) => self.uncover_column(column),
+                Operation::CoverRow(node) => self.uncover_row(node),
+                Operation::HideRow(row) => self.unhide_row(row),
+            }
         }
     }
 
-    fn add_right(self_ptr: *mut Self, neighbor_ptr: *mut Column) {
-        BaseNode::add_right(self_ptr.cast(), neighbor_ptr.cast());
+    /// Cover every column that has a `Node` in the same row as this `Node`,
+    /// starting with the column of this `Node`.
+    ///
+    /// This is the operation of adding the row to a partial solution, and can
+    /// be undone with [`Grid::unselect_row`].
+    pub fn select_row(&mut self, node: Node) {
+        self.cover_column(self.node_column(node));
+        for neighbor in self.row_neighbors(node) {
+            self.cover_column(self.node_column(neighbor));
+        }
     }
 
-    /// Return true if there are no uncovered `Node`s in this column.
-    pub fn is_empty(self_ptr: *const Self) -> bool {
-        unsafe {
-            let column = ptr::read(self_ptr);
-
-            let empty = (column.base.down as *const _) == self_ptr;
-
-            debug_assert!(
-                !empty && Self::size(self_ptr) == 0,
-                "The size should be tracked accurately."
-            );
-
-            empty
+    /// Uncover every column that has a `Node` in the same row as this `Node`,
+    /// in the reverse order of [`Grid::select_row`].
+    pub fn unselect_row(&mut self, node: Node) {
+        for neighbor in self.row_neighbors(node).rev() {
+            self.uncover_column(self.node_column(neighbor));
         }
+        self.uncover_column(self.node_column(node));
     }
+}
+
+fn add_all_coordinates<I: LinkIndex>(
+    links: &mut Vec<Links<I>>,
+    headers: &mut [Header],
+    row_start: &mut Vec<usize>,
+    num_columns: usize,
+    coordinates: &[(usize, usize)],
+) {
+    links.extend((0..(num_columns + 2)).map(|idx| Links::new(idx, idx, idx, 0)));
+    row_start.push(links.len());
+
+    for &(row, column) in coordinates {
+        while row_start.len() < row {
+            row_start.push(links.len());
+        }
 
-    /// Return an iterator over the row indices of all uncovered `Node`s in this
-    /// column.
-    pub fn row_indices(self_ptr: *const Self) -> impl Iterator<Item = usize> {
-        Column::rows(self_ptr).map(|node_ptr| unsafe { ptr::read(node_ptr).row })
+        let node = links.len();
+        let last = links[column].up.to_usize();
+        links.push(Links::new(last, column, column, row));
+        links[last].down = I::from_usize(node);
+        links[column].up = I::from_usize(node);
+        headers[column].size += 1;
     }
+    row_start.push(links.len());
+}
+
+fn row_range(row_start: &[usize], row: usize) -> (usize, usize) {
+    (row_start[row - 1], row_start[row])
+}
 
-    /// Return an iterator of pointers to all uncovered `Node`s in this column.
-    pub fn rows(self_ptr: *const Self) -> impl Iterator<Item = *const Node> {
-        base_node::iter::down(self_ptr.cast(), Some(self_ptr.cast()))
-            .map(|base_ptr| base_ptr.cast())
+// The other nodes of the row `start..end`, starting after `node` and wrapping around
+fn neighbors(node: usize, start: usize, end: usize) -> impl DoubleEndedIterator<Item = usize> {
+    ((node + 1)..end).chain(start..node)
+}
+
+fn hide_neighbors<I: LinkIndex>(
+    links: &mut [Links<I>],
+    headers: &mut [Header],
+    row_start: &[usize],
+    node: usize,
+) {
+    let (start, end) = row_range(row_start, links[node].row.to_usize());
+
+    // Skip over the originating node in the row so that it can be recovered from
+    // the column.
+    for neighbor in neighbors(node, start, end) {
+        let Links {
+            up, down, column, ..
+        } = links[neighbor];
+
+        links[up.to_usize()].down = down;
+        links[down.to_usize()].up = up;
+        headers[column.to_usize()].size -= 1;
     }
+}
 
-    /// Return an iterator of mut pointers to all uncovered `Node`s in this
-    /// column.
-    pub fn nodes_mut(self_ptr: *mut Self) -> impl Iterator<Item = *mut Node> {
-        base_node::iter::down_mut(self_ptr.cast(), Some(self_ptr.cast()))
-            .map(|base_ptr| base_ptr.cast())
+fn unhide_neighbors<I: LinkIndex>(
+    links: &mut [Links<I>],
+    headers: &mut [Header],
+    row_start: &[usize],
+    node: usize,
+) {
+    let (start, end) = row_range(row_start, links[node].row.to_usize());
+
+    for neighbor in neighbors(node, start, end).rev() {
+        let Links {
+            up, down, column, ..
+        } = links[neighbor];
+
+        links[up.to_usize()].down = I::from_usize(neighbor);
+        links[down.to_usize()].up = I::from_usize(neighbor);
+        headers[column.to_usize()].size += 1;
     }
+}
 
-    /// Return the column index.
-    #[inline]
-    pub fn index(self_ptr: *const Self) -> usize {
-        unsafe { ptr::read(self_ptr).index }
+fn hide_row<I: LinkIndex>(
+    links: &mut [Links<I>],
+    headers: &mut [Header],
+    row_start: &[usize],
+    row: usize,
+) {
+    let (start, end) = row_range(row_start, row);
+    if start != end {
+        hide_neighbors(links, headers, row_start, start);
+
+        let Links {
+            up, down, column, ..
+        } = links[start];
+        links[up.to_usize()].down = down;
+        links[down.to_usize()].up = up;
+        headers[column.to_usize()].size -= 1;
     }
+}
 
-    /// Return the number of uncovered nodes in this column.
-    #[inline]
-    pub fn size(self_ptr: *const Self) -> usize {
-        unsafe { ptr::read(self_ptr).size }
+fn unhide_row<I: LinkIndex>(
+    links: &mut [Links<I>],
+    headers: &mut [Header],
+    row_start: &[usize],
+    row: usize,
+) {
+    let (start, end) = row_range(row_start, row);
+    if start != end {
+        let Links {
+            up, down, column, ..
+        } = links[start];
+        links[up.to_usize()].down = I::from_usize(start);
+        links[down.to_usize()].up = I::from_usize(start);
+        headers[column.to_usize()].size += 1;
+
+        unhide_neighbors(links, headers, row_start, start);
     }
 }
 
@@ -563,6 +898,8 @@ mod tests {
             .collect()
         );
         assert!(!thin_grid.is_empty());
+        assert_eq!(thin_grid.row_nodes(4).count(), 0);
+        assert_eq!(thin_grid.row_nodes(9).count(), 0);
 
         let very_thin_grid = Grid::new(0, vec![]);
 
@@ -576,13 +913,10 @@ mod tests {
         let mut grid = Grid::new(4, vec![(1, 1), (1, 4), (2, 2), (3, 3), (4, 1), (4, 4)]);
 
         // mutate the grid
-        Column::cover(grid.all_columns_mut().nth(3).unwrap());
+        grid.cover_column(grid.all_columns().nth(3).unwrap());
 
         // Check remaining columns
-        assert!(grid
-            .uncovered_columns()
-            .map(|column_ptr| unsafe { ptr::read(column_ptr).index })
-            .eq(1..=3));
+        assert!(grid.uncovered_columns().eq(1..=3));
         assert_eq!(
             grid.to_dense(),
             [
@@ -597,13 +931,10 @@ mod tests {
         );
 
         // mutate the grid
-        Column::uncover(grid.all_columns_mut().nth(3).unwrap());
+        grid.uncover_column(grid.all_columns().nth(3).unwrap());
 
         // Check remaining columns
-        assert!(grid
-            .uncovered_columns()
-            .map(|column_ptr| unsafe { ptr::read(column_ptr).index })
-            .eq(1..=4));
+        assert!(grid.uncovered_columns().eq(1..=4));
         assert_eq!(
             grid.to_dense(),
             [
@@ -629,12 +960,12 @@ mod tests {
         ]);
 
         // mutate the grid
-        for column_ptr in grid.all_columns_mut() {
-            Column::cover(column_ptr)
+        for column in grid.all_columns() {
+            grid.cover_column(column)
         }
 
         // Check remaining columns
-        assert!(grid.uncovered_columns().map(|column_ptr| unsafe { ptr::read(column_ptr).index }).eq(0..0));
+        assert!(grid.uncovered_columns().eq(0..0));
         assert_eq!(
             grid.to_dense(),
             [
@@ -650,12 +981,12 @@ mod tests {
         assert!(grid.is_empty());
 
         // mutate the grid
-        for column_ptr in grid.all_columns_mut().rev() {
-            Column::uncover(column_ptr)
+        for column in grid.all_columns().rev() {
+            grid.uncover_column(column)
         }
 
         // Check remaining columns
-        assert!(grid.uncovered_columns().map(|column_ptr| unsafe { ptr::read(column_ptr).index }).eq(1..=4));
+        assert!(grid.uncovered_columns().eq(1..=4));
         assert_eq!(
             grid.to_dense(),
             [
@@ -699,9 +1030,9 @@ mod tests {
         );
         assert!(!grid.is_empty());
 
-        Column::cover(grid.get_column_mut(2).unwrap());
-        Column::cover(grid.get_column_mut(3).unwrap());
-        Column::cover(grid.get_column_mut(5).unwrap());
+        grid.cover_column(2);
+        grid.cover_column(3);
+        grid.cover_column(5);
 
         assert_eq!(
             grid.to_dense(),
@@ -715,5 +1046,236 @@ mod tests {
             .map(Box::<[_]>::from)
             .collect()
         );
+        assert_eq!(grid.column_size(1), 1);
+        assert_eq!(grid.column_size(6), 1);
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn optional_columns() {
+        let mut grid = Grid::with_optional_columns(4, [2, 4], vec![
+            (1, 1), (1, 2),
+                    (2, 2), (2, 3),
+            (3, 1),                 (3, 4),
+        ]);
+
+        assert!(grid.uncovered_columns().eq([1, 3, 2, 4]));
+        assert!(grid.uncovered_primary_columns().eq([1, 3]));
+        assert!(grid.is_column_optional(2));
+        assert!(!grid.is_column_optional(3));
+
+        let node = grid.column_nodes(1).next().unwrap();
+        assert_eq!(grid.node_row(node), 1);
+        assert_eq!(grid.node_column(node), 1);
+        assert!(grid.row_neighbors(node).map(|node| grid.node_column(node)).eq([2]));
+
+        grid.select_row(node);
+        assert!(grid.uncovered_columns().eq([3, 4]));
+        assert!(grid.column_row_indices(3).eq(0..0));
+        assert!(!grid.is_primary_empty());
+
+        grid.unselect_row(node);
+        assert!(grid.uncovered_columns().eq([1, 3, 2, 4]));
+        assert!(grid.column_row_indices(3).eq([2]));
+
+        grid.cover_column(1);
+        grid.cover_column(3);
+        assert!(grid.is_primary_empty());
+        assert!(!grid.is_empty());
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn clone_preserves_cover_state() {
+        let mut grid = Grid::new(4, vec![(1, 1), (1, 4), (2, 2), (3, 3), (4, 1), (4, 4)]);
+        grid.cover_column(4);
+
+        let mut cloned = grid.clone();
+        assert_eq!(cloned.to_dense(), grid.to_dense());
+        assert!(cloned.uncovered_columns().eq(1..=3));
+        assert!(cloned.is_column_covered(4));
+
+        // The clone is independent of the original
+        cloned.cover_column(2);
+        assert!(cloned.uncovered_columns().eq([1, 3]));
+        assert!(grid.uncovered_columns().eq(1..=3));
+
+        cloned.uncover_column(2);
+        cloned.uncover_column(4);
+        assert_eq!(
+            cloned.to_dense(),
+            [
+                true, false, false, true,
+                false, true, false, false,
+                false, false, true, false,
+                true, false, false, true
+            ]
+            .chunks(4)
+            .map(Box::<[_]>::from)
+            .collect()
+        );
+        assert_eq!(cloned.column_size(1), 2);
+        assert_eq!(grid.column_size(1), 0);
+    }
+
+    #[test]
+    fn snapshot_and_restore() {
+        let mut grid = Grid::new(4, vec![(1, 1), (1, 4), (2, 2), (3, 3), (4, 1), (4, 4)]);
+        let original = grid.to_dense();
+
+        grid.cover_column(3);
+        let snapshot = grid.snapshot();
+        let covered_3 = grid.to_dense();
+
+        let node = grid.column_nodes(1).next().unwrap();
+        grid.select_row(node);
+        let node = grid.column_nodes(2).next().unwrap();
+        grid.cover_row(node);
+        assert!(grid.uncovered_columns().eq([2]));
+
+        grid.restore(snapshot);
+        assert_eq!(grid.to_dense(), covered_3);
+        assert!(grid.uncovered_columns().eq([1, 2, 4]));
+        assert_eq!(grid.column_size(1), 2);
+
+        // Restoring again is a no-op
+        grid.restore(snapshot);
+        assert_eq!(grid.to_dense(), covered_3);
+
+        grid.uncover_column(3);
+        assert_eq!(grid.to_dense(), original);
+    }
+
+    #[test]
+    #[should_panic(expected = "The grid has been uncovered past the given snapshot.")]
+    fn restore_stale_snapshot() {
+        let mut grid = Grid::new(2, vec![(1, 1), (2, 2)]);
+
+        grid.cover_column(1);
+        let snapshot = grid.snapshot();
+        grid.uncover_column(1);
+
+        grid.restore(snapshot);
+    }
+
+    #[test]
+    #[should_panic(expected = "The grid has been uncovered past the given snapshot.")]
+    fn restore_replaced_snapshot() {
+        let mut grid = Grid::new(2, vec![(1, 1), (2, 2)]);
+
+        // The trail is as long as when the snapshot was taken, but holds a
+        // different operation
+        grid.cover_column(1);
+        let snapshot = grid.snapshot();
+        grid.uncover_column(1);
+        grid.cover_column(2);
+
+        grid.restore(snapshot);
+    }
+
+    #[test]
+    fn hide_and_unhide_row() {
+        let mut grid = Grid::new(4, vec![(1, 1), (1, 4), (2, 2), (3, 3), (4, 1), (4, 4)]);
+        let original = grid.to_dense();
+
+        let snapshot = grid.snapshot();
+        grid.hide_row(4);
+        assert!(grid.column_row_indices(1).eq([1]));
+        assert!(grid.column_row_indices(4).eq([1]));
+        assert_eq!(grid.column_size(1), 1);
+        assert!(grid.uncovered_columns().eq([1, 2, 3, 4]));
+
+        // Hidden rows stay hidden when other rows are selected and unselected
+        let node = grid.column_nodes(2).next().unwrap();
+        grid.select_row(node);
+        grid.unselect_row(node);
+        assert!(grid.column_row_indices(1).eq([1]));
+
+        grid.restore(snapshot);
+        assert_eq!(grid.to_dense(), original);
+        assert_eq!(grid.column_size(4), 2);
+
+        grid.hide_row(2);
+        assert_eq!(grid.column_size(2), 0);
+        grid.unhide_row(2);
+        assert_eq!(grid.to_dense(), original);
+    }
+
+    #[test]
+    fn row_neighbors_wrap_around() {
+        let grid = Grid::new(4, vec![(1, 1), (1, 2), (1, 3), (1, 4)]);
+        let nodes: Vec<_> = grid.row_nodes(1).collect();
+
+        assert!(grid
+            .row_neighbors(nodes[2])
+            .map(|node| grid.node_column(node))
+            .eq([4, 1, 2]));
+        assert!(grid
+            .row_neighbors(nodes[2])
+            .rev()
+            .map(|node| grid.node_column(node))
+            .eq([2, 1, 4]));
+    }
+
+    #[test]
+    fn index_widths() {
+        // Each link takes 4 integers, so the compact links are half the size on 64-bit
+        // targets
+        assert_eq!(core::mem::size_of::<Links<u32>>(), 16);
+        assert_eq!(
+            core::mem::size_of::<Links<usize>>(),
+            4 * core::mem::size_of::<usize>()
+        );
+
+        let grid = Grid::new(4, vec![(1, 1), (1, 4), (2, 2), (3, 3), (4, 1), (4, 4)]);
+        assert_eq!(grid.index_width(), IndexWidth::U32);
+
+        let mut wide = grid.clone().with_index_width(IndexWidth::Usize);
+        assert_eq!(wide.index_width(), IndexWidth::Usize);
+        assert_eq!(wide.to_dense(), grid.to_dense());
+
+        let node = wide.column_nodes(1).next().unwrap();
+        wide.select_row(node);
+        assert!(wide.uncovered_columns().eq([2, 3]));
+
+        // Converting keeps the cover state
+        let mut narrow = wide.with_index_width(IndexWidth::U32);
+        assert_eq!(narrow.index_width(), IndexWidth::U32);
+        assert!(narrow.uncovered_columns().eq([2, 3]));
+        assert_eq!(narrow.column_size(1), 2);
+
+        narrow.unselect_row(node);
+        assert_eq!(narrow.to_dense(), grid.to_dense());
+    }
+
+    #[test]
+    fn rebuild_replaces_contents() {
+        let mut grid = Grid::new(4, vec![(1, 1), (1, 4), (2, 2), (3, 3), (4, 1), (4, 4)]);
+        let node = grid.column_nodes(1).next().unwrap();
+        grid.select_row(node);
+
+        // The cover state of the old contents is forgotten
+        let coordinates = vec![(1, 1), (1, 2), (2, 3), (3, 2), (3, 3)];
+        grid.rebuild(3, [3], coordinates.clone());
+        let expected = Grid::with_optional_columns(3, [3], coordinates);
+        assert_eq!(grid.to_dense(), expected.to_dense());
+        assert!(grid.uncovered_columns().eq([1, 2, 3]));
+        assert!(grid.uncovered_primary_columns().eq([1, 2]));
+        assert_eq!(grid.column_size(2), 2);
+        assert_eq!(grid.max_row(), 3);
+
+        let node = grid.column_nodes(1).next().unwrap();
+        grid.select_row(node);
+        assert!(grid.uncovered_columns().eq([3]));
+        assert!(grid.column_row_indices(3).eq([2]));
+
+        // The width of the links follows the new contents
+        let mut wide = expected.with_index_width(IndexWidth::Usize);
+        wide.rebuild(2, [], vec![(1, 1), (2, 2)]);
+        assert_eq!(wide.index_width(), IndexWidth::U32);
+        assert_eq!(
+            wide.to_dense(),
+            Grid::new(2, vec![(1, 1), (2, 2)]).to_dense()
+        );
     }
 }
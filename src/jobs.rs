@@ -0,0 +1,281 @@
+//! Splitting the search for the solutions of an exact cover problem into
+//! independent jobs, which can be solved in parallel or on different machines.
+//!
+//! [`split_jobs`] expands the top of the search tree of a [`Solver`], and
+//! returns one [`Job`] for each node on the frontier of the expanded part. A
+//! job is described by the possibilities chosen on the path from the root of
+//! the tree to its node, and its solutions are exactly the solutions which
+//! contain all of those possibilities. Every solution of the problem belongs to
+//! exactly one job.
+//!
+//! The jobs are returned in the order of the search tree, so solving them one
+//! after the other returns the same solutions in the same order as a single
+//! `Solver` using the same heuristic.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{
+//!     jobs::{split_jobs, SplitLimit},
+//!     queens::NQueens,
+//!     ColumnHeuristic, ExactCover,
+//! };
+//!
+//! let queens = NQueens::new(8, []);
+//! let jobs = split_jobs(&queens, ColumnHeuristic::default(), SplitLimit::Jobs(20));
+//! assert!(jobs.len() >= 20);
+//!
+//! let num_solutions: usize = jobs
+//!     .iter()
+//!     .map(|job| job.solver(&queens).unwrap().count())
+//!     .sum();
+//! assert_eq!(num_solutions, queens.solver().count());
+//! ```
+
+use crate::{ColumnHeuristic, ExactCover, PartialSolution, Solver};
+use alloc::{collections::VecDeque, vec::Vec};
+
+/// How far [`split_jobs`] expands the search tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SplitLimit {
+    /// Expand every node of the search tree above the given depth, so that
+    /// every job has this many forced possibilities (unless it is a solution
+    /// or the tree is shallower).
+    Depth(usize),
+    /// Expand nodes in breadth-first order until there are at least this many
+    /// jobs, or the whole tree has been expanded.
+    Jobs(usize),
+}
+
+/// An independent part of the search for the solutions of a problem, created
+/// by [`split_jobs`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Job {
+    /// The heuristic used to build the search tree.
+    pub heuristic: ColumnHeuristic,
+    /// The indices (into [`ExactCover::possibilities`]) of the possibilities
+    /// which are part of every solution of this job, in the order they were
+    /// chosen.
+    pub forced_rows: Vec<usize>,
+}
+
+impl Job {
+    /// Return a `Solver` for the solutions of this job, or `None` if the job
+    /// doesn't belong to the given problem.
+    ///
+    /// See [`Solver::with_forced_rows`].
+    pub fn solver<'e, E: ExactCover>(&self, problem: &'e E) -> Option<Solver<'e, E>> {
+        Solver::with_forced_rows(problem, self.heuristic, &self.forced_rows)
+    }
+}
+
+/// A node of the expanded part of the search tree.
+struct TreeNode {
+    // The position of the chosen row among the rows of the column chosen at each
+    // level, which orders the nodes like a depth-first search
+    path: Vec<usize>,
+    forced_rows: Vec<usize>,
+}
+
+/// Split the search for the solutions of the given problem into independent
+/// jobs, by expanding the search tree up to the given limit.
+///
+/// Branches of the tree which are found to have no solutions while expanding,
+/// or which are rejected by [`ExactCover::is_viable`], are left out, so some
+/// problems produce fewer jobs than requested, or none at all.
+pub fn split_jobs<E: ExactCover>(
+    problem: &E,
+    heuristic: ColumnHeuristic,
+    limit: SplitLimit,
+) -> Vec<Job> {
+    let mut grid = Solver::populate_grid(problem);
+
+    let mut frontier = VecDeque::new();
+    // Like `Solver`, a problem without primary constraints has no solutions
+    if !grid.is_primary_empty() {
+        frontier.push_back(TreeNode {
+            path: Vec::new(),
+            forced_rows: Vec::new(),
+        });
+    }
+    let mut leaves = Vec::new();
+
+    while let Some(node) = frontier.front() {
+        let done = match limit {
+            SplitLimit::Depth(depth) => node.forced_rows.len() >= depth,
+            SplitLimit::Jobs(jobs) => frontier.len() + leaves.len() >= jobs,
+        };
+        if done {
+            break;
+        }
+        let node = frontier.pop_front().unwrap();
+
+        let snapshot = grid.snapshot();
+        for row_index in &node.forced_rows {
+            let first = grid.row_nodes(row_index + 1).next().unwrap();
+            grid.select_row(first);
+        }
+
+        let partial = PartialSolution::from_grid(problem, &node.forced_rows, &grid);
+        if !node.forced_rows.is_empty() && !problem.is_viable(&partial) {
+            // The node and its children are pruned
+        } else if grid.is_primary_empty() {
+            // The node is a solution, so it can't be expanded further
+            leaves.push(node);
+        } else {
+            let column = heuristic.choose_column(&grid).unwrap();
+            for (position, row) in grid.column_row_indices(column).enumerate() {
+                let mut path = node.path.clone();
+                path.push(position);
+                let mut forced_rows = node.forced_rows.clone();
+                forced_rows.push(row - 1);
+
+                frontier.push_back(TreeNode { path, forced_rows });
+            }
+        }
+
+        grid.restore(snapshot);
+    }
+
+    leaves.extend(frontier);
+    leaves.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+
+    leaves
+        .into_iter()
+        .map(|node| Job {
+            heuristic,
+            forced_rows: node.forced_rows,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        sudoku::Sudoku,
+        testing::{random_problem, RandomProblemConfig},
+        text::TextProblem,
+    };
+    use core::ops::ControlFlow;
+
+    /// Return the solutions found by the solver, as possibility indices in the
+    /// order they were chosen.
+    fn solutions<E: ExactCover>(mut solver: Solver<'_, E>) -> Vec<Vec<usize>> {
+        let mut solutions = Vec::new();
+        let _ = solver.solve_with(|solution| {
+            solutions.push(solution.row_indices().to_vec());
+            ControlFlow::Continue(())
+        });
+
+        solutions
+    }
+
+    /// Check that solving the jobs in order gives the same solutions as the
+    /// full search, in the same order.
+    fn assert_same_as_full<E: ExactCover>(problem: &E, heuristic: ColumnHeuristic, jobs: &[Job]) {
+        let expected = solutions(Solver::with_heuristic(problem, heuristic));
+        let from_jobs: Vec<_> = jobs
+            .iter()
+            .flat_map(|job| solutions(job.solver(problem).unwrap()))
+            .collect();
+
+        assert_eq!(from_jobs, expected);
+    }
+
+    #[test]
+    fn split_by_depth() {
+        let sudoku = Sudoku::new(2, []);
+
+        for depth in 0..4 {
+            let jobs = split_jobs(
+                &sudoku,
+                ColumnHeuristic::default(),
+                SplitLimit::Depth(depth),
+            );
+            assert!(jobs.iter().all(|job| job.forced_rows.len() == depth));
+            assert_same_as_full(&sudoku, ColumnHeuristic::default(), &jobs);
+        }
+
+        let jobs = split_jobs(&sudoku, ColumnHeuristic::default(), SplitLimit::Depth(1));
+        assert_eq!(jobs.len(), 4);
+    }
+
+    #[test]
+    fn split_by_job_count() {
+        let sudoku = Sudoku::new(2, []);
+
+        for target in [1, 5, 17, 100, 10_000] {
+            for heuristic in [
+                ColumnHeuristic::MinimumRemainingValues,
+                ColumnHeuristic::FirstUncovered,
+            ] {
+                let jobs = split_jobs(&sudoku, heuristic, SplitLimit::Jobs(target));
+                assert_same_as_full(&sudoku, heuristic, &jobs);
+            }
+        }
+
+        let jobs = split_jobs(&sudoku, ColumnHeuristic::default(), SplitLimit::Jobs(17));
+        assert!(jobs.len() >= 17);
+
+        // Expanding the whole tree leaves one job per solution
+        let jobs = split_jobs(
+            &sudoku,
+            ColumnHeuristic::default(),
+            SplitLimit::Jobs(10_000),
+        );
+        assert_eq!(jobs.len(), 288);
+        assert!(jobs.iter().all(|job| job.forced_rows.len() == 16));
+    }
+
+    #[test]
+    fn invalid_forced_rows() {
+        let problem = TextProblem::parse("a b c\na b\nb c\nc\n").unwrap();
+
+        assert!(Solver::with_forced_rows(&problem, ColumnHeuristic::default(), &[0, 2]).is_some());
+        // Both rows cover `b`
+        assert!(Solver::with_forced_rows(&problem, ColumnHeuristic::default(), &[0, 1]).is_none());
+        assert!(Solver::with_forced_rows(&problem, ColumnHeuristic::default(), &[3]).is_none());
+
+        let problem = TextProblem::parse("a b c\na b\n\nc\n").unwrap();
+        let mut solver =
+            Solver::with_forced_rows(&problem, ColumnHeuristic::default(), &[1]).unwrap();
+        assert_eq!(solver.next().unwrap().len(), 2);
+        assert!(solver.next().is_none());
+
+        // The forced rows survive a reset
+        solver.reset();
+        assert_eq!(solver.count(), 1);
+    }
+
+    #[test]
+    fn resume_job_from_checkpoint() {
+        let sudoku = Sudoku::new(2, []);
+        let job = &split_jobs(&sudoku, ColumnHeuristic::default(), SplitLimit::Depth(2))[1];
+
+        let mut solver = job.solver(&sudoku).unwrap();
+        let first = solver.next().unwrap();
+        let checkpoint = solver.checkpoint();
+        let rest: Vec<_> = solver.collect();
+
+        let mut resumed = Solver::from_checkpoint(&sudoku, &checkpoint).unwrap();
+        assert!(resumed.by_ref().eq(rest));
+        assert!(job
+            .forced_rows
+            .iter()
+            .all(|row| first.contains(&&sudoku.possibilities[*row])));
+    }
+
+    #[test]
+    fn random_problems() {
+        for seed in 0..if cfg!(miri) { 20 } else { 300 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+
+            for limit in [SplitLimit::Depth(2), SplitLimit::Jobs(6)] {
+                let jobs = split_jobs(&problem, ColumnHeuristic::default(), limit);
+                assert_same_as_full(&problem, ColumnHeuristic::default(), &jobs);
+            }
+        }
+    }
+}
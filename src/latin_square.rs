@@ -3,10 +3,11 @@
 //! each row and exactly once in each column.
 
 use crate::ExactCover;
-use std::collections::HashSet;
+use alloc::{collections::BTreeSet, vec::Vec};
 
 /// Instance of a Latin square puzzle.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct LatinSquare {
     /// The list of possible positions + values that could solve the Latin
     /// square puzzle.
@@ -32,13 +33,13 @@ impl LatinSquare {
             })
             .collect();
 
-        let satisfied: HashSet<_> = filled_values
+        let satisfied: BTreeSet<_> = filled_values
             .iter()
             .copied()
             .flat_map(Possibility::satisfied_constraints)
             .collect();
 
-        let filled_coordinates: HashSet<_> = filled_values
+        let filled_coordinates: BTreeSet<_> = filled_values
             .iter()
             .map(|poss| (poss.row, poss.column))
             .collect();
@@ -81,6 +82,7 @@ impl ExactCover for LatinSquare {
 
 /// A position and value for a box inside of a Latin square puzzle.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Possibility {
     /// The row position of the box.
     ///
@@ -140,6 +142,7 @@ impl Possibility {
 
 /// A condition which must be satisfied in order to solve a Latin square puzzle.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constraint {
     /// A condition that each row should only have a single instance of a
     /// numeric value.
@@ -0,0 +1,371 @@
+//! Exact cover problems whose possibilities and constraints are generated on
+//! demand, instead of being stored in slices.
+//!
+//! An [`ExactCover`] problem has to list all of its possibilities and
+//! constraints up front, and the [`Solver`] checks every pair of them with
+//! [`ExactCover::satisfies`]. For large problems with a regular structure it is
+//! cheaper to describe them by index: a [`LazyExactCover`] problem only says
+//! how many possibilities and constraints there are, and which constraints
+//! each possibility satisfies. The grid of a [`LazySolver`] is built straight
+//! from those lists, and the values of the possibilities are only generated
+//! for the solutions that are returned.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::lazy::{LazyExactCover, LazySolver};
+//!
+//! /// Tilings of a strip of `n` cells with squares and dominoes.
+//! struct Strip(usize);
+//!
+//! impl LazyExactCover for Strip {
+//!     // The first cell and the length of a tile
+//!     type Possibility = (usize, usize);
+//!     type Constraint = usize;
+//!
+//!     fn num_possibilities(&self) -> usize {
+//!         2 * self.0
+//!     }
+//!
+//!     fn possibility(&self, index: usize) -> Self::Possibility {
+//!         (index / 2, 1 + index % 2)
+//!     }
+//!
+//!     fn num_constraints(&self) -> usize {
+//!         self.0
+//!     }
+//!
+//!     fn constraint(&self, index: usize) -> Self::Constraint {
+//!         index
+//!     }
+//!
+//!     fn satisfied_constraints(&self, possibility: usize) -> impl Iterator<Item = usize> + '_ {
+//!         let (start, len) = self.possibility(possibility);
+//!         // A domino on the last cell would stick out, so it covers nothing
+//!         let fits = start + len <= self.0;
+//!
+//!         (start..start + len).filter(move |_| fits)
+//!     }
+//! }
+//!
+//! let mut solver = LazySolver::new(&Strip(10));
+//! assert_eq!(solver.next().unwrap().iter().map(|tile| tile.1).sum::<usize>(), 10);
+//!
+//! // The number of tilings is a Fibonacci number
+//! assert_eq!(LazySolver::new(&Strip(10)).count(), 89);
+//! ```
+//!
+//! [`ExactCover`]: crate::ExactCover
+//! [`ExactCover::satisfies`]: crate::ExactCover::satisfies
+//! [`Solver`]: crate::Solver
+
+use crate::{
+    grid::Grid,
+    search::{Search, Step},
+    ColumnHeuristic, NodeControl, SolverStats,
+};
+use alloc::vec::Vec;
+use core::{fmt, ops::ControlFlow};
+
+/// An instance of an exact cover problem, whose possibilities and constraints
+/// are identified by their index and generated on demand.
+pub trait LazyExactCover {
+    /// The type of values that are elements of a solution to the exact cover
+    /// problem.
+    type Possibility: fmt::Debug;
+
+    /// The type of value that are constraints on a given instance of an exact
+    /// cover problem.
+    type Constraint: fmt::Debug;
+
+    /// Return the number of possibilities of this instance of the problem.
+    fn num_possibilities(&self) -> usize;
+
+    /// Return the possibility at the given index, which is less than
+    /// [`num_possibilities`](LazyExactCover::num_possibilities).
+    fn possibility(&self, index: usize) -> Self::Possibility;
+
+    /// Return the number of constraints of this instance of the problem.
+    fn num_constraints(&self) -> usize;
+
+    /// Return the constraint at the given index, which is less than
+    /// [`num_constraints`](LazyExactCover::num_constraints).
+    fn constraint(&self, index: usize) -> Self::Constraint;
+
+    /// Return the indices of the constraints that are satisfied by the
+    /// possibility at the given index.
+    ///
+    /// Each index must be less than
+    /// [`num_constraints`](LazyExactCover::num_constraints), and may only be
+    /// returned once.
+    fn satisfied_constraints(&self, possibility: usize) -> impl Iterator<Item = usize> + '_;
+
+    /// Return true if the constraint at the given index is optional.
+    ///
+    /// The default implementation makes every constraint primary.
+    fn is_optional(&self, constraint: usize) -> bool {
+        let _ = constraint;
+        false
+    }
+}
+
+/// Build the grid of a lazy problem, asking each possibility for the
+/// constraints it satisfies.
+fn populate_grid<L: LazyExactCover>(problem: &L) -> Grid {
+    let num_constraints = problem.num_constraints();
+    let coordinates = (0..problem.num_possibilities()).flat_map(|row_index| {
+        problem
+            .satisfied_constraints(row_index)
+            .map(move |col_index| {
+                debug_assert!(
+                    col_index < num_constraints,
+                    "constraint index should be in bounds [{col_index:?}]"
+                );
+
+                (row_index + 1, col_index + 1)
+            })
+    });
+    let optional_columns = (0..num_constraints)
+        .filter(|col_index| problem.is_optional(*col_index))
+        .map(|col_index| col_index + 1);
+
+    Grid::with_optional_columns(num_constraints, optional_columns, coordinates)
+}
+
+/// Solver that iteratively returns the solutions to a [`LazyExactCover`]
+/// problem.
+///
+/// The search is the same as the one of a [`Solver`], so the solutions are
+/// found in the same order as for an [`ExactCover`] problem with the same
+/// possibilities and constraints. Lazy problems have no equivalent of
+/// [`ExactCover::is_viable`], so every partial solution is searched.
+///
+/// [`ExactCover`]: crate::ExactCover
+/// [`ExactCover::is_viable`]: crate::ExactCover::is_viable
+/// [`Solver`]: crate::Solver
+pub struct LazySolver<'l, L: LazyExactCover> {
+    problem: &'l L,
+    search: Search,
+}
+
+impl<'l, L> LazySolver<'l, L>
+where
+    L: LazyExactCover,
+{
+    /// Create a new `LazySolver` with the given instance of a lazy exact cover
+    /// problem.
+    pub fn new(problem: &'l L) -> Self {
+        Self::with_heuristic(problem, ColumnHeuristic::default())
+    }
+
+    /// Create a new `LazySolver` with the given instance of a lazy exact cover
+    /// problem, which will use the given heuristic to choose columns.
+    pub fn with_heuristic(problem: &'l L, heuristic: ColumnHeuristic) -> Self {
+        Self {
+            problem,
+            search: Search::new(populate_grid(problem), heuristic),
+        }
+    }
+
+    /// Reset all solver state, so that the search starts over.
+    pub fn reset(&mut self) {
+        self.search.reset(|_, _| true);
+    }
+
+    /// Return statistics about the search performed so far.
+    pub fn stats(&self) -> SolverStats {
+        self.search.stats()
+    }
+
+    /// Call `visit` with the possibility indices of each remaining solution,
+    /// in the order they were chosen, until there are no more solutions or
+    /// `visit` returns [`ControlFlow::Break`].
+    ///
+    /// Unlike the `Iterator` implementation, this doesn't generate the
+    /// possibilities of the solutions.
+    ///
+    /// Returns [`ControlFlow::Break`] if `visit` stopped the search early.
+    pub fn solve_with<F>(&mut self, mut visit: F) -> ControlFlow<()>
+    where
+        F: FnMut(&[usize]) -> ControlFlow<()>,
+    {
+        while self.search.run(u64::MAX, |_, _| NodeControl::Continue) == Step::Solution {
+            visit(self.search.partial_solution())?;
+        }
+
+        ControlFlow::Continue(())
+    }
+}
+
+impl<L: LazyExactCover> fmt::Debug for LazySolver<'_, L> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LazySolver")
+            .field("search", &self.search)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<L> Iterator for LazySolver<'_, L>
+where
+    L: LazyExactCover,
+{
+    type Item = Vec<L::Possibility>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let problem = self.problem;
+        let mut solution = None;
+        let _ = self.solve_with(|row_indices| {
+            solution = Some(
+                row_indices
+                    .iter()
+                    .map(|row_index| problem.possibility(*row_index))
+                    .collect(),
+            );
+            ControlFlow::Break(())
+        });
+
+        solution
+    }
+}
+
+impl<L: LazyExactCover> core::iter::FusedIterator for LazySolver<'_, L> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        queens::{self, NQueens},
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+        text::TextProblem,
+        ExactCover, Solution,
+    };
+
+    /// The `n` queens problem, with the constraints in the same order as
+    /// [`NQueens`].
+    struct LazyQueens(usize);
+
+    impl LazyExactCover for LazyQueens {
+        type Constraint = usize;
+        type Possibility = queens::Possibility;
+
+        fn num_possibilities(&self) -> usize {
+            self.0 * self.0
+        }
+
+        fn possibility(&self, index: usize) -> Self::Possibility {
+            queens::Possibility {
+                row: index / self.0,
+                column: index % self.0,
+            }
+        }
+
+        fn num_constraints(&self) -> usize {
+            2 * self.0 + 2 * (2 * self.0 - 1)
+        }
+
+        fn constraint(&self, index: usize) -> Self::Constraint {
+            index
+        }
+
+        fn satisfied_constraints(&self, possibility: usize) -> impl Iterator<Item = usize> + '_ {
+            let n = self.0;
+            let poss = self.possibility(possibility);
+
+            [
+                poss.row,
+                n + poss.column,
+                2 * n + poss.leading_diagonal(n),
+                2 * n + (2 * n - 1) + poss.trailing_diagonal(),
+            ]
+            .into_iter()
+        }
+
+        fn is_optional(&self, constraint: usize) -> bool {
+            constraint >= 2 * self.0
+        }
+    }
+
+    /// A `TextProblem` without the slices.
+    struct LazyText(TextProblem);
+
+    impl LazyExactCover for LazyText {
+        type Constraint = usize;
+        type Possibility = usize;
+
+        fn num_possibilities(&self) -> usize {
+            self.0.possibilities.len()
+        }
+
+        fn possibility(&self, index: usize) -> Self::Possibility {
+            index
+        }
+
+        fn num_constraints(&self) -> usize {
+            self.0.constraints.len()
+        }
+
+        fn constraint(&self, index: usize) -> Self::Constraint {
+            index
+        }
+
+        fn satisfied_constraints(&self, possibility: usize) -> impl Iterator<Item = usize> + '_ {
+            self.0.possibilities[possibility].items.iter().copied()
+        }
+
+        fn is_optional(&self, constraint: usize) -> bool {
+            self.0.constraints[constraint].optional
+        }
+    }
+
+    #[test]
+    fn lazy_queens() {
+        for n in 1..=7 {
+            let sorted = |solution: &mut Vec<queens::Possibility>| solution.sort();
+
+            let mut expected: Vec<Vec<_>> = NQueens::new(n, [])
+                .solver()
+                .map(|solution| solution.into_iter().copied().collect())
+                .collect();
+            expected.iter_mut().for_each(sorted);
+            expected.sort();
+
+            let mut solutions: Vec<_> = LazySolver::new(&LazyQueens(n)).collect();
+            solutions.iter_mut().for_each(sorted);
+            solutions.sort();
+
+            assert_eq!(solutions, expected, "n = {n}");
+        }
+    }
+
+    #[test]
+    fn random_problems_match_solver() {
+        for seed in 0..if cfg!(miri) { 20 } else { 300 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let expected = reference_solutions(&problem);
+
+            // The search is the same as the one of `Solver`
+            let mut solver = problem.solver();
+            let mut order = Vec::new();
+            let _ = solver.solve_with(|solution| {
+                order.push(solution.row_indices().to_vec());
+                ControlFlow::Continue(())
+            });
+
+            let lazy = LazyText(random_problem(seed, &RandomProblemConfig::random(seed)));
+            let mut lazy_solver = LazySolver::new(&lazy);
+            let mut lazy_order = Vec::new();
+            let _ = lazy_solver.solve_with(|row_indices| {
+                lazy_order.push(row_indices.to_vec());
+                ControlFlow::Continue(())
+            });
+            assert_eq!(lazy_order, order, "seed {seed}");
+            assert_eq!(lazy_solver.stats(), solver.stats(), "seed {seed}");
+
+            lazy_solver.reset();
+            let mut solutions: Vec<_> = lazy_solver.by_ref().map(Solution::new).collect();
+            solutions.sort();
+            assert_eq!(solutions, expected, "seed {seed}");
+            assert!(lazy_solver.next().is_none());
+        }
+    }
+}
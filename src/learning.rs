@@ -0,0 +1,560 @@
+//! Nogood learning for the [`Solver`](crate::Solver), which is enabled with
+//! [`Solver::with_learning`](crate::Solver::with_learning).
+
+use crate::grid::Grid;
+use alloc::{vec, vec::Vec};
+
+/// Configuration of nogood learning for a [`Solver`](crate::Solver).
+///
+/// A nogood is a set of possibilities that can't all be part of a solution.
+/// When a column of the grid runs out of rows, the solver records the
+/// possibilities of the partial solution which removed those rows. When every
+/// branch of a column fails, it combines the nogoods of the branches into a
+/// nogood for the partial solution above them. A partial solution which
+/// contains a recorded nogood is abandoned as soon as the last possibility of
+/// the nogood is added, which prunes the search on problems that run into the
+/// same conflict in many different branches, like hard infeasible instances.
+///
+/// Partial solutions rejected by
+/// [`ExactCover::is_viable`](crate::ExactCover::is_viable) are not explained
+/// by any nogood, so the columns above them are not learned from.
+///
+/// Learning adds some work to every step of the search, so it only pays off
+/// when it prunes enough of the search. Comparing [`SolverStats`] and timings
+/// with and without it on representative instances is the best way to tell.
+///
+/// [`SolverStats`]: crate::SolverStats
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Learning {
+    /// The largest number of nogoods that are kept at once. When there are
+    /// more, the oldest nogood is forgotten.
+    pub max_nogoods: usize,
+    /// The largest number of possibilities in a nogood that is kept, since
+    /// large nogoods rarely apply to another part of the search.
+    pub max_size: usize,
+}
+
+impl Default for Learning {
+    fn default() -> Self {
+        Self {
+            max_nogoods: 4096,
+            max_size: 4,
+        }
+    }
+}
+
+/// The nogoods learned by a `Solver`, and the state needed to explain its
+/// dead ends.
+///
+/// Each nogood watches one of its rows, which is unselected unless the nogood
+/// is violated. Only the nogoods watching a row have to be checked when it is
+/// selected, and since rows are unselected in the reverse order they were
+/// selected, the watches stay valid when the search backtracks.
+///
+/// Rows and columns are numbered like the grid.
+#[derive(Debug, Clone)]
+pub(crate) struct Nogoods {
+    config: Learning,
+
+    // All of the rows of each column, including the removed ones
+    column_rows: Vec<Vec<usize>>,
+    // The selected row which covers each column, or 0 if there is none
+    covered_by: Vec<usize>,
+    // The selected rows in the order they were selected, and the position of
+    // each row in that order, starting at 1, or 0 if it isn't selected
+    order: Vec<usize>,
+    position: Vec<usize>,
+
+    // The nogoods, as sorted rows, in a ring buffer where `next` is the oldest
+    nogoods: Vec<Vec<usize>>,
+    next: usize,
+    // The row watched by each nogood, and the indices of the nogoods which
+    // watch each row
+    watched: Vec<usize>,
+    watches: Vec<Vec<usize>>,
+
+    // Buffers which are reused to avoid allocating at every dead end, and
+    // the bitsets of the conflicts of frames which were popped
+    explanation: Conflict,
+    rows: Vec<usize>,
+    spare: Vec<Vec<u64>>,
+}
+
+impl Nogoods {
+    /// Create an empty set of nogoods for the given grid, where `selected`
+    /// are the rows which are already selected, in order.
+    pub(crate) fn new(
+        config: Learning,
+        grid: &Grid,
+        selected: impl IntoIterator<Item = usize>,
+    ) -> Self {
+        let mut column_rows = vec![Vec::new(); grid.num_columns() + 1];
+        for row in 1..=grid.max_row() {
+            for node in grid.row_nodes(row) {
+                column_rows[grid.node_column(node)].push(row);
+            }
+        }
+
+        let mut nogoods = Self {
+            config,
+
+            column_rows,
+            covered_by: vec![0; grid.num_columns() + 1],
+            order: Vec::new(),
+            position: vec![0; grid.max_row() + 1],
+
+            nogoods: Vec::new(),
+            next: 0,
+            watched: Vec::new(),
+            watches: vec![Vec::new(); grid.max_row() + 1],
+
+            explanation: Conflict::new(),
+            rows: Vec::new(),
+            spare: Vec::new(),
+        };
+        for row in selected {
+            nogoods.select(grid, row);
+        }
+
+        nogoods
+    }
+
+    /// Return the configuration the nogoods were created with.
+    pub(crate) fn config(&self) -> Learning {
+        self.config
+    }
+
+    /// Record that the given row was selected, returning the index of a
+    /// nogood whose rows are now all selected, if there is one.
+    pub(crate) fn select(&mut self, grid: &Grid, row: usize) -> Option<usize> {
+        for node in grid.row_nodes(row) {
+            self.covered_by[grid.node_column(node)] = row;
+        }
+        self.order.push(row);
+        self.position[row] = self.order.len();
+
+        // Move the watches of the row to other unselected rows, keeping the
+        // ones of violated nogoods
+        let mut violated = None;
+        let mut index = 0;
+        while index < self.watches[row].len() {
+            let nogood = self.watches[row][index];
+            let unselected = self.nogoods[nogood]
+                .iter()
+                .find(|other| self.position[**other] == 0);
+
+            match unselected {
+                Some(&other) => {
+                    self.watches[row].swap_remove(index);
+                    self.watches[other].push(nogood);
+                    self.watched[nogood] = other;
+                }
+                None => {
+                    violated.get_or_insert(nogood);
+                    index += 1;
+                }
+            }
+        }
+
+        violated
+    }
+
+    /// Record that the given row was unselected, which must be the last row
+    /// that was selected.
+    pub(crate) fn unselect(&mut self, grid: &Grid, row: usize) {
+        debug_assert_eq!(self.order.last(), Some(&row));
+
+        for node in grid.row_nodes(row) {
+            self.covered_by[grid.node_column(node)] = 0;
+        }
+        self.order.pop();
+        self.position[row] = 0;
+    }
+
+    /// Forget every selected row.
+    pub(crate) fn clear_selection(&mut self) {
+        self.covered_by.fill(0);
+        self.order.clear();
+        self.position.fill(0);
+    }
+
+    /// Return an empty conflict for a new frame.
+    pub(crate) fn conflict(&mut self) -> Conflict {
+        let mut conflict = Conflict::Rows(self.spare.pop().unwrap_or_default());
+        conflict.clear();
+
+        conflict
+    }
+
+    /// Add the selected rows which removed the rows of the given column to
+    /// `conflict`, returning false if some row was removed for another reason.
+    ///
+    /// The `branched` rows of the column were tried by the solver, and are the
+    /// only ones that haven't been removed.
+    fn explain(
+        &self,
+        grid: &Grid,
+        column: usize,
+        branched: usize,
+        conflict: &mut Conflict,
+    ) -> bool {
+        let mut unexplained = 0;
+        for row in &self.column_rows[column] {
+            let cause = grid
+                .row_nodes(*row)
+                .map(|node| self.covered_by[grid.node_column(node)])
+                .find(|cause| *cause != 0);
+
+            match cause {
+                Some(cause) => conflict.insert(self.position[cause]),
+                None => unexplained += 1,
+            }
+        }
+
+        unexplained == branched
+    }
+
+    /// Add the rows of a violated nogood to the conflict of the frame which
+    /// selected the last row.
+    pub(crate) fn learn_violated(&self, nogood: usize, parent: &mut Conflict) {
+        for row in &self.nogoods[nogood] {
+            parent.insert(self.position[*row]);
+        }
+        parent.remove(self.order.len());
+    }
+
+    /// Learn from a column which has no rows left, when `parent` is the
+    /// conflict of the frame which selected the last row. Returns true if a
+    /// nogood was kept.
+    pub(crate) fn learn_dead_end(
+        &mut self,
+        grid: &Grid,
+        column: usize,
+        parent: Option<&mut Conflict>,
+    ) -> bool {
+        let mut explanation = core::mem::replace(&mut self.explanation, Conflict::Unknown);
+        explanation.clear();
+
+        let explained = self.explain(grid, column, 0, &mut explanation);
+        let kept = self.learn(&explanation, explained, parent);
+
+        self.explanation = explanation;
+        kept
+    }
+
+    /// Learn from a frame whose `branched` rows of the given column all
+    /// failed because of `conflict`, when `parent` is the conflict of the
+    /// frame which selected the last row. Returns true if a nogood was kept.
+    pub(crate) fn learn_frame(
+        &mut self,
+        grid: &Grid,
+        column: usize,
+        branched: usize,
+        mut conflict: Conflict,
+        parent: Option<&mut Conflict>,
+    ) -> bool {
+        let explained = self.explain(grid, column, branched, &mut conflict);
+        let kept = self.learn(&conflict, explained, parent);
+        if let Conflict::Rows(words) = conflict {
+            self.spare.push(words);
+        }
+
+        kept
+    }
+
+    /// Record the nogood of a conflict, and pass it on to the parent frame.
+    fn learn(
+        &mut self,
+        conflict: &Conflict,
+        explained: bool,
+        parent: Option<&mut Conflict>,
+    ) -> bool {
+        let words = match conflict {
+            Conflict::Rows(words) if explained => words,
+            _ => {
+                if let Some(parent) = parent {
+                    *parent = Conflict::Unknown;
+                }
+                return false;
+            }
+        };
+
+        if let Some(parent) = parent {
+            parent.merge(conflict, self.order.len());
+        }
+
+        let size: usize = words.iter().map(|word| word.count_ones() as usize).sum();
+        if size == 0 || size > self.config.max_size || self.config.max_nogoods == 0 {
+            return false;
+        }
+
+        let mut rows = core::mem::take(&mut self.rows);
+        rows.clear();
+        rows.extend(
+            conflict
+                .positions()
+                .map(|position| self.order[position - 1]),
+        );
+        let kept = self.record(&rows);
+
+        self.rows = rows;
+        kept
+    }
+
+    /// Keep the given nogood if it is small enough, returning true if it was
+    /// kept.
+    pub(crate) fn record(&mut self, nogood: &[usize]) -> bool {
+        if nogood.is_empty() || nogood.len() > self.config.max_size || self.config.max_nogoods == 0
+        {
+            return false;
+        }
+
+        let index = if self.nogoods.len() < self.config.max_nogoods {
+            self.nogoods.push(Vec::new());
+            self.watched.push(0);
+            self.nogoods.len() - 1
+        } else {
+            let index = self.next;
+            self.next = (self.next + 1) % self.config.max_nogoods;
+            self.watches[self.watched[index]].retain(|watch| *watch != index);
+            index
+        };
+
+        // Watch an unselected row, or else the one which will be unselected
+        // first
+        let watched = nogood
+            .iter()
+            .copied()
+            .find(|row| self.position[*row] == 0)
+            .or_else(|| nogood.iter().copied().max_by_key(|row| self.position[*row]))
+            .unwrap();
+        self.watches[watched].push(index);
+        self.watched[index] = watched;
+
+        let rows = &mut self.nogoods[index];
+        rows.clear();
+        rows.extend_from_slice(nogood);
+        rows.sort_unstable();
+
+        true
+    }
+}
+
+/// What is known about why the rows of a frame failed.
+#[derive(Debug, Clone)]
+pub(crate) enum Conflict {
+    /// The rows tried so far failed because of the rows of the partial
+    /// solution at these positions, as a bitset where bit `i` is position
+    /// `i + 1`.
+    Rows(Vec<u64>),
+    /// Some row led to a solution, or failed for a reason that isn't known.
+    Unknown,
+}
+
+impl Conflict {
+    /// Create a conflict without any rows.
+    pub(crate) fn new() -> Self {
+        Conflict::Rows(Vec::new())
+    }
+
+    fn clear(&mut self) {
+        match self {
+            Conflict::Rows(words) => words.fill(0),
+            Conflict::Unknown => *self = Conflict::new(),
+        }
+    }
+
+    fn insert(&mut self, position: usize) {
+        if let Conflict::Rows(words) = self {
+            let (word, bit) = ((position - 1) / 64, (position - 1) % 64);
+            if word >= words.len() {
+                words.resize(word + 1, 0);
+            }
+            words[word] |= 1 << bit;
+        }
+    }
+
+    fn remove(&mut self, position: usize) {
+        if let Conflict::Rows(words) = self {
+            let (word, bit) = ((position - 1) / 64, (position - 1) % 64);
+            if let Some(word) = words.get_mut(word) {
+                *word &= !(1 << bit);
+            }
+        }
+    }
+
+    /// Add the rows of another conflict, except for the one at `position`.
+    fn merge(&mut self, other: &Conflict, position: usize) {
+        if let (Conflict::Rows(words), Conflict::Rows(other)) = (&mut *self, other) {
+            if words.len() < other.len() {
+                words.resize(other.len(), 0);
+            }
+            for (word, other) in words.iter_mut().zip(other) {
+                *word |= other;
+            }
+            self.remove(position);
+        }
+    }
+
+    fn positions(&self) -> impl Iterator<Item = usize> + '_ {
+        let words = match self {
+            Conflict::Rows(words) => words.as_slice(),
+            Conflict::Unknown => &[],
+        };
+
+        words.iter().enumerate().flat_map(|(index, word)| {
+            (0..64)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| index * 64 + bit + 1)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        latin_square::{LatinSquare, Possibility},
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+        text, ExactCover, Solution, Solver,
+    };
+    use core::ops::ControlFlow;
+
+    /// A completion of a 6x6 latin square which has no solutions.
+    fn infeasible_latin_square() -> LatinSquare {
+        let filled = [
+            (1, 2, 5),
+            (0, 2, 4),
+            (2, 2, 6),
+            (4, 5, 2),
+            (2, 0, 3),
+            (5, 1, 3),
+            (2, 3, 2),
+            (4, 4, 3),
+            (4, 1, 1),
+        ]
+        .map(|(row, column, value)| Possibility { row, column, value });
+
+        LatinSquare::new(6, filled)
+    }
+
+    #[test]
+    fn learning_prunes_infeasible_latin_square() {
+        let latin = infeasible_latin_square();
+
+        let mut plain = latin.solver();
+        assert!(plain.next().is_none());
+        let mut learning = latin.solver().with_learning(Learning::default());
+        assert!(learning.next().is_none());
+
+        let (plain, learning) = (plain.stats(), learning.stats());
+        assert!(learning.learned > 0);
+        assert!(learning.pruned > 0);
+        assert!(
+            learning.nodes < plain.nodes,
+            "{} nodes with learning, {} without",
+            learning.nodes,
+            plain.nodes
+        );
+    }
+
+    #[test]
+    fn nogood_of_forced_rows_survives_reset() {
+        // Once `a b` is forced, `d` and `e` both need `c`
+        let problem = text::TextProblem::parse("a b c d e\na b\na\nc d\nc e\nb d\nb e\n").unwrap();
+        let mut solver = Solver::with_forced_rows(&problem, Default::default(), &[0])
+            .unwrap()
+            .with_learning(Learning::default());
+        assert!(solver.next().is_none());
+        let first = solver.stats();
+        assert!(first.nodes > 0);
+        assert!(first.learned > 0);
+
+        // The learned nogood is only made of the forced row, so the search ends
+        // before trying any row
+        solver.reset();
+        assert!(solver.next().is_none());
+        assert_eq!(solver.stats().nodes, 0);
+        assert_eq!(solver.stats().pruned, 1);
+    }
+
+    #[test]
+    fn random_problems_match_solver() {
+        for seed in 0..if cfg!(miri) { 20 } else { 300 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let config = Learning {
+                max_nogoods: 1 + (seed as usize % 16),
+                max_size: seed as usize % 6,
+            };
+
+            // Nogoods only cut off branches without solutions, so the solutions
+            // are found in the same order
+            let order = |mut solver: crate::Solver<'_, _>| {
+                let mut order = Vec::new();
+                let _ = solver.solve_with(|solution| {
+                    order.push(solution.row_indices().to_vec());
+                    ControlFlow::Continue(())
+                });
+                (order, solver.stats())
+            };
+            let (expected, plain) = order(problem.solver());
+            let (solutions, learning) = order(problem.solver().with_learning(config));
+            assert_eq!(solutions, expected, "seed {seed}");
+            assert!(learning.nodes <= plain.nodes, "seed {seed}");
+
+            // Learning can start in the middle of a search, and the nogoods
+            // are kept when the solver is reset
+            let mut solver = problem.solver();
+            let to_solution = |solution: Vec<&text::Possibility>| {
+                Solution::new(solution.iter().map(|poss| poss.index))
+            };
+            let first = solver.next().map(to_solution);
+            let mut solver = solver.with_learning(config);
+            let mut solutions: Vec<_> = first
+                .into_iter()
+                .chain(solver.by_ref().map(to_solution))
+                .collect();
+            solutions.sort();
+            assert_eq!(solutions, reference_solutions(&problem), "seed {seed}");
+
+            solver.reset();
+            assert_eq!(solver.count(), expected.len(), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn memory_is_bounded() {
+        let latin = infeasible_latin_square();
+        let grid = crate::Solver::populate_grid(&latin);
+        let mut nogoods = Nogoods::new(
+            Learning {
+                max_nogoods: 2,
+                max_size: 2,
+            },
+            &grid,
+            [],
+        );
+
+        assert!(!nogoods.record(&[1, 2, 3]));
+        assert!(nogoods.record(&[1, 2]));
+        assert!(nogoods.record(&[2, 3]));
+        assert!(nogoods.record(&[3, 4]));
+        assert!(nogoods.record(&[5]));
+
+        // The oldest nogoods were forgotten
+        assert_eq!(nogoods.nogoods, [vec![3, 4], vec![5]]);
+        assert!(nogoods.watches[1].is_empty());
+        assert!(nogoods.watches[2].is_empty());
+        assert_eq!(nogoods.watches[3], [0]);
+        assert_eq!(nogoods.watches[5], [1]);
+
+        // A nogood is violated once all of its rows are selected, and its
+        // watch moves to the rows which aren't
+        assert_eq!(nogoods.select(&grid, 3), None);
+        assert_eq!(nogoods.watches[4], [0]);
+        assert_eq!(nogoods.select(&grid, 4), Some(0));
+        nogoods.unselect(&grid, 4);
+        assert_eq!(nogoods.select(&grid, 5), Some(1));
+    }
+}
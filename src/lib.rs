@@ -1,18 +1,57 @@
 #![deny(missing_docs)]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
 //! Implementation of [Dancing Links](https://en.wikipedia.org/wiki/Dancing_Links)
 //! and [Algorithm X](https://en.wikipedia.org/wiki/Knuth%27s_Algorithm_X) for solving
 //!  [exact cover](https://en.wikipedia.org/wiki/Exact_cover) problems.
+//!
+//! # Features
+//!
+//!  - `std` (enabled by default): implement `std::error::Error` for error
+//!    types, and enable the [`sat`] and [`lp`] modules, which write to
+//!    `std::io` outputs. Without it the crate is `#![no_std]`, and only needs
+//!    the `alloc` crate.
+//!  - `serde`: derive `Serialize` and `Deserialize` for the puzzle types,
+//!    [`Solution`], [`Checkpoint`] and the solver configuration.
+//!  - `testing`: enable the `testing` module, with a generator of random
+//!    problems and a naive reference solver to check solvers against.
 
+extern crate alloc;
+
+pub mod analysis;
+pub mod backbone;
+pub mod batch;
+pub mod bitset;
+pub mod combinators;
+pub mod components;
+pub mod dot;
 pub mod grid;
+pub mod jobs;
 pub mod latin_square;
+pub mod lazy;
+pub(crate) mod learning;
+#[cfg(feature = "std")]
+pub mod lp;
+pub mod ordered;
+pub mod packing;
 pub mod polyomino;
 pub mod queens;
+pub mod restarts;
+#[cfg(feature = "std")]
+pub mod sat;
+pub(crate) mod search;
 pub(crate) mod solver;
 pub mod sudoku;
+#[cfg(any(feature = "testing", test))]
+pub mod testing;
+pub mod text;
 pub(crate) mod util;
 
-pub use solver::Solver;
+pub use learning::Learning;
+pub use solver::{
+    Checkpoint, ColumnHeuristic, NodeControl, PartialSolution, Solution, SolutionContext, Solver,
+    SolverObserver, SolverStats,
+};
 
 /// An instance of an exact cover problem.
 pub trait ExactCover {
@@ -38,6 +77,31 @@ pub trait ExactCover {
     /// the problem.
     fn constraints(&self) -> &[Self::Constraint];
 
+    /// Return false if the given partial solution should be abandoned, because
+    /// it breaks a condition of the problem that isn't expressed by the
+    /// constraints, like the sum of the values in a Killer Sudoku cage.
+    ///
+    /// This is called every time a possibility is added to a partial solution,
+    /// including the last possibility of a solution, so returning false for a
+    /// complete solution rejects it. The possibilities forced with
+    /// [`Solver::with_forced_rows`] are also added one at a time, so only the
+    /// last possibility of `partial` is new, and the rest were accepted
+    /// before. If a partial solution is rejected, all of the solutions that
+    /// extend it must be rejected too, since the search won't look at them.
+    ///
+    /// The default implementation accepts every partial solution. The hook is
+    /// used by every solver in this crate, except for
+    /// [`MaxCoverSolver`](packing::MaxCoverSolver), and the
+    /// [`LazySolver`](lazy::LazySolver) of problems which don't implement
+    /// `ExactCover` at all.
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool
+    where
+        Self: Sized,
+    {
+        let _ = partial;
+        true
+    }
+
     /// Return an iterator over all solutions to this instance of the exact
     /// cover problem.
     fn solver(&self) -> Solver<'_, Self>
@@ -47,3 +111,28 @@ pub trait ExactCover {
         Solver::new(self)
     }
 }
+
+impl<E: ExactCover> ExactCover for &E {
+    type Constraint = E::Constraint;
+    type Possibility = E::Possibility;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        (**self).satisfies(poss, cons)
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        (**self).is_optional(cons)
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        (**self).possibilities()
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        (**self).constraints()
+    }
+
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+        (**self).is_viable(&partial.part(*self, 0, 0))
+    }
+}
@@ -0,0 +1,634 @@
+//! Conversion of exact cover problems into 0/1 integer linear programs, in
+//! CPLEX LP and MPS formats.
+//!
+//! The model is the classic set partitioning formulation. Every possibility at
+//! index `i` of [`ExactCover::possibilities`] becomes a binary variable `x{i}`,
+//! and every constraint at index `j` of [`ExactCover::constraints`] becomes a
+//! row `c{j}` which sums the variables of the possibilities satisfying it.
+//! Primary constraints must sum to exactly 1, and optional constraints to at
+//! most 1.
+//!
+//! Like a [`Solver`](crate::Solver), the program never selects a possibility
+//! which satisfies no primary constraint: each of them gets a row `z{i}` which
+//! fixes its variable to 0. A problem without any primary constraint has no
+//! solutions, so its program gets a row `infeas` which can't be satisfied.
+
+use crate::ExactCover;
+use std::io;
+
+/// The direction of optimization for the objective function.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ObjectiveSense {
+    /// Find a solution with the lowest objective value.
+    #[default]
+    Minimize,
+    /// Find a solution with the highest objective value.
+    Maximize,
+}
+
+/// A 0/1 integer linear program derived from an exact cover problem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntegerProgram {
+    num_variables: usize,
+    // The variable indices which appear in each row, in increasing order
+    rows: Vec<Vec<usize>>,
+    optional: Vec<bool>,
+    // The variables of the possibilities which satisfy no primary constraint
+    fixed: Vec<usize>,
+    has_primary: bool,
+    sense: ObjectiveSense,
+    objective: Vec<f64>,
+}
+
+impl IntegerProgram {
+    /// Convert an exact cover problem to an integer program with a zero
+    /// objective, so that any optimal solution is just a feasible one.
+    pub fn from_problem<E: ExactCover>(problem: &E) -> Self {
+        let possibilities = problem.possibilities();
+        let constraints = problem.constraints();
+
+        let rows: Vec<Vec<usize>> = constraints
+            .iter()
+            .map(|cons| {
+                possibilities
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, poss)| problem.satisfies(poss, cons))
+                    .map(|(idx, _)| idx)
+                    .collect()
+            })
+            .collect();
+        let optional: Vec<bool> = constraints
+            .iter()
+            .map(|cons| problem.is_optional(cons))
+            .collect();
+
+        let mut has_primary = vec![false; possibilities.len()];
+        for (row, _) in rows
+            .iter()
+            .zip(&optional)
+            .filter(|(_, optional)| !**optional)
+        {
+            for var in row {
+                has_primary[*var] = true;
+            }
+        }
+
+        Self {
+            num_variables: possibilities.len(),
+            fixed: (0..possibilities.len())
+                .filter(|var| !has_primary[*var])
+                .collect(),
+            has_primary: optional.iter().any(|optional| !optional),
+            rows,
+            optional,
+            sense: ObjectiveSense::Minimize,
+            objective: vec![0.0; possibilities.len()],
+        }
+    }
+
+    /// Set the objective function to the given coefficients, one per
+    /// possibility in the order of [`ExactCover::possibilities`].
+    ///
+    /// # Panics
+    ///  - If the number of coefficients does not match the number of
+    ///    possibilities.
+    ///  - If any coefficient is not finite.
+    pub fn with_objective(
+        mut self,
+        sense: ObjectiveSense,
+        coefficients: impl IntoIterator<Item = f64>,
+    ) -> Self {
+        self.sense = sense;
+        self.objective = coefficients.into_iter().collect();
+
+        assert_eq!(
+            self.objective.len(),
+            self.num_variables,
+            "There should be exactly one objective coefficient per possibility."
+        );
+        assert!(
+            self.objective
+                .iter()
+                .all(|coefficient| coefficient.is_finite()),
+            "Objective coefficients should be finite."
+        );
+
+        self
+    }
+
+    /// Write the program in CPLEX LP format.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::InvalidInput`] if there are
+    /// rows but no possibilities, since the format can't express a row without
+    /// any variable. Even a problem without constraints has the `infeas` row.
+    pub fn write_lp(&self, mut out: impl io::Write) -> io::Result<()> {
+        // Keep lines well under the 510 character limit of the format
+        const TERMS_PER_LINE: usize = 8;
+
+        if self.num_variables == 0 && (!self.rows.is_empty() || !self.has_primary) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "rows without any possibility can't be written in LP format",
+            ));
+        }
+
+        writeln!(out, "\\ Exact cover problem exported by dancing-links")?;
+        match self.sense {
+            ObjectiveSense::Minimize => writeln!(out, "Minimize")?,
+            ObjectiveSense::Maximize => writeln!(out, "Maximize")?,
+        }
+
+        write!(out, " obj:")?;
+        let mut num_terms = 0;
+        for (var, coefficient) in self.objective.iter().enumerate() {
+            if *coefficient == 0.0 {
+                continue;
+            }
+
+            if num_terms != 0 && num_terms % TERMS_PER_LINE == 0 {
+                write!(out, "\n     ")?;
+            }
+            let sign = if *coefficient < 0.0 { '-' } else { '+' };
+            if num_terms == 0 && sign == '+' {
+                write!(out, " {} x{var}", coefficient.abs())?;
+            } else {
+                write!(out, " {sign} {} x{var}", coefficient.abs())?;
+            }
+            num_terms += 1;
+        }
+        if num_terms == 0 && self.num_variables != 0 {
+            // Name a variable with a zero coefficient, which some readers need to
+            // parse the objective. Without variables the objective stays empty.
+            write!(out, " 0 x0")?;
+        }
+        writeln!(out)?;
+
+        writeln!(out, "Subject To")?;
+        for (idx, (row, optional)) in self.rows.iter().zip(&self.optional).enumerate() {
+            write!(out, " c{idx}:")?;
+            for (term_idx, var) in row.iter().enumerate() {
+                if term_idx == 0 {
+                    write!(out, " x{var}")?;
+                } else {
+                    if term_idx % TERMS_PER_LINE == 0 {
+                        write!(out, "\n    ")?;
+                    }
+                    write!(out, " + x{var}")?;
+                }
+            }
+            if row.is_empty() {
+                // There is at least one variable, as checked above
+                write!(out, " 0 x0")?;
+            }
+            writeln!(out, " {} 1", if *optional { "<=" } else { "=" })?;
+        }
+        for var in &self.fixed {
+            writeln!(out, " z{var}: x{var} = 0")?;
+        }
+        if !self.has_primary {
+            // There is at least one variable, as checked above
+            writeln!(out, " infeas: 0 x0 = 1")?;
+        }
+
+        writeln!(out, "Binary")?;
+        for var in 0..self.num_variables {
+            if var != 0 && var % TERMS_PER_LINE == 0 {
+                writeln!(out)?;
+            }
+            write!(out, " x{var}")?;
+        }
+        if self.num_variables != 0 {
+            writeln!(out)?;
+        }
+
+        writeln!(out, "End")
+    }
+
+    /// Write the program in (fixed column) MPS format.
+    ///
+    /// The objective sense is written in an `OBJSENSE` section, which is an
+    /// extension supported by most solvers.
+    pub fn write_mps(&self, mut out: impl io::Write) -> io::Result<()> {
+        writeln!(out, "NAME          EXACTCOVER")?;
+        if self.sense == ObjectiveSense::Maximize {
+            writeln!(out, "OBJSENSE")?;
+            writeln!(out, "    MAX")?;
+        }
+
+        writeln!(out, "ROWS")?;
+        writeln!(out, " N  obj")?;
+        for (idx, optional) in self.optional.iter().enumerate() {
+            writeln!(out, " {}  c{idx}", if *optional { "L" } else { "E" })?;
+        }
+        for var in &self.fixed {
+            writeln!(out, " E  z{var}")?;
+        }
+        if !self.has_primary {
+            writeln!(out, " E  infeas")?;
+        }
+
+        // Transpose the rows so that every variable has its entries together
+        let mut columns = vec![Vec::new(); self.num_variables];
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            for var in row {
+                columns[*var].push(format!("c{row_idx}"));
+            }
+        }
+        for var in &self.fixed {
+            columns[*var].push(format!("z{var}"));
+        }
+
+        writeln!(out, "COLUMNS")?;
+        for (var, column) in columns.iter().enumerate() {
+            let name = format!("x{var}");
+            let coefficient = self.objective[var];
+            // Every variable has an entry in a `c` or `z` row, so it is declared
+            // here before its bounds
+            if coefficient != 0.0 {
+                writeln!(out, "    {name:<8}  {:<8}  {coefficient}", "obj")?;
+            }
+            for row in column {
+                writeln!(out, "    {name:<8}  {row:<8}  1")?;
+            }
+        }
+
+        writeln!(out, "RHS")?;
+        for idx in 0..self.rows.len() {
+            writeln!(out, "    {:<8}  {:<8}  1", "RHS", format!("c{idx}"))?;
+        }
+        // The `z{i}` rows are left at the default of 0
+        if !self.has_primary {
+            writeln!(out, "    {:<8}  {:<8}  1", "RHS", "infeas")?;
+        }
+
+        writeln!(out, "BOUNDS")?;
+        for var in 0..self.num_variables {
+            writeln!(out, " BV {:<8}  x{var}", "BND")?;
+        }
+
+        writeln!(out, "ENDATA")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+        text::TextProblem,
+        Solution,
+    };
+    use std::collections::BTreeMap;
+
+    /// A row read back from a written program: its terms, its relation (`=`
+    /// or `<=`) and its right hand side.
+    type ParsedRow = (Vec<(usize, f64)>, String, f64);
+
+    fn small_problem() -> TextProblem {
+        TextProblem::parse("a b | x\na\nb x\na b\n").unwrap()
+    }
+
+    fn to_string(write: impl FnOnce(&mut Vec<u8>) -> io::Result<()>) -> String {
+        let mut output = Vec::new();
+        write(&mut output).unwrap();
+
+        String::from_utf8(output).unwrap()
+    }
+
+    /// Read the rows of a program written by `write_lp`.
+    fn parse_lp_rows(lp: &str) -> Vec<ParsedRow> {
+        let start = lp.find("Subject To\n").unwrap() + "Subject To\n".len();
+        let end = lp.find("Binary\n").unwrap();
+
+        let mut rows: Vec<ParsedRow> = Vec::new();
+        let mut coefficient = 1.0;
+        let mut relation = None;
+        for token in lp[start..end].split_whitespace() {
+            if token.ends_with(':') {
+                rows.push((Vec::new(), String::new(), 0.0));
+            } else if let Some(var) = token.strip_prefix('x') {
+                let row = rows.last_mut().unwrap();
+                row.0.push((var.parse().unwrap(), coefficient));
+                coefficient = 1.0;
+            } else if token == "=" || token == "<=" {
+                relation = Some(token);
+            } else if token != "+" {
+                let value = token.parse().unwrap();
+                match relation.take() {
+                    Some(relation) => {
+                        let row = rows.last_mut().unwrap();
+                        row.1 = relation.into();
+                        row.2 = value;
+                    }
+                    None => coefficient = value,
+                }
+            }
+        }
+
+        rows
+    }
+
+    /// Read the rows of a program written by `write_mps`.
+    fn parse_mps_rows(mps: &str) -> Vec<ParsedRow> {
+        let mut rows = BTreeMap::new();
+        let mut section = "";
+        for line in mps.lines() {
+            if !line.starts_with(' ') {
+                section = line;
+                continue;
+            }
+
+            let fields: Vec<_> = line.split_whitespace().collect();
+            match section {
+                "ROWS" if fields[0] != "N" => {
+                    let relation = if fields[0] == "E" { "=" } else { "<=" };
+                    rows.insert(fields[1], (Vec::new(), relation.into(), 0.0));
+                }
+                "COLUMNS" if fields[1] != "obj" => {
+                    let var = fields[0].strip_prefix('x').unwrap().parse().unwrap();
+                    let row: &mut ParsedRow = rows.get_mut(fields[1]).unwrap();
+                    row.0.push((var, fields[2].parse().unwrap()));
+                }
+                "RHS" => rows.get_mut(fields[1]).unwrap().2 = fields[2].parse().unwrap(),
+                _ => {}
+            }
+        }
+
+        rows.into_values().collect()
+    }
+
+    /// Return the 0/1 assignments which satisfy all of the rows, by trying
+    /// every assignment.
+    fn feasible_solutions(num_variables: usize, rows: &[ParsedRow]) -> Vec<Solution> {
+        let mut solutions: Vec<_> = (0u64..(1 << num_variables))
+            .filter(|assignment| {
+                rows.iter().all(|(terms, relation, rhs)| {
+                    let sum: f64 = terms
+                        .iter()
+                        .filter(|(var, _)| assignment & (1 << var) != 0)
+                        .map(|(_, coefficient)| coefficient)
+                        .sum();
+                    if relation == "=" {
+                        sum == *rhs
+                    } else {
+                        sum <= *rhs
+                    }
+                })
+            })
+            .map(|assignment| {
+                Solution::new((0..num_variables).filter(|var| assignment & (1 << var) != 0))
+            })
+            .collect();
+        solutions.sort();
+
+        solutions
+    }
+
+    #[test]
+    fn random_programs_match_reference() {
+        for seed in 0..if cfg!(miri) { 10 } else { 100 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let num_variables = problem.possibilities.len();
+            if num_variables > 16 {
+                continue;
+            }
+            let program = IntegerProgram::from_problem(&problem);
+            let mut expected = reference_solutions(&problem);
+            expected.sort();
+
+            let mps = to_string(|out| program.write_mps(out));
+            let mps_rows = parse_mps_rows(&mps);
+            assert_eq!(
+                feasible_solutions(num_variables, &mps_rows),
+                expected,
+                "seed {seed}"
+            );
+
+            if num_variables != 0 {
+                let lp = to_string(|out| program.write_lp(out));
+                let lp_rows = parse_lp_rows(&lp);
+                assert_eq!(
+                    feasible_solutions(num_variables, &lp_rows),
+                    expected,
+                    "seed {seed}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn write_feasibility_lp() {
+        let program = IntegerProgram::from_problem(&small_problem());
+
+        assert_eq!(
+            to_string(|out| program.write_lp(out)),
+            "\\ Exact cover problem exported by dancing-links
+Minimize
+ obj: 0 x0
+Subject To
+ c0: x0 + x2 = 1
+ c1: x1 + x2 = 1
+ c2: x1 <= 1
+Binary
+ x0 x1 x2
+End
+"
+        );
+    }
+
+    #[test]
+    fn write_weighted_lp() {
+        let program = IntegerProgram::from_problem(&small_problem())
+            .with_objective(ObjectiveSense::Maximize, [-1.5, 0.0, 2.0]);
+
+        assert_eq!(
+            to_string(|out| program.write_lp(out)),
+            "\\ Exact cover problem exported by dancing-links
+Maximize
+ obj: - 1.5 x0 + 2 x2
+Subject To
+ c0: x0 + x2 = 1
+ c1: x1 + x2 = 1
+ c2: x1 <= 1
+Binary
+ x0 x1 x2
+End
+"
+        );
+    }
+
+    #[test]
+    fn write_long_lp_rows() {
+        let input = std::iter::once("a\n")
+            .chain(std::iter::repeat("a\n").take(10))
+            .collect::<String>();
+        let program = IntegerProgram::from_problem(&TextProblem::parse(&input).unwrap());
+
+        assert_eq!(
+            to_string(|out| program.write_lp(out)),
+            "\\ Exact cover problem exported by dancing-links
+Minimize
+ obj: 0 x0
+Subject To
+ c0: x0 + x1 + x2 + x3 + x4 + x5 + x6 + x7
+     + x8 + x9 = 1
+Binary
+ x0 x1 x2 x3 x4 x5 x6 x7
+ x8 x9
+End
+"
+        );
+    }
+
+    #[test]
+    fn write_mps() {
+        let program = IntegerProgram::from_problem(&small_problem())
+            .with_objective(ObjectiveSense::Maximize, [-1.5, 0.0, 2.0]);
+
+        assert_eq!(
+            to_string(|out| program.write_mps(out)),
+            "NAME          EXACTCOVER
+OBJSENSE
+    MAX
+ROWS
+ N  obj
+ E  c0
+ E  c1
+ L  c2
+COLUMNS
+    x0        obj       -1.5
+    x0        c0        1
+    x1        c1        1
+    x1        c2        1
+    x2        obj       2
+    x2        c0        1
+    x2        c1        1
+RHS
+    RHS       c0        1
+    RHS       c1        1
+    RHS       c2        1
+BOUNDS
+ BV BND       x0
+ BV BND       x1
+ BV BND       x2
+ENDATA
+"
+        );
+    }
+
+    /// A problem where possibility `i` satisfies constraint `i`, if it exists.
+    struct Diagonal {
+        possibilities: Vec<usize>,
+        constraints: Vec<usize>,
+    }
+
+    impl ExactCover for Diagonal {
+        type Possibility = usize;
+        type Constraint = usize;
+
+        fn satisfies(&self, poss: &usize, cons: &usize) -> bool {
+            poss == cons
+        }
+
+        fn is_optional(&self, _: &usize) -> bool {
+            false
+        }
+
+        fn possibilities(&self) -> &[usize] {
+            &self.possibilities
+        }
+
+        fn constraints(&self) -> &[usize] {
+            &self.constraints
+        }
+    }
+
+    #[test]
+    fn write_empty_programs() {
+        let no_possibilities = Diagonal {
+            possibilities: vec![],
+            constraints: vec![0],
+        };
+        let program = IntegerProgram::from_problem(&no_possibilities);
+        let error = program.write_lp(Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+
+        let empty = Diagonal {
+            possibilities: vec![],
+            constraints: vec![],
+        };
+        let program = IntegerProgram::from_problem(&empty);
+        let error = program.write_lp(Vec::new()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(
+            to_string(|out| program.write_mps(out)),
+            "NAME          EXACTCOVER
+ROWS
+ N  obj
+ E  infeas
+COLUMNS
+RHS
+    RHS       infeas    1
+BOUNDS
+ENDATA
+"
+        );
+    }
+
+    #[test]
+    fn write_unused_variable() {
+        // `x1` satisfies no constraint, and has no cost
+        let problem = Diagonal {
+            possibilities: vec![0, 1],
+            constraints: vec![0],
+        };
+        let program = IntegerProgram::from_problem(&problem);
+
+        assert_eq!(
+            to_string(|out| program.write_lp(out)),
+            "\\ Exact cover problem exported by dancing-links
+Minimize
+ obj: 0 x0
+Subject To
+ c0: x0 = 1
+ z1: x1 = 0
+Binary
+ x0 x1
+End
+"
+        );
+        assert_eq!(
+            to_string(|out| program.write_mps(out)),
+            "NAME          EXACTCOVER
+ROWS
+ N  obj
+ E  c0
+ E  z1
+COLUMNS
+    x0        c0        1
+    x1        z1        1
+RHS
+    RHS       c0        1
+BOUNDS
+ BV BND       x0
+ BV BND       x1
+ENDATA
+"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Objective coefficients should be finite.")]
+    fn objective_not_finite() {
+        let _program = IntegerProgram::from_problem(&small_problem())
+            .with_objective(ObjectiveSense::Minimize, [1.0, f64::NAN, f64::INFINITY]);
+    }
+
+    #[test]
+    #[should_panic(expected = "There should be exactly one objective coefficient per possibility.")]
+    fn objective_length_mismatch() {
+        let _program = IntegerProgram::from_problem(&small_problem())
+            .with_objective(ObjectiveSense::Minimize, [1.0]);
+    }
+}
@@ -0,0 +1,389 @@
+//! Solver which returns the solutions of an exact cover problem in a fixed,
+//! well-defined order.
+//!
+//! The order of the solutions from a [`Solver`] depends on the column
+//! heuristic and on the order of the links in the grid, which are
+//! implementation details. An [`OrderedSolver`] instead ranks every
+//! possibility, lists the possibilities of each solution from best to worst
+//! rank, and returns the solutions in lexicographic order of those lists. The
+//! first solution contains the best ranked possibility that appears in any
+//! solution, then the best ranked possibility that can be added to it, and so
+//! on.
+//!
+//! By default the possibilities are ranked by their index in
+//! [`ExactCover::possibilities`], so the order only depends on the problem.
+//!
+//! The search branches on including or excluding one possibility at a time, so
+//! it usually explores more nodes than a [`Solver`] to enumerate every
+//! solution.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{ordered::OrderedSolver, text::TextProblem};
+//!
+//! let problem = TextProblem::parse("a b c\nb c\na\nc\nb\na b\n").unwrap();
+//! let indices = |solution: Vec<_>| {
+//!     solution
+//!         .into_iter()
+//!         .map(|poss: &dancing_links::text::Possibility| poss.index)
+//!         .collect::<Vec<_>>()
+//! };
+//!
+//! let solutions: Vec<_> = OrderedSolver::new(&problem).map(indices).collect();
+//! assert_eq!(solutions, [vec![0, 1], vec![1, 2, 3], vec![2, 4]]);
+//!
+//! // Prefer the possibilities which cover the most items
+//! let solutions: Vec<_> = OrderedSolver::with_priority(&problem, |poss| {
+//!     core::cmp::Reverse(poss.items.len())
+//! })
+//! .map(indices)
+//! .collect();
+//! assert_eq!(solutions, [vec![0, 1], vec![4, 2], vec![1, 2, 3]]);
+//! ```
+
+use crate::{
+    grid::{Grid, Snapshot},
+    ExactCover, PartialSolution, Solver, SolverStats,
+};
+use alloc::vec::Vec;
+
+/// Solver that iteratively returns the solutions to an exact cover problem in
+/// order of the rank of their possibilities.
+///
+/// See the [module documentation](self) for the exact order.
+#[derive(Debug, Clone)]
+pub struct OrderedSolver<'e, E: ExactCover> {
+    problem: &'e E,
+    grid: Grid,
+
+    // The rows of the grid which cover at least one primary column, from best to
+    // worst rank
+    order: Vec<usize>,
+
+    partial_solution: Vec<usize>,
+    stack: Vec<Frame>,
+    started: bool,
+
+    stats: SolverStats,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Branch {
+    // The row of the frame is about to be included
+    Include,
+    // The row of the frame is about to be excluded
+    Exclude,
+    // Both branches have been explored
+    Done,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    // The position in `order` of the row to branch on
+    position: usize,
+    snapshot: Snapshot,
+    branch: Branch,
+}
+
+/// The result of checking the grid before branching.
+enum Step {
+    Solution,
+    DeadEnd,
+    Branch(usize),
+}
+
+impl<'e, E> OrderedSolver<'e, E>
+where
+    E: ExactCover,
+{
+    /// Create a new `OrderedSolver` which ranks the possibilities by their
+    /// index, returning the solutions in lexicographic order of their
+    /// possibility indices.
+    pub fn new(problem: &'e E) -> Self {
+        Self::with_priority(problem, |_| ())
+    }
+
+    /// Create a new `OrderedSolver` which ranks the possibilities by the given
+    /// key, from smallest to largest.
+    ///
+    /// Possibilities with equal keys are ranked by their index, so the order
+    /// of the solutions is fully determined by the problem and the keys.
+    pub fn with_priority<K: Ord>(
+        problem: &'e E,
+        mut key: impl FnMut(&E::Possibility) -> K,
+    ) -> Self {
+        let grid = Solver::populate_grid(problem);
+
+        let keys: Vec<_> = problem.possibilities().iter().map(&mut key).collect();
+        let mut order: Vec<_> = (1..=grid.max_row())
+            .filter(|row| {
+                grid.row_nodes(*row)
+                    .any(|node| !grid.is_column_optional(grid.node_column(node)))
+            })
+            .collect();
+        // The sort is stable, so equal keys stay in index order
+        order.sort_by(|a, b| keys[a - 1].cmp(&keys[b - 1]));
+
+        Self {
+            problem,
+            grid,
+
+            order,
+
+            partial_solution: Vec::new(),
+            stack: Vec::new(),
+            started: false,
+
+            stats: SolverStats::default(),
+        }
+    }
+
+    /// Return statistics about the search so far.
+    ///
+    /// The `nodes` count the possibilities that were included in a partial
+    /// solution, and the `dead_ends` count the partial solutions that were
+    /// abandoned because some constraint could no longer be covered.
+    pub fn stats(&self) -> SolverStats {
+        self.stats
+    }
+
+    /// Decide what to do at the current position of the search, where every
+    /// row before `position` in the order has been either included or
+    /// excluded.
+    fn step(&mut self, position: usize) -> Step {
+        if self.grid.is_primary_empty() {
+            self.stats.solutions += 1;
+            return Step::Solution;
+        }
+
+        let stuck = self
+            .grid
+            .uncovered_primary_columns()
+            .any(|column| self.grid.column_size(column) == 0);
+        if stuck {
+            self.stats.dead_ends += 1;
+            return Step::DeadEnd;
+        }
+
+        // The remaining rows of the uncovered columns all come after `position`, so
+        // the next row whose columns are all uncovered can be included
+        let next = (position..self.order.len()).find(|position| {
+            self.grid
+                .row_nodes(self.order[*position])
+                .all(|node| !self.grid.is_column_covered(self.grid.node_column(node)))
+        });
+
+        match next {
+            Some(position) => Step::Branch(position),
+            None => {
+                self.stats.dead_ends += 1;
+                Step::DeadEnd
+            }
+        }
+    }
+
+    fn push_frame(&mut self, position: usize) {
+        self.stack.push(Frame {
+            position,
+            snapshot: self.grid.snapshot(),
+            branch: Branch::Include,
+        });
+    }
+
+    /// Run the search until the next solution is found, returning `true` with
+    /// the solution left in `partial_solution`, or `false` if there are no more
+    /// solutions.
+    fn advance(&mut self) -> bool {
+        if !self.started {
+            self.started = true;
+
+            // Like `Solver`, a problem without primary constraints has no solutions
+            if self.grid.is_primary_empty() {
+                return false;
+            }
+            if let Step::Branch(position) = self.step(0) {
+                self.push_frame(position);
+            }
+        }
+
+        while let Some(frame) = self.stack.last_mut() {
+            let Frame {
+                position,
+                snapshot,
+                branch,
+            } = *frame;
+            let row = self.order[position];
+
+            let step = match branch {
+                Branch::Include => {
+                    frame.branch = Branch::Exclude;
+
+                    let node = self.grid.row_nodes(row).next().unwrap();
+                    self.grid.select_row(node);
+                    self.partial_solution.push(row - 1);
+                    self.stats.nodes += 1;
+
+                    let partial = PartialSolution::from_grid(
+                        self.problem,
+                        &self.partial_solution,
+                        &self.grid,
+                    );
+                    if self.problem.is_viable(&partial) {
+                        self.step(position + 1)
+                    } else {
+                        self.stats.pruned += 1;
+                        Step::DeadEnd
+                    }
+                }
+                Branch::Exclude => {
+                    frame.branch = Branch::Done;
+
+                    self.grid.restore(snapshot);
+                    self.partial_solution.pop();
+                    self.grid.hide_row(row);
+
+                    self.step(position + 1)
+                }
+                Branch::Done => {
+                    self.grid.restore(snapshot);
+                    self.stack.pop();
+
+                    continue;
+                }
+            };
+
+            match step {
+                Step::Solution => return true,
+                Step::DeadEnd => {}
+                Step::Branch(position) => self.push_frame(position),
+            }
+        }
+
+        false
+    }
+}
+
+impl<'e, E> Iterator for OrderedSolver<'e, E>
+where
+    E: ExactCover,
+{
+    type Item = Vec<&'e E::Possibility>;
+
+    /// Return the next solution, with its possibilities from best to worst
+    /// rank.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.advance() {
+            let possibilities = self.problem.possibilities();
+
+            Some(
+                self.partial_solution
+                    .iter()
+                    .map(|index| &possibilities[*index])
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+}
+
+impl<E: ExactCover> core::iter::FusedIterator for OrderedSolver<'_, E> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        queens::NQueens,
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+        Solution,
+    };
+
+    const SEEDS: u64 = if cfg!(miri) { 20 } else { 300 };
+
+    #[test]
+    fn lexicographic_matches_reference() {
+        for seed in 0..SEEDS {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+
+            let solutions: Vec<_> = OrderedSolver::new(&problem)
+                .map(|solution| {
+                    let indices: Vec<_> = solution.iter().map(|poss| poss.index).collect();
+                    // The possibilities are listed in rank order, which is index order
+                    assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+
+                    Solution::new(indices)
+                })
+                .collect();
+
+            assert_eq!(solutions, reference_solutions(&problem), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn priority_matches_reference() {
+        for seed in 0..SEEDS {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            // Rank by the number of items, breaking ties by index
+            let rank = |index: usize| (problem.possibilities[index].items.len(), index);
+
+            let mut solver = OrderedSolver::with_priority(&problem, |poss| poss.items.len());
+            let solutions: Vec<_> = solver
+                .by_ref()
+                .map(|solution| {
+                    solution
+                        .iter()
+                        .map(|poss| rank(poss.index))
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+
+            let mut expected: Vec<Vec<_>> = reference_solutions(&problem)
+                .into_iter()
+                .map(|solution| {
+                    let mut ranks: Vec<_> = solution.possibilities.into_iter().map(rank).collect();
+                    ranks.sort();
+                    ranks
+                })
+                .collect();
+            expected.sort();
+
+            assert_eq!(solutions, expected, "seed {seed}");
+            assert_eq!(solver.stats().solutions, expected.len() as u64);
+        }
+    }
+
+    #[test]
+    fn queens_in_order() {
+        let queens = NQueens::new(6, []);
+        let mut expected: Vec<_> = queens
+            .solver()
+            .map(|mut solution| {
+                solution.sort();
+                solution
+            })
+            .collect();
+        expected.sort();
+
+        // Ranking the possibilities by their own order lists each solution sorted
+        let solutions: Vec<_> = OrderedSolver::with_priority(&queens, |poss| *poss).collect();
+        assert_eq!(solutions, expected);
+
+        // With the opposite ranking, the first solution has the largest possibility
+        // of any solution
+        let reversed: Vec<_> =
+            OrderedSolver::with_priority(&queens, |poss| core::cmp::Reverse(*poss)).collect();
+        let largest = expected.iter().flatten().max().unwrap();
+        assert_eq!(reversed[0][0], *largest);
+
+        let mut reversed: Vec<_> = reversed
+            .into_iter()
+            .map(|mut solution| {
+                solution.sort();
+                solution
+            })
+            .collect();
+        reversed.sort();
+        assert_eq!(reversed, expected);
+    }
+}
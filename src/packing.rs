@@ -0,0 +1,456 @@
+//! Solver for the maximum partial cover of an exact cover problem, which is
+//! useful when the problem has no exact cover.
+//!
+//! A partial cover is a set of possibilities which never cover any constraint
+//! more than once, but may leave some of the primary constraints uncovered.
+//! The best partial cover is the one with the largest total weight of covered
+//! primary constraints, where by default every primary constraint has weight
+//! 1. If the problem has an exact cover, it is always a best partial cover.
+//!
+//! The search is a branch and bound over the same [`Grid`] as the
+//! [`Solver`]: each primary constraint is either covered by one
+//! of its possibilities, or left uncovered, and a branch is abandoned as soon
+//! as covering every remaining constraint could not beat the best partial
+//! cover found so far.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{packing::MaxCoverSolver, text::TextProblem};
+//!
+//! // There is no exact cover, since `b` appears in both options
+//! let problem = TextProblem::parse("a b c\na b\nb c\n").unwrap();
+//!
+//! let best = MaxCoverSolver::new(&problem).solve();
+//! assert_eq!(best.weight, 2);
+//! assert_eq!(best.possibilities[0].items, [0, 1]);
+//! assert_eq!(best.uncovered[0].name, "c");
+//!
+//! // Make `c` more valuable than the other two constraints together
+//! let best = MaxCoverSolver::with_weights(&problem, |cons| if cons.name == "c" { 3 } else { 1 })
+//!     .solve();
+//! assert_eq!(best.weight, 4);
+//! assert_eq!(best.possibilities[0].items, [1, 2]);
+//! assert_eq!(best.uncovered[0].name, "a");
+//! ```
+//!
+//! [`Grid`]: crate::grid::Grid
+
+use crate::{
+    grid::{Grid, Node, Snapshot},
+    ColumnHeuristic, ExactCover, Solver,
+};
+use alloc::vec::Vec;
+
+/// The best partial cover found by a [`MaxCoverSolver`].
+#[derive(Debug)]
+pub struct PartialCover<'e, E: ExactCover> {
+    /// The possibilities in the partial cover, in the order they were chosen.
+    pub possibilities: Vec<&'e E::Possibility>,
+    /// The primary constraints which are not covered by any of the
+    /// possibilities, in the order of [`ExactCover::constraints`].
+    pub uncovered: Vec<&'e E::Constraint>,
+    /// The total weight of the covered primary constraints.
+    pub weight: u64,
+}
+
+impl<E: ExactCover> PartialCover<'_, E> {
+    /// Return true if every primary constraint is covered, so that this is an
+    /// exact cover.
+    pub fn is_exact(&self) -> bool {
+        self.uncovered.is_empty()
+    }
+}
+
+/// Solver that finds a partial cover of an exact cover problem with the
+/// largest weight.
+#[derive(Debug)]
+pub struct MaxCoverSolver<'e, E: ExactCover> {
+    problem: &'e E,
+    grid: Grid,
+
+    // The weight of each column, indexed like the grid, with 0 for optional columns
+    column_weights: Vec<u64>,
+    // The total weight of the primary columns covered by each row, indexed like the
+    // grid
+    row_weights: Vec<u64>,
+
+    partial_solution: Vec<usize>,
+    weight: u64,
+    best_solution: Vec<usize>,
+    best_weight: u64,
+}
+
+/// A primary column whose rows are being tried, in the search of a
+/// [`MaxCoverSolver`].
+#[derive(Debug)]
+struct Frame {
+    column: usize,
+    rows: Vec<Node>,
+    next_row: usize,
+    // The row selected by the current branch, if it isn't the last one
+    selected_row: Option<usize>,
+    // True once the last branch, which leaves the column uncovered, has started
+    skipped: bool,
+    // The grid before any of the branches
+    snapshot: Snapshot,
+}
+
+impl<'e, E> MaxCoverSolver<'e, E>
+where
+    E: ExactCover,
+{
+    /// Create a new `MaxCoverSolver` which maximizes the number of covered
+    /// primary constraints.
+    pub fn new(problem: &'e E) -> Self {
+        Self::with_weights(problem, |_| 1)
+    }
+
+    /// Create a new `MaxCoverSolver` which maximizes the total weight of the
+    /// covered primary constraints, using the given weight for each
+    /// constraint.
+    ///
+    /// The weights of optional constraints are ignored.
+    ///
+    /// # Panics
+    ///
+    /// If the total weight of the primary constraints doesn't fit in a `u64`.
+    /// Every weight computed by the search is at most this total, so it can't
+    /// overflow.
+    pub fn with_weights(problem: &'e E, mut weight: impl FnMut(&E::Constraint) -> u64) -> Self {
+        let grid = Solver::populate_grid(problem);
+
+        let column_weights: Vec<_> = core::iter::once(0)
+            .chain(problem.constraints().iter().map(|cons| {
+                if problem.is_optional(cons) {
+                    0
+                } else {
+                    weight(cons)
+                }
+            }))
+            .collect();
+        let total_weight = column_weights
+            .iter()
+            .try_fold(0u64, |total, weight| total.checked_add(*weight));
+        assert!(
+            total_weight.is_some(),
+            "The total weight of the primary constraints should fit in a u64."
+        );
+        let row_weights = (0..=grid.max_row())
+            .map(|row| {
+                grid.row_nodes(row)
+                    .map(|node| column_weights[grid.node_column(node)])
+                    .sum()
+            })
+            .collect();
+
+        Self {
+            problem,
+            grid,
+
+            column_weights,
+            row_weights,
+
+            partial_solution: Vec::new(),
+            weight: 0,
+            best_solution: Vec::new(),
+            best_weight: 0,
+        }
+    }
+
+    /// Run the search and return the best partial cover.
+    ///
+    /// If there are several partial covers with the largest weight, the first
+    /// one found is returned.
+    pub fn solve(mut self) -> PartialCover<'e, E> {
+        self.search();
+
+        let possibilities = self.problem.possibilities();
+        let mut covered = alloc::vec![false; self.problem.constraints().len()];
+        for row in &self.best_solution {
+            for node in self.grid.row_nodes(row + 1) {
+                covered[self.grid.node_column(node) - 1] = true;
+            }
+        }
+
+        PartialCover {
+            possibilities: self
+                .best_solution
+                .iter()
+                .map(|row| &possibilities[*row])
+                .collect(),
+            uncovered: self
+                .problem
+                .constraints()
+                .iter()
+                .zip(covered)
+                .filter(|(cons, covered)| !covered && !self.problem.is_optional(cons))
+                .map(|(cons, _)| cons)
+                .collect(),
+            weight: self.best_weight,
+        }
+    }
+
+    /// Extend the current partial cover in every way that could improve on the
+    /// best one.
+    ///
+    /// Each level of the search covers at least one column, so it can be as
+    /// deep as there are columns. The levels are kept on an explicit stack
+    /// rather than the call stack, like in [`Solver`].
+    fn search(&mut self) {
+        let mut stack: Vec<Frame> = Vec::new();
+
+        loop {
+            if let Some(frame) = self.branch() {
+                stack.push(frame);
+            }
+
+            // Move on to the next branch of the deepest frame which has one left
+            loop {
+                let Some(frame) = stack.last_mut() else {
+                    return;
+                };
+                self.grid.restore(frame.snapshot);
+                if let Some(row) = frame.selected_row.take() {
+                    self.weight -= self.row_weights[row];
+                    self.partial_solution.pop();
+                }
+
+                if let Some(node) = frame.rows.get(frame.next_row).copied() {
+                    frame.next_row += 1;
+                    let row = self.grid.node_row(node);
+                    self.grid.select_row(node);
+                    self.partial_solution.push(row - 1);
+                    self.weight += self.row_weights[row];
+                    frame.selected_row = Some(row);
+                    break;
+                } else if !frame.skipped {
+                    // Leave the column uncovered, which also removes its rows from
+                    // the other columns
+                    frame.skipped = true;
+                    self.grid.cover_column(frame.column);
+                    break;
+                } else {
+                    stack.pop();
+                }
+            }
+        }
+    }
+
+    /// Record the current partial cover if it is the best one so far, and
+    /// return a frame to extend it, unless it can't improve on the best one.
+    fn branch(&mut self) -> Option<Frame> {
+        if self.weight > self.best_weight {
+            self.best_weight = self.weight;
+            self.best_solution.clone_from(&self.partial_solution);
+        }
+
+        if self.weight + self.remaining_weight() <= self.best_weight {
+            return None;
+        }
+
+        let column = ColumnHeuristic::MinimumRemainingValues.choose_column(&self.grid)?;
+
+        Some(Frame {
+            column,
+            rows: self.grid.column_nodes(column).collect(),
+            next_row: 0,
+            selected_row: None,
+            skipped: false,
+            snapshot: self.grid.snapshot(),
+        })
+    }
+
+    /// Return the total weight of the uncovered primary columns which still
+    /// have rows, which is the most that the current partial cover can grow by.
+    fn remaining_weight(&self) -> u64 {
+        self.grid
+            .uncovered_primary_columns()
+            .filter(|column| self.grid.column_size(*column) > 0)
+            .map(|column| self.column_weights[column])
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        polyomino::{PShape, Polyomino, ShapeTransform},
+        queens::NQueens,
+        testing::{random_problem, RandomProblemConfig},
+        text::TextProblem,
+    };
+
+    /// Return the largest weight of any partial cover, by trying every subset
+    /// of the possibilities.
+    fn brute_force_weight(problem: &TextProblem, weights: &[u64]) -> u64 {
+        let rows = &problem.possibilities;
+        (0u32..(1 << rows.len()))
+            .filter_map(|subset| {
+                let mut covered = alloc::vec![false; problem.constraints.len()];
+                for (index, row) in rows.iter().enumerate() {
+                    if subset & (1 << index) == 0 {
+                        continue;
+                    }
+                    for item in &row.items {
+                        if covered[*item] {
+                            return None;
+                        }
+                        covered[*item] = true;
+                    }
+                }
+
+                Some(
+                    problem
+                        .constraints
+                        .iter()
+                        .zip(covered)
+                        .filter(|(cons, covered)| *covered && !cons.optional)
+                        .map(|(cons, _)| weights[cons.index])
+                        .sum(),
+                )
+            })
+            .max()
+            .unwrap()
+    }
+
+    /// Check that the partial cover doesn't cover anything twice, and that its
+    /// weight and uncovered constraints are correct.
+    fn assert_valid(problem: &TextProblem, weights: &[u64], cover: &PartialCover<TextProblem>) {
+        let mut covered = alloc::vec![false; problem.constraints.len()];
+        for poss in &cover.possibilities {
+            for item in &poss.items {
+                assert!(!covered[*item], "item {item} is covered twice");
+                covered[*item] = true;
+            }
+        }
+
+        let uncovered: Vec<_> = problem
+            .constraints
+            .iter()
+            .filter(|cons| !cons.optional && !covered[cons.index])
+            .collect();
+        assert_eq!(cover.uncovered, uncovered);
+
+        let weight: u64 = problem
+            .constraints
+            .iter()
+            .filter(|cons| !cons.optional && covered[cons.index])
+            .map(|cons| weights[cons.index])
+            .sum();
+        assert_eq!(cover.weight, weight);
+    }
+
+    #[test]
+    fn exact_cover_is_best() {
+        let queens = NQueens::new(5, []);
+        let best = MaxCoverSolver::new(&queens).solve();
+
+        assert!(best.is_exact());
+        assert_eq!(best.possibilities.len(), 5);
+        // The diagonals are optional, so only the 5 rows and 5 columns count
+        assert_eq!(best.weight, 10);
+    }
+
+    #[test]
+    fn partial_polyomino_packing() {
+        // Two 2x2 squares always overlap on a 3x3 board, so only one of them fits
+        let square = PShape::from([[1, 1], [1, 1]]);
+        let polyomino = Polyomino::new(
+            (3, 3),
+            alloc::vec![square.clone(), square],
+            ShapeTransform::NoTransform,
+        );
+
+        let best = MaxCoverSolver::new(&polyomino).solve();
+        assert_eq!(best.possibilities.len(), 1);
+        assert_eq!(best.weight, 5);
+        assert_eq!(best.uncovered.len(), 6);
+        assert!(!best.is_exact());
+    }
+
+    #[test]
+    fn no_possibilities() {
+        let problem = TextProblem::parse("a b | c\n").unwrap();
+        let best = MaxCoverSolver::new(&problem).solve();
+
+        assert!(best.possibilities.is_empty());
+        assert_eq!(best.weight, 0);
+        assert_eq!(
+            best.uncovered
+                .iter()
+                .map(|cons| cons.name.as_str())
+                .collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+    }
+
+    #[test]
+    fn large_weights() {
+        // The heaviest cover is worth nearly the largest `u64`
+        let problem = TextProblem::parse("a b c\na b\nb c\n").unwrap();
+        let best = MaxCoverSolver::with_weights(&problem, |cons| match cons.name.as_str() {
+            "b" => 1,
+            _ => u64::MAX / 2,
+        })
+        .solve();
+
+        assert_eq!(best.weight, u64::MAX / 2 + 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "The total weight of the primary constraints should fit in a u64.")]
+    fn total_weight_overflows() {
+        let problem = TextProblem::parse("a b | c\na\nb\n").unwrap();
+        let _solver = MaxCoverSolver::with_weights(&problem, |_| u64::MAX / 2 + 1);
+    }
+
+    #[test]
+    fn deep_search() {
+        // Every level covers a single column, so the search is as deep as there
+        // are columns
+        let num_items = if cfg!(miri) { 100 } else { 2_000 };
+        let mut input = alloc::string::String::new();
+        for item in 0..num_items {
+            input += &alloc::format!("i{item} ");
+        }
+        input += "\n";
+        for item in 0..num_items {
+            input += &alloc::format!("i{item}\n");
+        }
+        let problem = TextProblem::parse(&input).unwrap();
+
+        let best = MaxCoverSolver::new(&problem).solve();
+        assert!(best.is_exact());
+        assert_eq!(best.weight, num_items);
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)] // takes too long on miri
+    fn random_problems_match_brute_force() {
+        for seed in 0..300 {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let weights: Vec<_> = (0..problem.constraints.len())
+                .map(|index| (seed as usize + 3 * index) as u64 % 4)
+                .collect();
+
+            let best = MaxCoverSolver::new(&problem).solve();
+            let unit_weights = alloc::vec![1; problem.constraints.len()];
+            assert_valid(&problem, &unit_weights, &best);
+            assert_eq!(
+                best.weight,
+                brute_force_weight(&problem, &unit_weights),
+                "seed {seed}"
+            );
+
+            let best = MaxCoverSolver::with_weights(&problem, |cons| weights[cons.index]).solve();
+            assert_valid(&problem, &weights, &best);
+            assert_eq!(
+                best.weight,
+                brute_force_weight(&problem, &weights),
+                "seed {seed}"
+            );
+        }
+    }
+}
@@ -4,7 +4,7 @@
 //! overlaps or gaps.
 
 use crate::ExactCover;
-use std::rc::Rc;
+use alloc::{rc::Rc, vec, vec::Vec};
 
 /// Type representing shape of a single polyomino, encoded as binary mask.
 /// `PShape` is represented as a vector of `height * width` elements,
@@ -13,6 +13,7 @@ use std::rc::Rc;
 /// and `j`th column is available under index `i * width + j`.
 /// 0 represents an empty cell, 1 represents a filled cell.
 #[derive(Debug, PartialEq, Eq, Clone, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PShape {
     /// Width of polyomino
     pub width: usize,
@@ -118,6 +119,7 @@ impl<const W: usize, const H: usize> From<[[u8; W]; H]> for PShape {
 
 /// Available transformations for polyomino shapes during tiling.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ShapeTransform {
     /// No transformation applied to the shape.
     NoTransform,
@@ -129,6 +131,7 @@ pub enum ShapeTransform {
 
 /// Instance of a polyomino tiling puzzle.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Polyomino {
     /// The list of possible placements of polyominoes in the grid.
     pub possibilities: Vec<Possibility>,
@@ -300,6 +303,7 @@ impl ExactCover for Polyomino {
 
 /// A possible placement of a polyomino in the grid.
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Possibility {
     /// Index of the shape in the list of polyominoes.
     pub shape_index: usize,
@@ -324,13 +328,14 @@ impl Possibility {
             .occupied_cells
             .iter()
             .map(|&(row, col)| Constraint::Field(row, col));
-        std::iter::once(shape_constraint).chain(field_constraints)
+        core::iter::once(shape_constraint).chain(field_constraints)
     }
 }
 
 /// A condition that must be satisfied in order to solve a polyomino
 /// tiling puzzle.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constraint {
     /// A constraint that a specific shape must be placed in the grid.
     /// Inner integer represents the index of the shape in the list
@@ -6,10 +6,11 @@
 //! column, or diagonal.
 
 use crate::ExactCover;
-use std::collections::HashSet;
+use alloc::{collections::BTreeSet, vec::Vec};
 
 /// An instance of the `n` queens problem.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NQueens {
     /// The list of possible positions that could solve the `n` queens puzzle.
     pub possibilities: Vec<Possibility>,
@@ -26,13 +27,13 @@ impl NQueens {
     pub fn new(side_length: usize, filled_values: impl IntoIterator<Item = Possibility>) -> Self {
         let filled_values: Vec<_> = filled_values.into_iter().collect();
 
-        let satisfied: HashSet<_> = filled_values
+        let satisfied: BTreeSet<_> = filled_values
             .iter()
             .copied()
             .flat_map(|poss| poss.satisfied_constraints(side_length))
             .collect();
 
-        let filled_coordinates: HashSet<_> = filled_values
+        let filled_coordinates: BTreeSet<_> = filled_values
             .iter()
             .map(|poss| (poss.row, poss.column))
             .collect();
@@ -86,6 +87,7 @@ impl ExactCover for NQueens {
 
 /// A position on the chess board.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Possibility {
     /// The row index, ranging from 0 to `n - 1`.
     pub row: usize,
@@ -134,6 +136,7 @@ impl Possibility {
 
 /// A condition which must be satisfied in order to solve an `n` queens puzzle.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constraint {
     /// A condition that a given row should have exactly one queen.
     Row {
@@ -175,6 +178,8 @@ impl Constraint {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{Solver, SolverObserver};
+    use core::ops::ControlFlow;
     use std::iter;
 
     fn p(row: usize, column: usize) -> Possibility {
@@ -242,6 +247,138 @@ mod tests {
         assert!(solver.next().is_none());
     }
 
+    #[test]
+    fn visit_solutions_with_early_exit() {
+        let queens = NQueens::new(4, iter::empty());
+        let mut solver = queens.solver();
+
+        let mut visited = Vec::new();
+        let flow = solver.solve_with(|solution| {
+            assert_eq!(solution.depth(), 4);
+            visited.push(solution.possibilities().copied().collect::<Vec<_>>());
+            ControlFlow::Break(())
+        });
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert_eq!(visited.len(), 1);
+
+        // Resuming the search picks up the remaining solution
+        let flow = solver.solve_with(|solution| {
+            visited.push(solution.possibilities().copied().collect::<Vec<_>>());
+            ControlFlow::Continue(())
+        });
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(visited.len(), 2);
+        assert!(solver.next().is_none());
+
+        let mut expected: Vec<_> = queens
+            .solver()
+            .map(|solution| solution.into_iter().copied().collect::<Vec<_>>())
+            .collect();
+        assert_eq!(visited, expected);
+
+        expected.iter_mut().for_each(|solution| solution.sort());
+        assert_eq!(
+            expected,
+            vec![
+                vec![p(0, 1), p(1, 3), p(2, 0), p(3, 2)],
+                vec![p(0, 2), p(1, 0), p(2, 3), p(3, 1)]
+            ]
+        );
+    }
+
+    #[test]
+    fn fork_solver_mid_search() {
+        let queens = NQueens::new(6, iter::empty());
+        let mut solver = queens.solver();
+        let first = solver.next().unwrap();
+
+        let forked = solver.clone();
+        let remaining: Vec<_> = solver.collect();
+        assert_eq!(remaining.len(), 3);
+        assert_eq!(forked.collect::<Vec<_>>(), remaining);
+        assert!(!remaining.contains(&first));
+    }
+
+    #[test]
+    fn resume_from_checkpoint() {
+        let queens = NQueens::new(6, iter::empty());
+        let mut full = queens.solver();
+        let expected: Vec<_> = full.by_ref().collect();
+        let full_stats = full.stats();
+
+        let mut solver = queens.solver();
+        let fresh = Solver::from_checkpoint(&queens, &solver.checkpoint()).unwrap();
+        assert!(fresh.eq(expected.iter().cloned()));
+
+        // Take a checkpoint after every solution, and at the end
+        for (idx, solution) in expected.iter().enumerate() {
+            assert_eq!(solver.next().as_ref(), Some(solution));
+
+            let checkpoint = solver.checkpoint();
+            let mut resumed = Solver::from_checkpoint(&queens, &checkpoint).unwrap();
+            assert_eq!(resumed.checkpoint(), checkpoint);
+            assert_eq!(resumed.stats(), solver.stats());
+            assert!(resumed.by_ref().eq(expected[(idx + 1)..].iter().cloned()));
+            assert_eq!(resumed.stats(), full_stats);
+        }
+    }
+
+    #[derive(Debug, Default)]
+    struct CountingObserver {
+        chosen: u64,
+        tried: u64,
+        backtracked: u64,
+        dead_ends: u64,
+        solutions: Vec<Vec<usize>>,
+        max_depth: usize,
+    }
+
+    impl SolverObserver for CountingObserver {
+        fn on_choose_column(&mut self, _constraint: usize, _num_rows: usize) {
+            self.chosen += 1;
+        }
+
+        fn on_try_row(&mut self, _possibility: usize, depth: usize) {
+            self.tried += 1;
+            self.max_depth = self.max_depth.max(depth + 1);
+        }
+
+        fn on_backtrack(&mut self, _possibility: usize, _depth: usize) {
+            self.backtracked += 1;
+        }
+
+        fn on_dead_end(&mut self, _constraint: usize) {
+            self.dead_ends += 1;
+        }
+
+        fn on_solution(&mut self, row_indices: &[usize]) {
+            self.solutions.push(row_indices.to_vec());
+        }
+    }
+
+    #[test]
+    fn observe_search() {
+        let queens = NQueens::new(6, iter::empty());
+        let mut observer = CountingObserver::default();
+        let mut solver = queens.solver().with_observer(&mut observer);
+
+        let mut solutions = Vec::new();
+        let _ = solver.solve_with(|solution| {
+            solutions.push(solution.row_indices().to_vec());
+            ControlFlow::Continue(())
+        });
+        let stats = solver.stats();
+
+        assert_eq!(observer.solutions, solutions);
+        assert_eq!(observer.solutions.len() as u64, stats.solutions);
+        assert_eq!(observer.tried, stats.nodes);
+        assert_eq!(observer.backtracked, stats.nodes);
+        assert_eq!(observer.dead_ends, stats.dead_ends);
+        // Every node that isn't a solution chooses a column, as does the root
+        assert_eq!(observer.chosen, stats.nodes - stats.solutions + 1);
+        assert_eq!(observer.max_depth, 6);
+    }
+
     #[test]
     #[cfg_attr(miri, ignore)] // takes too long on miri
     fn count_medium_board() {
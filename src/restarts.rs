@@ -0,0 +1,316 @@
+//! Finding a first solution quickly with randomized restarts.
+//!
+//! The time a [`Solver`] takes to find a first solution depends a lot on its
+//! early choices: a bad choice near the root of the search can hide every
+//! solution below a huge subtree, which the search has to exhaust before it
+//! ever revisits that choice. [`find_one`] tries the rows of each column in a
+//! random order instead, and gives up on a search once it has tried a number
+//! of rows set by a [`Schedule`], to start again with a different order. The
+//! limits grow without bound, so the last search always runs to completion,
+//! and a problem without solutions is still reported as such.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{
+//!     queens::NQueens,
+//!     restarts::{find_one, Restarts, Schedule},
+//! };
+//!
+//! let queens = NQueens::new(20, []);
+//! let restarts = Restarts {
+//!     schedule: Schedule::Luby { unit: 64 },
+//!     ..Restarts::default()
+//! };
+//! let (solution, stats) = find_one(&queens, &restarts);
+//!
+//! assert_eq!(solution.unwrap().len(), 20);
+//! assert_eq!(stats.runs, stats.restarts + 1);
+//! ```
+
+use crate::{ColumnHeuristic, ExactCover, Learning, Solver, SolverStats};
+use alloc::vec::Vec;
+
+/// How many rows each search of [`find_one`] may try before it restarts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Schedule {
+    /// The Luby sequence `1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ...` multiplied by
+    /// `unit`, which is treated as 1 if it is 0.
+    ///
+    /// This sequence is within a constant factor of the best possible
+    /// schedule for any distribution of running times.
+    Luby {
+        /// The number of rows tried by the shortest searches.
+        unit: u64,
+    },
+    /// Limits which start at `initial` and are multiplied by `factor` after
+    /// every restart, growing by at least one row each time.
+    Geometric {
+        /// The number of rows tried by the first search, treated as 1 if it
+        /// is 0.
+        initial: u64,
+        /// The growth of the limit from one search to the next.
+        factor: f64,
+    },
+}
+
+impl Schedule {
+    /// Return the limit on the number of rows tried by each search, in order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dancing_links::restarts::Schedule;
+    ///
+    /// let luby: Vec<_> = Schedule::Luby { unit: 10 }.cutoffs().take(7).collect();
+    /// assert_eq!(luby, [10, 10, 20, 10, 10, 20, 40]);
+    ///
+    /// let schedule = Schedule::Geometric {
+    ///     initial: 100,
+    ///     factor: 1.5,
+    /// };
+    /// let geometric: Vec<_> = schedule.cutoffs().take(4).collect();
+    /// assert_eq!(geometric, [100, 150, 225, 337]);
+    /// ```
+    pub fn cutoffs(&self) -> impl Iterator<Item = u64> {
+        let schedule = *self;
+        let mut index = 0;
+        let mut previous = 0u64;
+
+        core::iter::from_fn(move || {
+            let cutoff = match schedule {
+                Schedule::Luby { unit } => unit.max(1).saturating_mul(luby(index)),
+                Schedule::Geometric { initial, factor } => {
+                    if index == 0 {
+                        initial.max(1)
+                    } else {
+                        // Casting saturates, and turns NaN into 0
+                        ((previous as f64 * factor) as u64).max(previous.saturating_add(1))
+                    }
+                }
+            };
+            index += 1;
+            previous = cutoff;
+
+            Some(cutoff)
+        })
+    }
+}
+
+/// Return the element at the given index (from 0) of the Luby sequence.
+fn luby(mut index: u64) -> u64 {
+    // Find the smallest complete subsequence `2^k - 1` long which contains the
+    // index, then the smaller subsequence it falls in, until the index is the
+    // last element of a subsequence, which is `2^(k-1)`
+    let mut size = 1u64;
+    let mut exponent = 0;
+    while size < index.saturating_add(1) {
+        size = size.saturating_mul(2).saturating_add(1);
+        exponent += 1;
+    }
+    while size - 1 != index {
+        size = (size - 1) / 2;
+        exponent -= 1;
+        index %= size;
+    }
+
+    1u64.checked_shl(exponent).unwrap_or(u64::MAX)
+}
+
+/// Configuration of the search of [`find_one`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Restarts {
+    /// The limits on the number of rows tried by each search.
+    pub schedule: Schedule,
+    /// The seed of the random order of the rows. The same seed always gives
+    /// the same searches.
+    pub seed: u64,
+    /// The heuristic used to choose columns.
+    pub heuristic: ColumnHeuristic,
+    /// Nogood learning, if enabled. The nogoods learned by one search are
+    /// kept for the next ones, so that they don't run into the same
+    /// conflicts again.
+    pub learning: Option<Learning>,
+}
+
+impl Default for Restarts {
+    fn default() -> Self {
+        Self {
+            schedule: Schedule::Luby { unit: 100 },
+            seed: 0,
+            heuristic: ColumnHeuristic::default(),
+            learning: None,
+        }
+    }
+}
+
+/// Statistics about the searches performed by [`find_one`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RestartStats {
+    /// The number of searches, including the last one.
+    pub runs: u64,
+    /// The number of searches which were stopped by their limit.
+    pub restarts: u64,
+    /// The number of rows tried by all of the searches together.
+    pub nodes: u64,
+    /// The statistics of the last search, which found the solution or proved
+    /// that there is none.
+    pub last_run: SolverStats,
+}
+
+/// Search for one solution of the given problem with randomized restarts, as
+/// described in the [module documentation](self).
+///
+/// Returns the solution, or `None` if the problem has no solutions, along with
+/// statistics about the searches. The search respects
+/// [`ExactCover::is_viable`].
+pub fn find_one<'e, E: ExactCover>(
+    problem: &'e E,
+    restarts: &Restarts,
+) -> (Option<Vec<&'e E::Possibility>>, RestartStats) {
+    let mut solver =
+        Solver::with_heuristic(problem, restarts.heuristic).with_shuffled_rows(restarts.seed);
+    if let Some(learning) = restarts.learning {
+        solver = solver.with_learning(learning);
+    }
+
+    let mut stats = RestartStats::default();
+    for cutoff in restarts.schedule.cutoffs() {
+        let found = solver.advance_within(cutoff);
+        stats.runs += 1;
+        stats.nodes += solver.stats().nodes;
+
+        if let Some(found) = found {
+            stats.last_run = solver.stats();
+            return (found.then(|| solver.current_solution()), stats);
+        }
+        stats.restarts += 1;
+        // The random order carries on from where it stopped, so the next
+        // search is different
+        solver.reset();
+    }
+
+    unreachable!("The schedule never ends")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        latin_square::{self, LatinSquare},
+        queens::NQueens,
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+        Solution,
+    };
+
+    #[test]
+    fn luby_sequence() {
+        let sequence: Vec<_> = (0..15).map(luby).collect();
+        assert_eq!(sequence, [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+        assert_eq!(luby(62), 32);
+        assert_eq!(luby(u64::MAX - 1), 1 << 63);
+    }
+
+    #[test]
+    fn schedules_grow() {
+        let zero_unit: Vec<_> = Schedule::Luby { unit: 0 }.cutoffs().take(3).collect();
+        assert_eq!(zero_unit, [1, 1, 2]);
+
+        // Factors which don't grow still add a row every time
+        let flat = Schedule::Geometric {
+            initial: 0,
+            factor: 0.5,
+        };
+        assert_eq!(flat.cutoffs().take(4).collect::<Vec<_>>(), [1, 2, 3, 4]);
+
+        let huge = Schedule::Geometric {
+            initial: u64::MAX / 2,
+            factor: 10.0,
+        };
+        assert_eq!(huge.cutoffs().nth(3), Some(u64::MAX));
+    }
+
+    #[test]
+    fn queens_restart() {
+        let queens = NQueens::new(12, []);
+        let restarts = Restarts {
+            schedule: Schedule::Luby { unit: 1 },
+            seed: 7,
+            ..Restarts::default()
+        };
+        let (solution, stats) = find_one(&queens, &restarts);
+
+        let solution = solution.unwrap();
+        assert_eq!(solution.len(), 12);
+        for (index, first) in solution.iter().enumerate() {
+            for second in &solution[index + 1..] {
+                assert_ne!(first.row, second.row);
+                assert_ne!(first.column, second.column);
+            }
+        }
+        assert!(stats.restarts > 0);
+        assert_eq!(stats.runs, stats.restarts + 1);
+        assert_eq!(stats.last_run.solutions, 1);
+        assert!(stats.nodes > stats.last_run.nodes);
+
+        // The same seed repeats the same searches
+        assert_eq!(find_one(&queens, &restarts).1, stats);
+    }
+
+    #[test]
+    fn infeasible_latin_square() {
+        // A 6x6 latin square which can't be completed, but where every cell
+        // still has some values left
+        let filled = [
+            (1, 2, 5),
+            (0, 2, 4),
+            (2, 2, 6),
+            (4, 5, 2),
+            (2, 0, 3),
+            (5, 1, 3),
+            (2, 3, 2),
+            (4, 4, 3),
+            (4, 1, 1),
+        ]
+        .map(|(row, column, value)| latin_square::Possibility { row, column, value });
+        let latin = LatinSquare::new(6, filled);
+        let restarts = Restarts {
+            schedule: Schedule::Geometric {
+                initial: 1,
+                factor: 2.0,
+            },
+            learning: Some(Learning::default()),
+            ..Restarts::default()
+        };
+
+        let (solution, stats) = find_one(&latin, &restarts);
+        assert!(solution.is_none());
+        assert_eq!(stats.last_run.solutions, 0);
+    }
+
+    #[test]
+    fn random_problems_match_reference() {
+        for seed in 0..if cfg!(miri) { 20 } else { 300 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let restarts = Restarts {
+                schedule: Schedule::Luby { unit: 2 },
+                seed,
+                learning: (seed % 2 == 0).then(Learning::default),
+                ..Restarts::default()
+            };
+            let (solution, _) = find_one(&problem, &restarts);
+
+            let reference = reference_solutions(&problem);
+            match solution {
+                Some(solution) => {
+                    let solution = Solution::new(solution.iter().map(|poss| poss.index));
+                    assert!(reference.contains(&solution), "seed {seed}");
+                }
+                None => assert!(reference.is_empty(), "seed {seed}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,386 @@
+//! Conversion of exact cover problems into boolean satisfiability (SAT)
+//! instances in [DIMACS CNF](https://jix.github.io/varisat/manual/0.2.0/formats/dimacs.html)
+//! format.
+//!
+//! Every possibility of the problem gets its own variable, numbered by its
+//! position in [`ExactCover::possibilities`] starting from 1. Each primary
+//! constraint becomes an "exactly one" condition over the possibilities which
+//! satisfy it, and each optional constraint becomes an "at most one" condition.
+//! Possibilities which don't satisfy any primary constraint are never part of
+//! a solution found by the [`Solver`](crate::Solver), so their variables are
+//! forced to be false. Like the solver, a problem without any primary
+//! constraint has no solutions, which is encoded by an empty clause.
+//!
+//! Any auxiliary variables introduced by a [`CardinalityEncoding`] are numbered
+//! after the possibility variables, and are fully determined by them. This
+//! means the number of models of the formula is exactly the number of solutions
+//! to the exact cover problem, which makes the output suitable for model
+//! counting (#SAT) tools as well.
+
+use crate::ExactCover;
+use core::fmt;
+use std::io;
+
+/// The method used to encode "at most one of these variables is true".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CardinalityEncoding {
+    /// Forbid every pair of variables from being true together.
+    ///
+    /// Uses no auxiliary variables, but produces a quadratic number of
+    /// clauses.
+    #[default]
+    Pairwise,
+    /// The sequential counter encoding from Sinz, "Towards an Optimal CNF
+    /// Encoding of Boolean Cardinality Constraints" (2005).
+    ///
+    /// Uses `n - 1` auxiliary variables and a linear number of clauses.
+    SequentialCounter,
+    /// The commander encoding from Klieber and Kwon, "Efficient CNF Encoding
+    /// for Selecting 1 from N Objects" (2007), with groups of 3 variables.
+    ///
+    /// Uses roughly `n / 2` auxiliary variables and a linear number of
+    /// clauses.
+    Commander,
+}
+
+/// A boolean formula in conjunctive normal form.
+///
+/// Literals follow the DIMACS convention: variable `v` is written as `v` and
+/// its negation as `-v`, where variables are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Cnf {
+    /// The number of variables used in the formula.
+    pub num_variables: usize,
+    /// The clauses of the formula, each of which is a disjunction of literals.
+    pub clauses: Vec<Vec<i64>>,
+}
+
+impl Cnf {
+    /// Convert an exact cover problem to a CNF formula, using the given
+    /// encoding for the "at most one" conditions.
+    pub fn from_problem<E: ExactCover>(problem: &E, encoding: CardinalityEncoding) -> Self {
+        let possibilities = problem.possibilities();
+        let mut cnf = Cnf {
+            num_variables: possibilities.len(),
+            clauses: Vec::new(),
+        };
+
+        let mut variables = Vec::new();
+        let mut has_primary = vec![false; possibilities.len()];
+        for cons in problem.constraints() {
+            variables.clear();
+            variables.extend(
+                possibilities
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, poss)| problem.satisfies(poss, cons))
+                    .map(|(idx, _)| variable(idx)),
+            );
+
+            if !problem.is_optional(cons) {
+                // At least one, which is an empty (unsatisfiable) clause if nothing can
+                // satisfy the constraint.
+                cnf.clauses.push(variables.clone());
+                for var in &variables {
+                    has_primary[*var as usize - 1] = true;
+                }
+            }
+
+            cnf.at_most_one(&variables, encoding);
+        }
+
+        if !problem
+            .constraints()
+            .iter()
+            .any(|cons| !problem.is_optional(cons))
+        {
+            cnf.clauses.push(Vec::new());
+        }
+        for (idx, has_primary) in has_primary.into_iter().enumerate() {
+            if !has_primary {
+                cnf.clauses.push(vec![-variable(idx)]);
+            }
+        }
+
+        cnf
+    }
+
+    fn new_variable(&mut self) -> i64 {
+        self.num_variables += 1;
+
+        self.num_variables as i64
+    }
+
+    fn at_most_one(&mut self, variables: &[i64], encoding: CardinalityEncoding) {
+        match encoding {
+            CardinalityEncoding::Pairwise => self.at_most_one_pairwise(variables),
+            CardinalityEncoding::SequentialCounter => self.at_most_one_sequential(variables),
+            CardinalityEncoding::Commander => self.at_most_one_commander(variables),
+        }
+    }
+
+    fn at_most_one_pairwise(&mut self, variables: &[i64]) {
+        for (idx, first) in variables.iter().enumerate() {
+            for second in &variables[(idx + 1)..] {
+                self.clauses.push(vec![-first, -second]);
+            }
+        }
+    }
+
+    fn at_most_one_sequential(&mut self, variables: &[i64]) {
+        if variables.len() <= 1 {
+            return;
+        }
+
+        // `counter[i]` is true iff any of `variables[..=i]` is true
+        let counters: Vec<_> = (0..(variables.len() - 1))
+            .map(|_| self.new_variable())
+            .collect();
+
+        for (idx, &var) in variables.iter().enumerate() {
+            let prev = idx.checked_sub(1).map(|prev| counters[prev]);
+            let curr = counters.get(idx).copied();
+
+            if let Some(curr) = curr {
+                self.clauses.push(vec![-var, curr]);
+                if let Some(prev) = prev {
+                    self.clauses.push(vec![-prev, curr]);
+                    self.clauses.push(vec![-curr, prev, var]);
+                } else {
+                    self.clauses.push(vec![-curr, var]);
+                }
+            }
+
+            if let Some(prev) = prev {
+                self.clauses.push(vec![-var, -prev]);
+            }
+        }
+    }
+
+    fn at_most_one_commander(&mut self, variables: &[i64]) {
+        const GROUP_SIZE: usize = 3;
+
+        if variables.len() <= GROUP_SIZE + 1 {
+            self.at_most_one_pairwise(variables);
+            return;
+        }
+
+        let mut commanders = Vec::with_capacity(variables.len().div_ceil(GROUP_SIZE));
+        for group in variables.chunks(GROUP_SIZE) {
+            self.at_most_one_pairwise(group);
+
+            // The commander is true iff any variable in the group is true
+            let commander = self.new_variable();
+            let mut any_in_group = vec![-commander];
+            for &var in group {
+                self.clauses.push(vec![-var, commander]);
+                any_in_group.push(var);
+            }
+            self.clauses.push(any_in_group);
+
+            commanders.push(commander);
+        }
+
+        self.at_most_one_commander(&commanders);
+    }
+
+    /// Write the formula in DIMACS CNF format.
+    pub fn write_dimacs(&self, mut out: impl io::Write) -> io::Result<()> {
+        writeln!(out, "p cnf {} {}", self.num_variables, self.clauses.len())?;
+
+        for clause in &self.clauses {
+            for literal in clause {
+                write!(out, "{literal} ")?;
+            }
+            writeln!(out, "0")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for Cnf {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut output = Vec::new();
+        self.write_dimacs(&mut output).map_err(|_| fmt::Error)?;
+
+        f.write_str(core::str::from_utf8(&output).map_err(|_| fmt::Error)?)
+    }
+}
+
+/// Return the DIMACS variable for the possibility at the given index.
+pub fn variable(possibility_index: usize) -> i64 {
+    possibility_index as i64 + 1
+}
+
+/// Convert a model of the formula produced by [`Cnf::from_problem`] into a
+/// solution of the exact cover problem.
+///
+/// The model is a list of literals, as printed on the `v` lines of a SAT
+/// solver's output. Literals for auxiliary variables and the terminating `0`
+/// are ignored. The returned possibilities are in the same order as
+/// [`ExactCover::possibilities`].
+///
+/// This does not check that the model actually satisfies the formula.
+pub fn decode_model<E: ExactCover>(
+    problem: &E,
+    model: impl IntoIterator<Item = i64>,
+) -> Vec<&E::Possibility> {
+    let possibilities = problem.possibilities();
+    let mut selected = vec![false; possibilities.len()];
+
+    for literal in model {
+        if literal > 0 && (literal as usize) <= possibilities.len() {
+            selected[literal as usize - 1] = true;
+        }
+    }
+
+    possibilities
+        .iter()
+        .zip(selected)
+        .filter_map(|(poss, selected)| selected.then_some(poss))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        latin_square::LatinSquare,
+        testing::{random_problem, RandomProblemConfig},
+        text::TextProblem,
+    };
+
+    const KNUTH_EXAMPLE: &str = "\
+A B C D E F G
+C E F
+A D G
+B C F
+A D
+B G
+D E G
+";
+
+    const ENCODINGS: [CardinalityEncoding; 3] = [
+        CardinalityEncoding::Pairwise,
+        CardinalityEncoding::SequentialCounter,
+        CardinalityEncoding::Commander,
+    ];
+
+    /// Return all models of the formula by trying every assignment, which is
+    /// only suitable for a small number of variables.
+    fn brute_force_models(cnf: &Cnf) -> Vec<Vec<i64>> {
+        assert!(cnf.num_variables <= 20, "too many variables to brute force");
+
+        (0u64..(1 << cnf.num_variables))
+            .filter(|assignment| {
+                cnf.clauses.iter().all(|clause| {
+                    clause.iter().any(|literal| {
+                        let value = assignment & (1 << (literal.unsigned_abs() - 1)) != 0;
+                        value == (*literal > 0)
+                    })
+                })
+            })
+            .map(|assignment| {
+                (1..=cnf.num_variables as i64)
+                    .map(|var| {
+                        if assignment & (1 << (var - 1)) != 0 {
+                            var
+                        } else {
+                            -var
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn pairwise_dimacs_output() {
+        let problem = TextProblem::parse("a b | x\na\nb x\na b\n").unwrap();
+        let cnf = Cnf::from_problem(&problem, CardinalityEncoding::Pairwise);
+
+        assert_eq!(
+            cnf.to_string(),
+            "p cnf 3 4\n1 3 0\n-1 -3 0\n2 3 0\n-2 -3 0\n"
+        );
+    }
+
+    #[test]
+    fn model_counts_match_solver() {
+        let knuth = TextProblem::parse(KNUTH_EXAMPLE).unwrap();
+        let secondary = TextProblem::parse("a b | x y\na x\nb x\na y\nb\na\nb y\n").unwrap();
+        let latin = LatinSquare::new(2, vec![]);
+
+        for encoding in ENCODINGS {
+            let cnf = Cnf::from_problem(&knuth, encoding);
+            assert_eq!(brute_force_models(&cnf).len(), knuth.solver().count());
+
+            let cnf = Cnf::from_problem(&secondary, encoding);
+            assert_eq!(brute_force_models(&cnf).len(), secondary.solver().count());
+
+            let cnf = Cnf::from_problem(&latin, encoding);
+            assert_eq!(brute_force_models(&cnf).len(), latin.solver().count());
+        }
+    }
+
+    #[test]
+    fn large_at_most_one_encodings() {
+        // An optional constraint with 6 possibilities and a primary one with 7
+        // exercise the auxiliary variables of the sequential counter and
+        // (recursive) commander encodings.
+        let problem = TextProblem::parse("a | x\na\na x\na x\na x\na x\na x\na x\n").unwrap();
+
+        for encoding in ENCODINGS {
+            let cnf = Cnf::from_problem(&problem, encoding);
+            // Exactly one of the 7 possibilities for `a` is selected
+            assert_eq!(brute_force_models(&cnf).len(), 7, "{encoding:?}");
+            assert_eq!(problem.solver().count(), 7);
+        }
+    }
+
+    #[test]
+    fn possibilities_without_primary_constraints() {
+        // `x` only satisfies an optional constraint, and `y` nothing at all
+        let problem = TextProblem::parse("a | x y\na\nx\n").unwrap();
+
+        for encoding in ENCODINGS {
+            let cnf = Cnf::from_problem(&problem, encoding);
+            assert_eq!(brute_force_models(&cnf), [vec![1, -2]], "{encoding:?}");
+        }
+    }
+
+    #[test]
+    fn random_model_counts_match_solver() {
+        for seed in 0..if cfg!(miri) { 10 } else { 100 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+
+            for encoding in ENCODINGS {
+                let cnf = Cnf::from_problem(&problem, encoding);
+                if cnf.num_variables > 16 {
+                    continue;
+                }
+                assert_eq!(
+                    brute_force_models(&cnf).len(),
+                    problem.solver().count(),
+                    "seed {seed}, {encoding:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn decode_models() {
+        let problem = TextProblem::parse(KNUTH_EXAMPLE).unwrap();
+
+        for encoding in ENCODINGS {
+            let cnf = Cnf::from_problem(&problem, encoding);
+            let models = brute_force_models(&cnf);
+            assert_eq!(models.len(), 1);
+
+            let mut expected = problem.solver().next().unwrap();
+            expected.sort();
+            assert_eq!(decode_model(&problem, models[0].iter().copied()), expected);
+        }
+    }
+}
@@ -0,0 +1,545 @@
+//! The search of Algorithm X over a [`Grid`], shared by the [`Solver`] and the
+//! [`LazySolver`](crate::lazy::LazySolver).
+//!
+//! A [`Search`] only knows the rows and columns of its grid. The solvers on top
+//! of it translate rows back into possibilities, and decide which partial
+//! solutions are viable.
+//!
+//! [`Solver`]: crate::Solver
+
+use crate::{
+    grid::{Grid, Node, Snapshot},
+    learning::{Conflict, Learning, Nogoods},
+    util::SplitMix64,
+    ColumnHeuristic, NodeControl, SolverObserver, SolverStats,
+};
+use alloc::vec::Vec;
+
+/// The state of the search of a solver: its grid, the partial solution and the
+/// stack of columns being branched on.
+#[derive(Debug, Clone)]
+pub(crate) struct Search<O = ()> {
+    grid: Grid,
+    // The state of the grid before any row was selected, used to reset it
+    root: Snapshot,
+    // The row indices (from 0) of the partial solution, in the order they were
+    // selected
+    partial_solution: Vec<usize>,
+    stack: Vec<Frame>,
+    // The root frame is pushed on the first step, so that the observer sees it
+    started: bool,
+    // Rows which are part of every solution, selected before the search starts
+    forced_rows: Vec<usize>,
+    // False if the forced rows were rejected as they were selected, so that
+    // there are no solutions
+    forced_viable: bool,
+
+    heuristic: ColumnHeuristic,
+    stats: SolverStats,
+    observer: O,
+    // Only present when learning is enabled
+    nogoods: Option<Nogoods>,
+    // Only present when the rows of each column are tried in a random order,
+    // which checkpoints can't replay
+    shuffle: Option<SplitMix64>,
+}
+
+/// The outcome of running a [`Search`] for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    // A solution was found, and is left in the partial solution
+    Solution,
+    // There are no more solutions
+    Exhausted,
+    // The search tried as many rows as it was allowed to
+    Limit,
+    // The node visitor stopped the search
+    Break,
+}
+
+#[derive(Debug, Clone)]
+enum FrameState {
+    // Before covering one of the rows
+    Cover,
+    // After checking, before uncovering
+    Uncover,
+}
+
+#[derive(Debug, Clone)]
+struct Frame {
+    min_column: usize,
+    selected_rows: Vec<Node>,
+    current_row: usize,
+    state: FrameState,
+    // Why the rows tried so far failed, only used for learning
+    conflict: Conflict,
+}
+
+impl Search {
+    /// Create a new search of the given grid, which will use the given
+    /// heuristic to choose columns.
+    pub(crate) fn new(grid: Grid, heuristic: ColumnHeuristic) -> Self {
+        Self {
+            root: grid.snapshot(),
+            grid,
+            partial_solution: Vec::new(),
+            stack: Vec::new(),
+            started: false,
+            forced_rows: Vec::new(),
+            forced_viable: true,
+
+            heuristic,
+            stats: SolverStats::default(),
+            observer: (),
+            nogoods: None,
+            shuffle: None,
+        }
+    }
+}
+
+impl<O: SolverObserver> Search<O> {
+    /// Replace the observer which is notified of the steps of the search.
+    pub(crate) fn with_observer<P: SolverObserver>(self, observer: P) -> Search<P> {
+        Search {
+            grid: self.grid,
+            root: self.root,
+            partial_solution: self.partial_solution,
+            stack: self.stack,
+            started: self.started,
+            forced_rows: self.forced_rows,
+            forced_viable: self.forced_viable,
+
+            heuristic: self.heuristic,
+            stats: self.stats,
+            observer,
+            nogoods: self.nogoods,
+            shuffle: self.shuffle,
+        }
+    }
+
+    /// Start a new search from scratch, after `rebuild` has replaced the
+    /// grid, reusing the memory of this one.
+    ///
+    /// The heuristic, the observer and the configuration of learning and
+    /// shuffling are kept, but not the nogoods or the forced rows.
+    pub(crate) fn rebuild(&mut self, rebuild: impl FnOnce(&mut Grid)) {
+        rebuild(&mut self.grid);
+        self.root = self.grid.snapshot();
+        self.partial_solution.clear();
+        self.stack.clear();
+        self.started = false;
+        self.forced_rows.clear();
+        self.forced_viable = true;
+        self.stats = SolverStats::default();
+        if let Some(nogoods) = &mut self.nogoods {
+            *nogoods = Nogoods::new(nogoods.config(), &self.grid, []);
+        }
+    }
+
+    /// Enable nogood learning with the given configuration.
+    pub(crate) fn with_learning(mut self, learning: Learning) -> Self {
+        let selected = self.partial_solution.iter().map(|row_index| row_index + 1);
+        self.nogoods = Some(Nogoods::new(learning, &self.grid, selected));
+        // The frames of a search in progress don't know why their earlier rows
+        // failed
+        for frame in &mut self.stack {
+            frame.conflict = Conflict::Unknown;
+        }
+
+        self
+    }
+
+    /// Try the rows of each chosen column in a random order, generated from
+    /// the given seed, instead of in row order.
+    pub(crate) fn with_shuffled_rows(mut self, seed: u64) -> Self {
+        self.shuffle = Some(SplitMix64::new(seed));
+        self
+    }
+
+    /// Return the row indices (from 0) of the partial solution, in the order
+    /// they were selected.
+    pub(crate) fn partial_solution(&self) -> &[usize] {
+        &self.partial_solution
+    }
+
+    pub(crate) fn heuristic(&self) -> ColumnHeuristic {
+        self.heuristic
+    }
+
+    pub(crate) fn stats(&self) -> SolverStats {
+        self.stats
+    }
+
+    pub(crate) fn observer(&self) -> &O {
+        &self.observer
+    }
+
+    pub(crate) fn observer_mut(&mut self) -> &mut O {
+        &mut self.observer
+    }
+
+    pub(crate) fn started(&self) -> bool {
+        self.started
+    }
+
+    pub(crate) fn forced_rows(&self) -> &[usize] {
+        &self.forced_rows
+    }
+
+    /// Return the position of each frame of the search: the index of its
+    /// current row among the rows of its column, and whether that row is
+    /// selected.
+    pub(crate) fn positions(&self) -> impl Iterator<Item = (usize, bool)> + '_ {
+        self.stack.iter().map(|frame| {
+            (
+                frame.current_row,
+                matches!(frame.state, FrameState::Uncover),
+            )
+        })
+    }
+
+    /// Select the given rows in the fresh grid of this search, and keep them
+    /// selected across resets, returning false if they can't all be selected
+    /// together.
+    ///
+    /// `is_viable` is called with the partial solution after each row, like
+    /// in [`Search::run`].
+    pub(crate) fn force_rows<V>(&mut self, rows: &[usize], is_viable: V) -> bool
+    where
+        V: FnMut(&[usize], &Grid) -> bool,
+    {
+        self.forced_rows = rows.to_vec();
+        self.select_forced_rows(is_viable)
+    }
+
+    /// Replay a search from the root, down to the frames at the given
+    /// positions (as returned by [`Search::positions`]), with the given
+    /// statistics.
+    ///
+    /// Returns `None` if the positions don't match the search of this grid.
+    pub(crate) fn replay(
+        &mut self,
+        started: bool,
+        stats: SolverStats,
+        positions: impl ExactSizeIterator<Item = (usize, bool)>,
+    ) -> Option<()> {
+        self.stats = stats;
+        self.started = started;
+        if !started && positions.len() != 0 {
+            return None;
+        }
+
+        // Every frame except the last one must have its row selected, since the
+        // frames after it branch from that row.
+        let num_frames = positions.len();
+        for (depth, (current_row, selected)) in positions.enumerate() {
+            let is_last = depth + 1 == num_frames;
+            if !selected && !is_last {
+                return None;
+            }
+
+            let min_column = self.heuristic.choose_column(&self.grid)?;
+            let selected_rows: Vec<_> = self.grid.column_nodes(min_column).collect();
+            let node = *selected_rows.get(current_row)?;
+
+            let state = if selected {
+                self.partial_solution.push(self.grid.node_row(node) - 1);
+                self.grid.select_row(node);
+
+                FrameState::Uncover
+            } else {
+                FrameState::Cover
+            };
+
+            self.stack.push(Frame {
+                min_column,
+                selected_rows,
+                current_row,
+                state,
+                // The rows before the current one were tried before the checkpoint
+                conflict: Conflict::Unknown,
+            });
+        }
+
+        Some(())
+    }
+
+    /// Reset the search to its start, except for the forced rows, the learned
+    /// nogoods and the observer.
+    ///
+    /// The forced rows are selected again, and checked with `is_viable`.
+    pub(crate) fn reset<V>(&mut self, is_viable: V)
+    where
+        V: FnMut(&[usize], &Grid) -> bool,
+    {
+        self.grid.restore(self.root);
+        if let Some(nogoods) = &mut self.nogoods {
+            nogoods.clear_selection();
+        }
+        self.partial_solution.clear();
+        self.stack.clear();
+        self.started = false;
+        self.stats = SolverStats::default();
+
+        let selected = self.select_forced_rows(is_viable);
+        debug_assert!(
+            selected,
+            "The forced rows were checked when the solver was created"
+        );
+    }
+
+    /// Select the forced rows in a fresh grid, returning false if they can't
+    /// all be selected together.
+    ///
+    /// Each row is checked by `is_viable` and the learned nogoods as it is
+    /// selected, like the rows chosen by the search. If a row fails either
+    /// check, the search ends as soon as it starts, which counts as one pruned
+    /// node.
+    fn select_forced_rows<V>(&mut self, mut is_viable: V) -> bool
+    where
+        V: FnMut(&[usize], &Grid) -> bool,
+    {
+        self.forced_viable = true;
+        for row_index in &self.forced_rows {
+            // The rows may come from a deserialized checkpoint, so check them
+            // before computing anything with them
+            if *row_index >= self.grid.max_row() {
+                return false;
+            }
+            let Some(node) = self.grid.row_nodes(row_index + 1).next() else {
+                return false;
+            };
+            let available = self
+                .grid
+                .row_nodes(row_index + 1)
+                .all(|node| !self.grid.is_column_covered(self.grid.node_column(node)));
+            if !available {
+                return false;
+            }
+
+            self.grid.select_row(node);
+            if let Some(nogoods) = &mut self.nogoods {
+                // A nogood learned before a reset may be made of forced rows only,
+                // which proves that there is nothing left to search
+                if nogoods.select(&self.grid, row_index + 1).is_some() {
+                    self.forced_viable = false;
+                }
+            }
+            self.partial_solution.push(*row_index);
+
+            if self.forced_viable {
+                self.forced_viable = is_viable(&self.partial_solution, &self.grid);
+            }
+        }
+
+        true
+    }
+
+    fn push_root_frame(&mut self) {
+        // If the grid is already solved (no primary columns), don't bother to put a
+        // stack frame in
+        if !self.grid.is_primary_empty() {
+            let min_column = self.heuristic.choose_column(&self.grid).unwrap();
+            let mut selected_rows: Vec<_> = self.grid.column_nodes(min_column).collect();
+            if let Some(rng) = &mut self.shuffle {
+                rng.shuffle(&mut selected_rows);
+            }
+            self.observer
+                .on_choose_column(min_column - 1, selected_rows.len());
+
+            if selected_rows.is_empty() {
+                self.stats.dead_ends += 1;
+                self.observer.on_dead_end(min_column - 1);
+                if let Some(nogoods) = &mut self.nogoods {
+                    let kept = nogoods.learn_dead_end(&self.grid, min_column, None);
+                    self.stats.learned += u64::from(kept);
+                }
+            } else {
+                self.stack.push(Frame {
+                    state: FrameState::Cover,
+                    min_column,
+                    selected_rows,
+                    current_row: 0,
+                    conflict: self
+                        .nogoods
+                        .as_mut()
+                        .map_or(Conflict::Unknown, Nogoods::conflict),
+                });
+            }
+        }
+    }
+
+    /// Run the search until it finds a solution, runs out of solutions, tries
+    /// `node_limit` rows, or `visit_node` stops it.
+    ///
+    /// `visit_node` is called with the partial solution and the grid every
+    /// time a row is added without violating a learned nogood, and decides
+    /// whether the search goes on below it.
+    pub(crate) fn run<N>(&mut self, node_limit: u64, mut visit_node: N) -> Step
+    where
+        N: FnMut(&[usize], &Grid) -> NodeControl,
+    {
+        enum StackOp<T> {
+            Push(T),
+            Pop,
+            None,
+        }
+
+        if !self.started {
+            self.started = true;
+
+            if !self.forced_rows.is_empty() {
+                if !self.forced_viable {
+                    self.stats.pruned += 1;
+                    return Step::Exhausted;
+                }
+
+                // Forced rows which cover every primary column are the only solution
+                if self.grid.is_primary_empty() {
+                    self.stats.solutions += 1;
+                    self.observer.on_solution(&self.partial_solution);
+                    return Step::Solution;
+                }
+            }
+            self.push_root_frame();
+        }
+
+        let mut stopped = false;
+        while !self.stack.is_empty() {
+            if self.stats.nodes >= node_limit {
+                return Step::Limit;
+            }
+            let curr_frame = self.stack.last_mut().unwrap();
+
+            let (stack_op, found_solution) = match curr_frame.state {
+                // for the current row of this frame, cover the selected columns and add the row
+                // to the solution.
+                FrameState::Cover => {
+                    let node = curr_frame.selected_rows[curr_frame.current_row];
+                    let row = self.grid.node_row(node);
+                    let row_index = row - 1;
+
+                    self.observer
+                        .on_try_row(row_index, self.partial_solution.len());
+                    self.partial_solution.push(row_index);
+                    self.stats.nodes += 1;
+                    self.grid.select_row(node);
+                    let violated = self
+                        .nogoods
+                        .as_mut()
+                        .and_then(|nogoods| nogoods.select(&self.grid, row));
+
+                    // This is where the recursion happens, but we also have to check for the
+                    // solution here.
+                    let stack_op = if let Some(nogood) = violated {
+                        self.stats.pruned += 1;
+                        if let Some(nogoods) = &self.nogoods {
+                            nogoods.learn_violated(nogood, &mut curr_frame.conflict);
+                        }
+                        (StackOp::None, false)
+                    } else if let control @ (NodeControl::Skip | NodeControl::Break) =
+                        visit_node(&self.partial_solution, &self.grid)
+                    {
+                        self.stats.pruned += 1;
+                        curr_frame.conflict = Conflict::Unknown;
+                        stopped = control == NodeControl::Break;
+                        (StackOp::None, false)
+                    } else if self.grid.is_primary_empty() {
+                        self.stats.solutions += 1;
+                        self.observer.on_solution(&self.partial_solution);
+                        curr_frame.conflict = Conflict::Unknown;
+                        (StackOp::None, true)
+                    } else {
+                        let min_column = self.heuristic.choose_column(&self.grid).unwrap();
+                        let mut selected_rows: Vec<_> =
+                            self.grid.column_nodes(min_column).collect();
+                        if let Some(rng) = &mut self.shuffle {
+                            rng.shuffle(&mut selected_rows);
+                        }
+                        self.observer
+                            .on_choose_column(min_column - 1, selected_rows.len());
+
+                        if selected_rows.is_empty() {
+                            self.stats.dead_ends += 1;
+                            self.observer.on_dead_end(min_column - 1);
+                            if let Some(nogoods) = &mut self.nogoods {
+                                let parent = Some(&mut curr_frame.conflict);
+                                let kept = nogoods.learn_dead_end(&self.grid, min_column, parent);
+                                self.stats.learned += u64::from(kept);
+                            }
+                            (StackOp::None, false)
+                        } else {
+                            (
+                                StackOp::Push(Frame {
+                                    state: FrameState::Cover,
+                                    min_column,
+                                    selected_rows,
+                                    current_row: 0,
+                                    conflict: self
+                                        .nogoods
+                                        .as_mut()
+                                        .map_or(Conflict::Unknown, Nogoods::conflict),
+                                }),
+                                false,
+                            )
+                        }
+                    };
+
+                    curr_frame.state = FrameState::Uncover;
+                    stack_op
+                }
+                // Cleanup the current row, uncover the selected columns, remove the row from
+                // the solution.
+                FrameState::Uncover => {
+                    let node = curr_frame.selected_rows[curr_frame.current_row];
+
+                    if let Some(nogoods) = &mut self.nogoods {
+                        nogoods.unselect(&self.grid, self.grid.node_row(node));
+                    }
+                    self.grid.unselect_row(node);
+                    let row_index = self.partial_solution.pop().unwrap();
+                    self.observer
+                        .on_backtrack(row_index, self.partial_solution.len());
+                    curr_frame.current_row += 1;
+
+                    if curr_frame.current_row == curr_frame.selected_rows.len() {
+                        (StackOp::Pop, false)
+                    } else {
+                        curr_frame.state = FrameState::Cover;
+                        (StackOp::None, false)
+                    }
+                }
+            };
+
+            match stack_op {
+                StackOp::Push(val) => {
+                    self.stack.push(val);
+                }
+                StackOp::Pop => {
+                    let frame = self.stack.pop().unwrap();
+                    if let Some(nogoods) = &mut self.nogoods {
+                        let parent = self.stack.last_mut().map(|parent| &mut parent.conflict);
+                        let kept = nogoods.learn_frame(
+                            &self.grid,
+                            frame.min_column,
+                            frame.selected_rows.len(),
+                            frame.conflict,
+                            parent,
+                        );
+                        self.stats.learned += u64::from(kept);
+                    }
+                }
+                StackOp::None => {}
+            }
+
+            if found_solution {
+                return Step::Solution;
+            }
+            if stopped {
+                return Step::Break;
+            }
+        }
+
+        Step::Exhausted
+    }
+}
@@ -1,35 +1,229 @@
 use crate::{
-    grid::{Column, Grid, Node},
+    grid::Grid,
+    learning::Learning,
+    search::{Search, Step},
     ExactCover,
 };
-use core::iter;
-use std::collections::VecDeque;
+use alloc::vec::Vec;
+use core::{fmt, iter, ops::ControlFlow, ops::Range};
 
 /// Solver that iteratively returns solutions to exact cover problems.
+///
+/// The `O` parameter is a [`SolverObserver`] which is notified of every step
+/// of the search, and does nothing by default.
 #[derive(Debug)]
-pub struct Solver<'e, E: ExactCover> {
+pub struct Solver<'e, E: ExactCover, O = ()> {
     problem: &'e E,
+    search: Search<O>,
+}
+
+/// Callbacks for the events of the search performed by a `Solver`.
+///
+/// Every method does nothing by default, and the `()` implementation (which is
+/// used unless [`Solver::with_observer`] is called) compiles away entirely.
+///
+/// Constraints and possibilities are identified by their index in
+/// [`ExactCover::constraints`] and [`ExactCover::possibilities`]. The `depth`
+/// of an event is the number of possibilities in the partial solution before
+/// the event.
+pub trait SolverObserver {
+    /// The search chose to branch on the given constraint, which can be
+    /// satisfied by `num_rows` of the remaining possibilities.
+    #[inline]
+    fn on_choose_column(&mut self, constraint: usize, num_rows: usize) {
+        let _ = (constraint, num_rows);
+    }
+
+    /// The search added the given possibility to the partial solution.
+    #[inline]
+    fn on_try_row(&mut self, possibility: usize, depth: usize) {
+        let _ = (possibility, depth);
+    }
+
+    /// The search removed the given possibility from the partial solution,
+    /// leaving `depth` possibilities.
+    #[inline]
+    fn on_backtrack(&mut self, possibility: usize, depth: usize) {
+        let _ = (possibility, depth);
+    }
 
-    // Values used to track the state of solving
-    grid: Grid,
-    partial_solution: Vec<usize>,
-    stack: Vec<Frame>,
+    /// The constraint chosen by the search can not be satisfied by any of the
+    /// remaining possibilities. This is always preceded by a call to
+    /// [`SolverObserver::on_choose_column`] for the same constraint.
+    #[inline]
+    fn on_dead_end(&mut self, constraint: usize) {
+        let _ = constraint;
+    }
+
+    /// The partial solution, as indices in the order they were chosen, covers
+    /// every primary constraint.
+    #[inline]
+    fn on_solution(&mut self, row_indices: &[usize]) {
+        let _ = row_indices;
+    }
 }
 
-#[derive(Debug)]
-enum FrameState {
-    // Before covering one of the rows
-    Cover,
-    // After checking, before uncovering
-    Uncover,
+impl SolverObserver for () {}
+
+impl<O: SolverObserver + ?Sized> SolverObserver for &mut O {
+    #[inline]
+    fn on_choose_column(&mut self, constraint: usize, num_rows: usize) {
+        (**self).on_choose_column(constraint, num_rows);
+    }
+
+    #[inline]
+    fn on_try_row(&mut self, possibility: usize, depth: usize) {
+        (**self).on_try_row(possibility, depth);
+    }
+
+    #[inline]
+    fn on_backtrack(&mut self, possibility: usize, depth: usize) {
+        (**self).on_backtrack(possibility, depth);
+    }
+
+    #[inline]
+    fn on_dead_end(&mut self, constraint: usize) {
+        (**self).on_dead_end(constraint);
+    }
+
+    #[inline]
+    fn on_solution(&mut self, row_indices: &[usize]) {
+        (**self).on_solution(row_indices);
+    }
 }
 
-#[derive(Debug)]
-struct Frame {
-    #[allow(dead_code)]
-    min_column: *mut Column,
-    selected_rows: VecDeque<(usize, Vec<*mut Column>)>,
-    state: FrameState,
+/// Strategy used to pick which column to branch on at each step of the search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ColumnHeuristic {
+    /// Choose the uncovered primary column with the fewest remaining rows,
+    /// breaking ties by column order.
+    ///
+    /// This is the heuristic recommended by Knuth, and usually explores far
+    /// fewer nodes than the alternative.
+    #[default]
+    MinimumRemainingValues,
+    /// Choose the first uncovered primary column, in column order.
+    FirstUncovered,
+}
+
+impl ColumnHeuristic {
+    /// Return the uncovered primary column to branch on next, or `None` if all
+    /// primary columns are covered.
+    pub(crate) fn choose_column(self, grid: &Grid) -> Option<usize> {
+        let mut primary_columns = grid.uncovered_primary_columns();
+
+        match self {
+            ColumnHeuristic::MinimumRemainingValues => {
+                primary_columns.min_by_key(|column| grid.column_size(*column))
+            }
+            ColumnHeuristic::FirstUncovered => primary_columns.next(),
+        }
+    }
+}
+
+/// Statistics about the search performed by a `Solver`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SolverStats {
+    /// The number of rows that were added to a partial solution.
+    pub nodes: u64,
+    /// The number of solutions found.
+    pub solutions: u64,
+    /// The number of times a chosen column had no rows left to cover it.
+    pub dead_ends: u64,
+    /// The number of partial solutions rejected by [`ExactCover::is_viable`],
+    /// or by a nogood when [learning](Solver::with_learning) is enabled.
+    pub pruned: u64,
+    /// The number of nogoods recorded by [learning](Solver::with_learning).
+    pub learned: u64,
+}
+
+/// What [`Solver::search_with`] does after visiting a partial solution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NodeControl {
+    /// Keep searching below the partial solution.
+    Continue,
+    /// Abandon the partial solution and every solution that extends it, like
+    /// a rejection by [`ExactCover::is_viable`].
+    Skip,
+    /// Skip the partial solution and stop the search.
+    Break,
+}
+
+/// The position of a `Solver` in its search, which can be used to resume the
+/// search later with [`Solver::from_checkpoint`].
+///
+/// A `Checkpoint` only records which possibilities were chosen at each level of
+/// the search, so it is small, and it is independent of the problem instance.
+/// It must be used with the same problem it was created from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    heuristic: ColumnHeuristic,
+    stats: SolverStats,
+    started: bool,
+    forced_rows: Vec<usize>,
+    frames: Vec<FramePosition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct FramePosition {
+    // The index of the row of the frame, among the rows of the chosen column
+    current_row: usize,
+    // True if the row is part of the partial solution
+    selected: bool,
+}
+
+/// A solution to an exact cover problem, recorded as the indices of its
+/// possibilities in [`ExactCover::possibilities`], in increasing order.
+///
+/// Unlike the solutions returned by a `Solver`, a `Solution` does not borrow
+/// the problem, so it can be stored or sent elsewhere and resolved later with
+/// [`Solution::resolve`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Solution {
+    /// The indices of the possibilities in the solution, in increasing order.
+    pub possibilities: Vec<usize>,
+}
+
+impl Solution {
+    /// Create a new `Solution` from the indices of its possibilities, in any
+    /// order.
+    pub fn new(possibilities: impl IntoIterator<Item = usize>) -> Self {
+        let mut possibilities: Vec<_> = possibilities.into_iter().collect();
+        possibilities.sort_unstable();
+
+        Self { possibilities }
+    }
+
+    /// Return the possibilities of the solution from the given problem, or
+    /// `None` if any of the indices is out of range.
+    ///
+    /// This does not check that the possibilities are actually an exact cover
+    /// of the problem.
+    pub fn resolve<'e, E: ExactCover>(&self, problem: &'e E) -> Option<Vec<&'e E::Possibility>> {
+        let possibilities = problem.possibilities();
+
+        self.possibilities
+            .iter()
+            .map(|index| possibilities.get(*index))
+            .collect()
+    }
+}
+
+// Implemented by hand so that `E` doesn't need to be `Clone`
+impl<E: ExactCover, O: Clone> Clone for Solver<'_, E, O> {
+    /// Fork the `Solver`, so that the copy resumes the search from the same
+    /// point as the original, independently.
+    fn clone(&self) -> Self {
+        Self {
+            problem: self.problem,
+            search: self.search.clone(),
+        }
+    }
 }
 
 impl<'e, E> Solver<'e, E>
@@ -38,43 +232,81 @@ where
 {
     /// Create a new `Solver` with the given instance of an exact cover problem.
     pub fn new(problem: &'e E) -> Self {
-        let grid = Self::populate_grid(problem);
+        Self::with_heuristic(problem, ColumnHeuristic::default())
+    }
 
-        let mut solver = Self {
-            problem,
+    /// Create a new `Solver` with the given instance of an exact cover problem,
+    /// which will use the given heuristic to choose columns.
+    pub fn with_heuristic(problem: &'e E, heuristic: ColumnHeuristic) -> Self {
+        Self::with_grid(problem, Self::populate_grid(problem), heuristic)
+    }
 
-            grid,
-            partial_solution: Vec::new(),
-            stack: Vec::new(),
-        };
-
-        // If the grid is already solved (no primary columns), don't bother to put a
-        // stack frame in
-        if !Self::solution_test(&solver.grid, solver.problem) {
-            let min_column = Self::choose_column(&mut solver.grid, solver.problem);
-            let selected_rows = Self::select_rows_from_column(min_column);
-
-            if !selected_rows.is_empty() {
-                solver.stack.push(Frame {
-                    state: FrameState::Cover,
-                    min_column,
-                    selected_rows,
-                });
-            }
+    /// Create a new `Solver` which searches the given grid, which must have
+    /// been built from `problem`, or from a problem with the same rows and
+    /// columns.
+    pub(crate) fn with_grid(problem: &'e E, grid: Grid, heuristic: ColumnHeuristic) -> Self {
+        Self {
+            problem,
+            search: Search::new(grid, heuristic),
         }
+    }
+
+    /// Create a new `Solver` which only returns the solutions that contain all
+    /// of the given possibilities, identified by their index in
+    /// [`ExactCover::possibilities`].
+    ///
+    /// The forced possibilities are part of every solution returned, and the
+    /// rest of each solution is found the same way as with
+    /// [`Solver::with_heuristic`]. This is used to solve the jobs created by
+    /// [`split_jobs`](crate::jobs::split_jobs).
+    ///
+    /// Returns `None` if any index is out of range, if a possibility doesn't
+    /// satisfy any constraint, or if two of the possibilities satisfy the same
+    /// constraint.
+    pub fn with_forced_rows(
+        problem: &'e E,
+        heuristic: ColumnHeuristic,
+        rows: &[usize],
+    ) -> Option<Self> {
+        Self::with_heuristic(problem, heuristic).force_rows(rows)
+    }
 
+    /// Select the given rows in the fresh grid of this `Solver`, as described
+    /// by [`Solver::with_forced_rows`].
+    pub(crate) fn force_rows(mut self, rows: &[usize]) -> Option<Self> {
+        let selected = self.search.force_rows(rows, viable(self.problem));
+        selected.then_some(self)
+    }
+
+    /// Create a new `Solver` for the given problem, which resumes the search
+    /// from the given `Checkpoint`.
+    ///
+    /// Returns `None` if the checkpoint does not describe a valid position in
+    /// the search of this problem. This is only a partial check, so using a
+    /// checkpoint from a different problem may still produce wrong results.
+    pub fn from_checkpoint(problem: &'e E, checkpoint: &Checkpoint) -> Option<Self> {
+        let mut solver =
+            Self::with_forced_rows(problem, checkpoint.heuristic, &checkpoint.forced_rows)?;
+        let positions = checkpoint
+            .frames
+            .iter()
+            .map(|position| (position.current_row, position.selected));
         solver
+            .search
+            .replay(checkpoint.started, checkpoint.stats, positions)?;
+
+        Some(solver)
     }
 
-    /// Reset all solver state except for the stored possibilities and
-    /// constraints.
-    pub fn reset(&mut self) {
-        self.grid = Self::populate_grid(self.problem);
-        self.partial_solution.clear();
-        self.stack.clear();
+    pub(crate) fn populate_grid(problem: &E) -> Grid {
+        let mut grid = Grid::new(0, []);
+        Self::repopulate_grid(&mut grid, problem);
+
+        grid
     }
 
-    fn populate_grid(problem: &E) -> Grid {
+    /// Rebuild the given grid for `problem`, reusing its memory.
+    fn repopulate_grid(grid: &mut Grid, problem: &E) {
         let coordinates_iter = problem
             .possibilities()
             .iter()
@@ -101,36 +333,139 @@ where
                 }
             });
 
-        Grid::new(problem.constraints().len(), coordinates_iter)
+        let optional_columns = problem
+            .constraints()
+            .iter()
+            .enumerate()
+            .filter(|(_, cons)| problem.is_optional(cons))
+            .map(|(col_idx, _)| col_idx + 1);
+
+        grid.rebuild(
+            problem.constraints().len(),
+            optional_columns,
+            coordinates_iter,
+        );
+    }
+}
+
+impl<'e, E, O> Solver<'e, E, O>
+where
+    E: ExactCover,
+    O: SolverObserver,
+{
+    /// Replace the observer which is notified of the steps of the search.
+    ///
+    /// The new observer only sees the steps taken after this call, so it is
+    /// usually set before looking for any solutions. Pass `&mut observer` to
+    /// keep ownership of the observer.
+    pub fn with_observer<P: SolverObserver>(self, observer: P) -> Solver<'e, E, P> {
+        Solver {
+            problem: self.problem,
+            search: self.search.with_observer(observer),
+        }
+    }
+
+    /// Turn this solver into a solver for another problem, reusing the
+    /// memory of its grid and search state.
+    ///
+    /// The new solver starts from scratch, as if it was created by
+    /// [`Solver::with_heuristic`] with the same heuristic. The observer is
+    /// kept, and so is [learning](Solver::with_learning) if it was enabled,
+    /// without any of the nogoods of the old problem. This saves most of the
+    /// allocations of creating a new `Solver` when solving many problems of a
+    /// similar size, like one sudoku after the other.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dancing_links::{queens::NQueens, Solver};
+    ///
+    /// let four = NQueens::new(4, []);
+    /// let mut solver = Solver::new(&four);
+    /// assert_eq!(solver.by_ref().count(), 2);
+    ///
+    /// let six = NQueens::new(6, []);
+    /// let solver = solver.reuse_for(&six);
+    /// assert_eq!(solver.count(), 4);
+    /// ```
+    pub fn reuse_for<'f, F: ExactCover>(self, problem: &'f F) -> Solver<'f, F, O> {
+        let mut search = self.search;
+        search.rebuild(|grid| Solver::<F>::repopulate_grid(grid, problem));
+
+        Solver { problem, search }
     }
 
-    fn solution_test(grid: &Grid, problem: &E) -> bool {
-        !grid
-            .uncovered_columns()
-            .any(|column| !problem.is_optional(&problem.constraints()[Column::index(column) - 1]))
+    /// Enable nogood learning with the given configuration, which can prune
+    /// the search on hard problems at the cost of some bookkeeping at every
+    /// step. See [`Learning`] for how it works.
+    ///
+    /// Learning is off by default. The learned nogoods are kept when the
+    /// solver is [reset](Solver::reset), but they are not part of a
+    /// [`Checkpoint`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dancing_links::{latin_square::LatinSquare, ExactCover, Learning};
+    ///
+    /// let latin = LatinSquare::new(4, []);
+    /// let mut solver = latin.solver().with_learning(Learning::default());
+    /// assert_eq!(solver.by_ref().count(), 576);
+    /// assert_eq!(solver.stats().solutions, 576);
+    /// ```
+    pub fn with_learning(mut self, learning: Learning) -> Self {
+        self.search = self.search.with_learning(learning);
+        self
     }
 
-    fn choose_column(grid: &mut Grid, problem: &E) -> *mut Column {
-        grid.uncovered_columns_mut()
-            .filter(|column| {
-                !problem.is_optional(&problem.constraints()[Column::index(*column as *const _) - 1])
-            })
-            .min_by_key(|column_ptr| Column::size(*column_ptr))
-            .unwrap()
-    }
-
-    fn select_rows_from_column(min_column: *mut Column) -> VecDeque<(usize, Vec<*mut Column>)> {
-        Column::rows(min_column)
-            .map(|node_ptr| {
-                (
-                    Node::row_index(node_ptr),
-                    Node::neighbors(node_ptr)
-                        .map(Node::column_ptr)
-                        .chain(iter::once(Node::column_ptr(node_ptr)))
-                        .collect(),
-                )
-            })
-            .collect()
+    /// Try the rows of each chosen column in a random order, generated from
+    /// the given seed, instead of in row order.
+    pub(crate) fn with_shuffled_rows(mut self, seed: u64) -> Self {
+        self.search = self.search.with_shuffled_rows(seed);
+        self
+    }
+
+    /// Return a reference to the observer.
+    pub fn observer(&self) -> &O {
+        self.search.observer()
+    }
+
+    /// Return a mutable reference to the observer.
+    pub fn observer_mut(&mut self) -> &mut O {
+        self.search.observer_mut()
+    }
+
+    /// Reset all solver state except for the stored possibilities and
+    /// constraints, the forced possibilities, and the observer.
+    ///
+    /// The forced possibilities are checked by [`ExactCover::is_viable`]
+    /// again, one at a time.
+    pub fn reset(&mut self) {
+        self.search.reset(viable(self.problem));
+    }
+
+    /// Return statistics about the search performed so far.
+    pub fn stats(&self) -> SolverStats {
+        self.search.stats()
+    }
+
+    /// Record the current position of the search, so that it can be resumed
+    /// with [`Solver::from_checkpoint`].
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            heuristic: self.search.heuristic(),
+            stats: self.search.stats(),
+            started: self.search.started(),
+            forced_rows: self.search.forced_rows().to_vec(),
+            frames: self
+                .search
+                .positions()
+                .map(|(current_row, selected)| FramePosition {
+                    current_row,
+                    selected,
+                })
+                .collect(),
+        }
     }
 
     /// Return all possible solutions.
@@ -143,97 +478,340 @@ where
     where
         'e: 's,
     {
-        enum StackOp<T> {
-            Push(T),
-            Pop,
-            None,
+        self.advance().then(|| self.current_solution())
+    }
+
+    /// Return the possibilities of the solution found by the last step of the
+    /// search.
+    pub(crate) fn current_solution(&self) -> Vec<&'e E::Possibility> {
+        self.search
+            .partial_solution()
+            .iter()
+            .map(|row_index| &self.problem.possibilities()[*row_index])
+            .collect()
+    }
+
+    /// Call `visit` with each remaining solution, until there are no more
+    /// solutions or `visit` returns [`ControlFlow::Break`].
+    ///
+    /// Unlike [`Solver::next_solution`], this does not allocate for every
+    /// solution. The `Solver` can be resumed after breaking, by calling this
+    /// function (or any of the other solving methods) again.
+    ///
+    /// Returns [`ControlFlow::Break`] if `visit` stopped the search early.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::ops::ControlFlow;
+    /// use dancing_links::{queens::NQueens, ExactCover};
+    ///
+    /// let queens = NQueens::new(6, []);
+    /// let mut solver = queens.solver();
+    ///
+    /// let mut count = 0;
+    /// let _ = solver.solve_with(|solution| {
+    ///     assert_eq!(solution.depth(), 6);
+    ///     count += 1;
+    ///     ControlFlow::Continue(())
+    /// });
+    /// assert_eq!(count, 4);
+    /// ```
+    pub fn solve_with<F>(&mut self, mut visit: F) -> ControlFlow<()>
+    where
+        F: FnMut(&SolutionContext<'_, 'e, E>) -> ControlFlow<()>,
+    {
+        while self.advance() {
+            visit(&SolutionContext::new(
+                self.problem,
+                self.search.partial_solution(),
+            ))?;
         }
 
-        while !self.stack.is_empty() {
-            let curr_frame = self.stack.last_mut().unwrap();
-
-            let (stack_op, possible_solution) = match curr_frame.state {
-                // for the current row of this frame, cover the selected columns and add the row
-                // to the solution.
-                FrameState::Cover => {
-                    let (row_index, columns) = curr_frame.selected_rows.front().unwrap();
-
-                    self.partial_solution.push(row_index - 1);
-                    for column_ptr in columns {
-                        Column::cover(*column_ptr);
-                    }
-
-                    // This is where the recursion happens, but we also have to check for the
-                    // solution here.
-                    let stack_op = if Self::solution_test(&self.grid, self.problem) {
-                        (StackOp::None, Some(self.partial_solution.clone()))
-                    } else {
-                        let min_column = Self::choose_column(&mut self.grid, self.problem);
-                        let selected_rows = Self::select_rows_from_column(min_column);
-
-                        if selected_rows.is_empty() {
-                            (StackOp::None, None)
-                        } else {
-                            (
-                                StackOp::Push(Frame {
-                                    state: FrameState::Cover,
-                                    min_column,
-                                    selected_rows,
-                                }),
-                                None,
-                            )
-                        }
-                    };
+        ControlFlow::Continue(())
+    }
 
-                    curr_frame.state = FrameState::Uncover;
-                    stack_op
-                }
-                // Cleanup the current row, uncover the selected columns, remove the row from
-                // the solution.
-                FrameState::Uncover => {
-                    let (_row_index, columns) = curr_frame.selected_rows.pop_front().unwrap();
-
-                    for column_ptr in columns.into_iter().rev() {
-                        Column::uncover(column_ptr);
-                    }
-                    self.partial_solution.pop();
-
-                    if curr_frame.selected_rows.is_empty() {
-                        (StackOp::Pop, None)
-                    } else {
-                        curr_frame.state = FrameState::Cover;
-                        (StackOp::None, None)
-                    }
-                }
-            };
+    /// Call `visit_node` with every partial solution reached by the search,
+    /// and `visit` with each remaining solution, until there are no more
+    /// solutions or either callback stops the search.
+    ///
+    /// `visit_node` is called each time the search adds a possibility to the
+    /// partial solution and [`ExactCover::is_viable`] accepts it, including
+    /// the last possibility of a solution, before `visit` is called. Its
+    /// [`NodeControl`] decides whether the search goes on below the partial
+    /// solution, skips it, or stops. Skipped partial solutions are counted in
+    /// [`SolverStats::pruned`]. The possibilities forced with
+    /// [`Solver::with_forced_rows`] are not visited.
+    ///
+    /// Returns [`ControlFlow::Break`] if either callback stopped the search
+    /// early. The search can be resumed like with [`Solver::solve_with`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use core::ops::ControlFlow;
+    /// use dancing_links::{queens::NQueens, ExactCover, NodeControl};
+    ///
+    /// let queens = NQueens::new(8, []);
+    /// let mut solver = queens.solver();
+    ///
+    /// // Leave out the solutions with a queen in the top left corner, without
+    /// // searching below it
+    /// let mut count = 0;
+    /// let _ = solver.search_with(
+    ///     |partial| match partial.possibilities().last() {
+    ///         Some(queen) if (queen.row, queen.column) == (0, 0) => NodeControl::Skip,
+    ///         _ => NodeControl::Continue,
+    ///     },
+    ///     |_| {
+    ///         count += 1;
+    ///         ControlFlow::Continue(())
+    ///     },
+    /// );
+    /// assert_eq!(count, 92 - 4);
+    /// assert!(solver.stats().pruned > 0);
+    /// ```
+    pub fn search_with<N, F>(&mut self, mut visit_node: N, mut visit: F) -> ControlFlow<()>
+    where
+        N: FnMut(&PartialSolution<'_, 'e, E>) -> NodeControl,
+        F: FnMut(&SolutionContext<'_, 'e, E>) -> ControlFlow<()>,
+    {
+        loop {
+            match self.run(u64::MAX, &mut visit_node) {
+                Step::Solution => visit(&SolutionContext::new(
+                    self.problem,
+                    self.search.partial_solution(),
+                ))?,
+                Step::Break => return ControlFlow::Break(()),
+                Step::Exhausted | Step::Limit => return ControlFlow::Continue(()),
+            }
+        }
+    }
 
-            match stack_op {
-                StackOp::Push(val) => {
-                    self.stack.push(val);
-                }
-                StackOp::Pop => {
-                    self.stack.pop();
-                }
-                StackOp::None => {}
+    /// Run the search until the next solution is found, returning `true` with
+    /// the solution left in `partial_solution`, or `false` if there are no more
+    /// solutions.
+    fn advance(&mut self) -> bool {
+        self.advance_within(u64::MAX).unwrap_or(false)
+    }
+
+    /// Like [`Solver::advance`], but stop with `None` once the search has
+    /// added `node_limit` rows to a partial solution, counted by
+    /// [`SolverStats::nodes`]. The search can be resumed afterwards.
+    pub(crate) fn advance_within(&mut self, node_limit: u64) -> Option<bool> {
+        match self.run(node_limit, |_| NodeControl::Continue) {
+            Step::Solution => Some(true),
+            Step::Exhausted => Some(false),
+            Step::Limit => None,
+            Step::Break => unreachable!("The search was not stopped"),
+        }
+    }
+
+    /// Run the search until it finds a solution, runs out of solutions, tries
+    /// `node_limit` rows, or `visit_node` stops it.
+    ///
+    /// `visit_node` is only called with the partial solutions accepted by
+    /// [`ExactCover::is_viable`].
+    fn run<N>(&mut self, node_limit: u64, mut visit_node: N) -> Step
+    where
+        N: FnMut(&PartialSolution<'_, 'e, E>) -> NodeControl,
+    {
+        let problem = self.problem;
+        self.search.run(node_limit, |row_indices, grid| {
+            let partial = PartialSolution::from_grid(problem, row_indices, grid);
+            if problem.is_viable(&partial) {
+                visit_node(&partial)
+            } else {
+                NodeControl::Skip
             }
+        })
+    }
+}
 
-            if let Some(solution) = possible_solution {
-                return Some(
-                    solution
-                        .into_iter()
-                        .map(|row_index| &self.problem.possibilities()[row_index])
-                        .collect(),
-                );
+/// Return a function which checks the partial solutions of `problem` with
+/// [`ExactCover::is_viable`], for [`Search`].
+fn viable<E: ExactCover>(problem: &E) -> impl FnMut(&[usize], &Grid) -> bool + '_ {
+    move |row_indices, grid| {
+        problem.is_viable(&PartialSolution::from_grid(problem, row_indices, grid))
+    }
+}
+
+/// A borrowed view of a partial solution, passed to [`ExactCover::is_viable`].
+pub struct PartialSolution<'s, 'e, E: ExactCover> {
+    problem: &'e E,
+    row_indices: &'s [usize],
+    remaining: Remaining<'s>,
+
+    // The rows and constraints of the solver which belong to `problem`, which is
+    // only part of the solved problem when it is wrapped by one of the
+    // `combinators`. Row `row_range.start + i` of the solver is possibility `i`
+    // of `problem`, and likewise for constraints.
+    row_range: Range<usize>,
+    constraint_offset: usize,
+}
+
+/// How a `PartialSolution` finds the number of possibilities left for each
+/// constraint.
+#[derive(Clone, Copy)]
+enum Remaining<'s> {
+    Grid(&'s Grid),
+    Function(&'s dyn Fn(usize) -> Option<usize>),
+}
+
+impl<'s, 'e, E: ExactCover> PartialSolution<'s, 'e, E> {
+    pub(crate) fn from_grid(problem: &'e E, row_indices: &'s [usize], grid: &'s Grid) -> Self {
+        Self::new(problem, row_indices, Remaining::Grid(grid))
+    }
+
+    /// Create a `PartialSolution` for a solver which doesn't use a `Grid`,
+    /// where `remaining` implements [`PartialSolution::remaining`].
+    pub(crate) fn from_fn(
+        problem: &'e E,
+        row_indices: &'s [usize],
+        remaining: &'s dyn Fn(usize) -> Option<usize>,
+    ) -> Self {
+        Self::new(problem, row_indices, Remaining::Function(remaining))
+    }
+
+    fn new(problem: &'e E, row_indices: &'s [usize], remaining: Remaining<'s>) -> Self {
+        Self {
+            problem,
+            row_indices,
+            remaining,
+
+            row_range: 0..problem.possibilities().len(),
+            constraint_offset: 0,
+        }
+    }
+
+    /// Return a view of the part of this partial solution that belongs to a
+    /// problem wrapped by this one, whose possibilities and constraints start
+    /// at the given offsets.
+    pub(crate) fn part<'f, F: ExactCover>(
+        &self,
+        problem: &'f F,
+        possibility_offset: usize,
+        constraint_offset: usize,
+    ) -> PartialSolution<'s, 'f, F> {
+        let start = self.row_range.start + possibility_offset;
+
+        PartialSolution {
+            problem,
+            row_indices: self.row_indices,
+            remaining: self.remaining,
+
+            row_range: start..(start + problem.possibilities().len()),
+            constraint_offset: self.constraint_offset + constraint_offset,
+        }
+    }
+
+    /// Return the indices (into [`ExactCover::possibilities`]) of the
+    /// possibilities in this partial solution, in the order they were chosen.
+    pub fn row_indices(&self) -> impl Iterator<Item = usize> + 's {
+        let Range { start, end } = self.row_range;
+
+        self.row_indices
+            .iter()
+            .filter(move |row_index| (start..end).contains(*row_index))
+            .map(move |row_index| row_index - start)
+    }
+
+    /// Return the number of possibilities in this partial solution.
+    pub fn depth(&self) -> usize {
+        self.row_indices().count()
+    }
+
+    /// Return an iterator over the possibilities in this partial solution, in
+    /// the order they were chosen. The last one is usually the possibility
+    /// that was just added.
+    pub fn possibilities(&self) -> impl Iterator<Item = &'e E::Possibility> + 's
+    where
+        'e: 's,
+    {
+        let possibilities = self.problem.possibilities();
+
+        self.row_indices()
+            .map(move |row_index| &possibilities[row_index])
+    }
+
+    /// Return the number of remaining possibilities which could still satisfy
+    /// the constraint at the given index, or `None` if the constraint is
+    /// already satisfied by the partial solution.
+    ///
+    /// A remaining possibility doesn't satisfy any constraint that is already
+    /// satisfied, so it could be added to the partial solution.
+    pub fn remaining(&self, constraint: usize) -> Option<usize> {
+        let constraint = self.constraint_offset + constraint;
+
+        match self.remaining {
+            Remaining::Grid(grid) => {
+                let column = constraint + 1;
+                (!grid.is_column_covered(column)).then(|| grid.column_size(column))
             }
+            Remaining::Function(remaining) => remaining(constraint),
+        }
+    }
+}
+
+impl<E: ExactCover> fmt::Debug for PartialSolution<'_, '_, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PartialSolution")
+            .field("row_indices", &self.row_indices().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
+
+/// A borrowed view of a solution found by [`Solver::solve_with`].
+#[derive(Debug)]
+pub struct SolutionContext<'s, 'e, E: ExactCover> {
+    problem: &'e E,
+    row_indices: &'s [usize],
+}
+
+impl<'s, 'e, E: ExactCover> SolutionContext<'s, 'e, E> {
+    pub(crate) fn new(problem: &'e E, row_indices: &'s [usize]) -> Self {
+        Self {
+            problem,
+            row_indices,
         }
+    }
+
+    /// Return the indices (into [`ExactCover::possibilities`]) of the
+    /// possibilities in this solution, in the order they were chosen.
+    pub fn row_indices(&self) -> &'s [usize] {
+        self.row_indices
+    }
+
+    /// Return an owned copy of this solution, which does not borrow the
+    /// problem.
+    pub fn to_solution(&self) -> Solution {
+        Solution::new(self.row_indices.iter().copied())
+    }
+
+    /// Return the number of possibilities in this solution, which is also the
+    /// depth of the search tree at which it was found.
+    pub fn depth(&self) -> usize {
+        self.row_indices.len()
+    }
 
-        None
+    /// Return an iterator over the possibilities in this solution, in the
+    /// order they were chosen.
+    pub fn possibilities(&self) -> impl Iterator<Item = &'e E::Possibility> + 's
+    where
+        'e: 's,
+    {
+        let possibilities = self.problem.possibilities();
+
+        self.row_indices
+            .iter()
+            .map(move |row_index| &possibilities[*row_index])
     }
 }
 
-impl<'e, E> Iterator for Solver<'e, E>
+impl<'e, E, O> Iterator for Solver<'e, E, O>
 where
     E: ExactCover,
+    O: SolverObserver,
 {
     type Item = Vec<&'e E::Possibility>;
 
@@ -241,3 +819,184 @@ where
         self.next_solution()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        testing::{random_problem, reference_solutions, RandomProblemConfig},
+        text::TextProblem,
+    };
+
+    /// Return the solutions found by `solve_with`, in increasing order.
+    fn sorted_solutions(
+        solve_with: impl FnOnce(&mut dyn FnMut(Solution) -> ControlFlow<()>),
+    ) -> Vec<Solution> {
+        let mut solutions = Vec::new();
+        solve_with(&mut |solution| {
+            solutions.push(solution);
+            ControlFlow::Continue(())
+        });
+        solutions.sort();
+
+        solutions
+    }
+
+    /// Run `search_with`, and return the partial solutions it visited and the
+    /// solutions it found, as row indices in the order they were chosen.
+    fn search_order(
+        solver: &mut Solver<TextProblem>,
+        mut control: impl FnMut(&[usize]) -> NodeControl,
+    ) -> (ControlFlow<()>, Vec<Vec<usize>>, Vec<Vec<usize>>) {
+        let mut visited = Vec::new();
+        let mut solutions = Vec::new();
+        let flow = solver.search_with(
+            |partial| {
+                let row_indices: Vec<_> = partial.row_indices().collect();
+                let next = control(&row_indices);
+                visited.push(row_indices);
+                next
+            },
+            |solution| {
+                solutions.push(solution.row_indices().to_vec());
+                ControlFlow::Continue(())
+            },
+        );
+
+        (flow, visited, solutions)
+    }
+
+    /// The problem of the `search_with` tests, whose search with
+    /// [`ColumnHeuristic::FirstUncovered`] tries `[0]`, `[0, 1]`, `[0, 1, 2]`,
+    /// `[0, 4]`, `[3]` and `[3, 2]`.
+    fn small_problem() -> TextProblem {
+        TextProblem::parse("a b c\na\nb\nc\na b\nb c\n").unwrap()
+    }
+
+    #[test]
+    fn search_with_continue() {
+        let problem = small_problem();
+        let mut solver = Solver::with_heuristic(&problem, ColumnHeuristic::FirstUncovered);
+
+        let (flow, visited, solutions) = search_order(&mut solver, |_| NodeControl::Continue);
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(
+            visited,
+            [
+                vec![0],
+                vec![0, 1],
+                vec![0, 1, 2],
+                vec![0, 4],
+                vec![3],
+                vec![3, 2]
+            ]
+        );
+        assert_eq!(solutions, [vec![0, 1, 2], vec![0, 4], vec![3, 2]]);
+        assert_eq!(solver.stats().pruned, 0);
+    }
+
+    #[test]
+    fn search_with_skip() {
+        let problem = small_problem();
+        let mut solver = Solver::with_heuristic(&problem, ColumnHeuristic::FirstUncovered);
+
+        // Skipping `[0, 1]` leaves out `[0, 1, 2]`, the only node below it
+        let (flow, visited, solutions) = search_order(&mut solver, |row_indices| {
+            if row_indices == [0, 1] {
+                NodeControl::Skip
+            } else {
+                NodeControl::Continue
+            }
+        });
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(
+            visited,
+            [vec![0], vec![0, 1], vec![0, 4], vec![3], vec![3, 2]]
+        );
+        assert_eq!(solutions, [vec![0, 4], vec![3, 2]]);
+        assert_eq!(solver.stats().pruned, 1);
+        assert_eq!(solver.stats().nodes, 5);
+    }
+
+    #[test]
+    fn search_with_break() {
+        let problem = small_problem();
+        let mut solver = Solver::with_heuristic(&problem, ColumnHeuristic::FirstUncovered);
+
+        let (flow, visited, solutions) = search_order(&mut solver, |row_indices| {
+            if row_indices == [0, 1] {
+                NodeControl::Break
+            } else {
+                NodeControl::Continue
+            }
+        });
+        assert_eq!(flow, ControlFlow::Break(()));
+        assert_eq!(visited, [vec![0], vec![0, 1]]);
+        assert!(solutions.is_empty());
+
+        // The search resumes after the node it stopped at, which is skipped
+        let (flow, visited, solutions) = search_order(&mut solver, |_| NodeControl::Continue);
+        assert_eq!(flow, ControlFlow::Continue(()));
+        assert_eq!(visited, [vec![0, 4], vec![3], vec![3, 2]]);
+        assert_eq!(solutions, [vec![0, 4], vec![3, 2]]);
+    }
+
+    #[test]
+    fn search_with_random_problems() {
+        for seed in 0..if cfg!(miri) { 20 } else { 300 } {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let expected = reference_solutions(&problem);
+            let without = |rows: &[usize]| -> Vec<Solution> {
+                expected
+                    .iter()
+                    .filter(|solution| !rows.iter().all(|row| solution.possibilities.contains(row)))
+                    .cloned()
+                    .collect()
+            };
+
+            // Skipping the partial solutions with the first possibility leaves
+            // out exactly the solutions with it
+            let mut solver = problem.solver();
+            let solutions = sorted_solutions(|visit| {
+                let _ = solver.search_with(
+                    |partial| {
+                        if partial.row_indices().any(|row| row == 0) {
+                            NodeControl::Skip
+                        } else {
+                            NodeControl::Continue
+                        }
+                    },
+                    |solution| visit(solution.to_solution()),
+                );
+            });
+            assert_eq!(solutions, without(&[0]), "seed {seed}");
+
+            // Breaking at the third node skips it, and the search resumes after it
+            let mut solver = problem.solver();
+            let mut visited = 0;
+            let mut skipped = Vec::new();
+            let solutions = sorted_solutions(|visit| {
+                let flow = solver.search_with(
+                    |partial| {
+                        visited += 1;
+                        if visited == 3 {
+                            skipped = partial.row_indices().collect();
+                            NodeControl::Break
+                        } else {
+                            NodeControl::Continue
+                        }
+                    },
+                    |solution| visit(solution.to_solution()),
+                );
+                assert_eq!(flow.is_break(), visited >= 3, "seed {seed}");
+                let _ = solver.solve_with(|solution| visit(solution.to_solution()));
+            });
+            let expected = if skipped.is_empty() {
+                expected.clone()
+            } else {
+                without(&skipped)
+            };
+            assert_eq!(solutions, expected, "seed {seed}");
+        }
+    }
+}
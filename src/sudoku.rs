@@ -3,10 +3,11 @@
 //! sub-array contains the values `1` through `n` with no repeats.
 
 use super::{latin_square, ExactCover};
-use std::collections::HashSet;
+use alloc::{collections::BTreeSet, vec::Vec};
 
 /// An instance of a Sudoku puzzle.
 #[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sudoku {
     /// The list of possible values and positions that are valid for this Sudoku
     /// puzzle.
@@ -29,7 +30,7 @@ impl Sudoku {
 
         let latin = latin_square::LatinSquare::new(side_length, filled_values.iter().copied());
 
-        let satisfied: HashSet<_> = filled_values
+        let satisfied: BTreeSet<_> = filled_values
             .iter()
             .copied()
             .map(|latin_poss| Possibility::from_latin(latin_poss, box_side_length))
@@ -87,6 +88,7 @@ impl ExactCover for Sudoku {
 
 /// A position and value for a box inside of a Sudoku puzzle.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Possibility {
     /// The row position of the box.
     ///
@@ -168,6 +170,7 @@ impl From<Possibility> for latin_square::Possibility {
 
 /// A condition which must be satisfied in order to solve a Sudoku puzzle.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Constraint {
     /// A constraint which is also shared by a Latin Square puzzle.
     Latin(latin_square::Constraint),
@@ -0,0 +1,362 @@
+//! Tools for checking exact cover solvers against each other: a generator of
+//! random problem instances, and a naive reference solver.
+//!
+//! This module is only available with the `testing` feature.
+//!
+//! # Example
+//!
+//! ```
+//! use dancing_links::{
+//!     testing::{random_problem, reference_solutions, RandomProblemConfig},
+//!     ExactCover,
+//! };
+//!
+//! for seed in 0..20 {
+//!     let config = RandomProblemConfig::random(seed);
+//!     let problem = random_problem(seed, &config);
+//!
+//!     let mut solutions: Vec<_> = problem
+//!         .solver()
+//!         .map(|solution| {
+//!             let mut indices: Vec<_> = solution.iter().map(|poss| poss.index).collect();
+//!             indices.sort();
+//!             indices
+//!         })
+//!         .collect();
+//!     solutions.sort();
+//!
+//!     let expected: Vec<_> = reference_solutions(&problem)
+//!         .into_iter()
+//!         .map(|solution| solution.possibilities)
+//!         .collect();
+//!     assert_eq!(solutions, expected, "seed {seed}");
+//! }
+//! ```
+
+use crate::{
+    text::{Constraint, Possibility, TextProblem},
+    util::SplitMix64,
+    ExactCover, Solution,
+};
+use alloc::{format, vec, vec::Vec};
+
+/// Parameters for [`random_problem`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RandomProblemConfig {
+    /// The number of constraints in the problem.
+    pub num_constraints: usize,
+    /// The number of possibilities in the problem, not counting the ones added
+    /// by `planted_solution`.
+    pub num_possibilities: usize,
+    /// The probability that a possibility satisfies any given constraint.
+    pub density: f64,
+    /// The probability that any given constraint is optional.
+    pub optional_probability: f64,
+    /// If true, add possibilities which form a solution to the problem, so
+    /// that it usually has at least one solution.
+    pub planted_solution: bool,
+    /// If true, make sure the problem has no solution by removing one primary
+    /// constraint from every possibility.
+    pub infeasible: bool,
+}
+
+impl RandomProblemConfig {
+    /// Return a configuration for a small problem, with parameters chosen at
+    /// random from the given seed.
+    ///
+    /// The problems are small enough that [`reference_solutions`] finishes
+    /// quickly.
+    pub fn random(seed: u64) -> Self {
+        let mut rng = SplitMix64::new(seed);
+
+        Self {
+            num_constraints: rng.below(11),
+            num_possibilities: rng.below(15),
+            density: 0.05 + 0.5 * rng.next_f64(),
+            optional_probability: if rng.chance(0.5) {
+                0.0
+            } else {
+                0.5 * rng.next_f64()
+            },
+            planted_solution: rng.chance(0.5),
+            infeasible: rng.chance(0.1),
+        }
+    }
+}
+
+/// Generate a random exact cover problem from the given seed.
+///
+/// The constraints are named `c0`, `c1`, etc. Optional constraints may appear
+/// anywhere in the list of constraints.
+pub fn random_problem(seed: u64, config: &RandomProblemConfig) -> TextProblem {
+    // Use a different stream than `RandomProblemConfig::random`, so that using
+    // the same seed for both doesn't correlate the parameters and the problem
+    let mut rng = SplitMix64::new(seed ^ 0x6a09_e667_f3bc_c909);
+
+    let constraints: Vec<_> = (0..config.num_constraints)
+        .map(|index| Constraint {
+            index,
+            name: format!("c{index}"),
+            optional: rng.chance(config.optional_probability),
+        })
+        .collect();
+
+    let mut rows: Vec<Vec<usize>> = (0..config.num_possibilities)
+        .map(|_| {
+            (0..config.num_constraints)
+                .filter(|_| rng.chance(config.density))
+                .collect()
+        })
+        .collect();
+
+    if config.planted_solution && config.num_constraints > 0 {
+        // Split the constraints into random groups, leaving some of the optional
+        // constraints out, and add each group as a possibility
+        let num_groups = 1 + rng.below(config.num_constraints);
+        let mut groups = vec![Vec::new(); num_groups];
+        for cons in &constraints {
+            if cons.optional && rng.chance(0.5) {
+                continue;
+            }
+            groups[rng.below(num_groups)].push(cons.index);
+        }
+
+        for group in groups.into_iter().filter(|group| !group.is_empty()) {
+            let position = rng.below(rows.len() + 1);
+            rows.insert(position, group);
+        }
+    }
+
+    if config.infeasible {
+        let primary: Vec<_> = constraints
+            .iter()
+            .filter(|cons| !cons.optional)
+            .map(|cons| cons.index)
+            .collect();
+
+        if !primary.is_empty() {
+            let removed = primary[rng.below(primary.len())];
+            for row in &mut rows {
+                row.retain(|cons| *cons != removed);
+            }
+        }
+    }
+
+    let possibilities = rows
+        .into_iter()
+        .enumerate()
+        .map(|(index, items)| Possibility { index, items })
+        .collect();
+
+    TextProblem {
+        possibilities,
+        constraints,
+    }
+}
+
+/// Return all solutions to the given problem, in increasing order, by trying
+/// every combination of possibilities.
+///
+/// This is exponential in the number of possibilities, so it is only useful
+/// for small problems. It does not share any code with the other solvers, so
+/// it can be used to check them.
+///
+/// Like the other solvers, a possibility which only satisfies optional
+/// constraints is never part of a solution, and a problem without any primary
+/// constraints has no solutions.
+pub fn reference_solutions<E: ExactCover>(problem: &E) -> Vec<Solution> {
+    let constraints = problem.constraints();
+    let primary: Vec<_> = constraints
+        .iter()
+        .map(|cons| !problem.is_optional(cons))
+        .collect();
+    if !primary.contains(&true) {
+        return Vec::new();
+    }
+    let rows: Vec<Vec<usize>> = problem
+        .possibilities()
+        .iter()
+        .map(|poss| {
+            (0..constraints.len())
+                .filter(|index| problem.satisfies(poss, &constraints[*index]))
+                .collect()
+        })
+        .collect();
+
+    let mut covered = vec![false; constraints.len()];
+    let mut chosen = Vec::new();
+    let mut solutions = Vec::new();
+    choose_from(
+        &rows,
+        &primary,
+        0,
+        &mut covered,
+        &mut chosen,
+        &mut solutions,
+    );
+    solutions.sort_unstable();
+
+    solutions
+}
+
+/// Extend the partial solution in `chosen` with possibilities from `next_row`
+/// onwards, in every possible way.
+fn choose_from(
+    rows: &[Vec<usize>],
+    primary: &[bool],
+    next_row: usize,
+    covered: &mut [bool],
+    chosen: &mut Vec<usize>,
+    solutions: &mut Vec<Solution>,
+) {
+    if next_row == rows.len() {
+        let all_primary_covered = primary
+            .iter()
+            .zip(covered.iter())
+            .all(|(is_primary, is_covered)| !is_primary || *is_covered);
+        if all_primary_covered {
+            solutions.push(Solution::new(chosen.iter().copied()));
+        }
+        return;
+    }
+
+    let row = &rows[next_row];
+    let can_choose =
+        row.iter().any(|cons| primary[*cons]) && row.iter().all(|cons| !covered[*cons]);
+    if can_choose {
+        row.iter().for_each(|cons| covered[*cons] = true);
+        chosen.push(next_row);
+        choose_from(rows, primary, next_row + 1, covered, chosen, solutions);
+        chosen.pop();
+        row.iter().for_each(|cons| covered[*cons] = false);
+    }
+
+    choose_from(rows, primary, next_row + 1, covered, chosen, solutions);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bitset::BitsetSolver, ColumnHeuristic, Solver};
+    use core::ops::ControlFlow;
+
+    // Fewer seeds on miri, which is much slower
+    const SEEDS: u64 = if cfg!(miri) { 20 } else { 500 };
+
+    /// Return the solutions found by `solve_with`, in increasing order.
+    fn sorted_solutions(
+        solve_with: impl FnOnce(&mut dyn FnMut(Solution) -> ControlFlow<()>),
+    ) -> Vec<Solution> {
+        let mut solutions = Vec::new();
+        solve_with(&mut |solution| {
+            solutions.push(solution);
+            ControlFlow::Continue(())
+        });
+        solutions.sort();
+
+        solutions
+    }
+
+    #[test]
+    fn reference_small_problem() {
+        let problem = TextProblem::parse("a b c d | x\na b\nc d x\na c\nb d\nx\n").unwrap();
+
+        assert_eq!(
+            reference_solutions(&problem),
+            vec![Solution::new([0, 1]), Solution::new([2, 3])]
+        );
+    }
+
+    #[test]
+    fn random_problems() {
+        let mut infeasible = 0;
+        let mut with_solutions = 0;
+        let mut with_optional = 0;
+        for seed in 0..SEEDS {
+            let config = RandomProblemConfig::random(seed);
+            let problem = random_problem(seed, &config);
+
+            assert_eq!(problem.constraints.len(), config.num_constraints);
+            assert!(problem.possibilities.len() >= config.num_possibilities);
+            assert!(problem
+                .possibilities
+                .iter()
+                .all(|poss| poss.items.windows(2).all(|pair| pair[0] < pair[1])));
+
+            let solutions = reference_solutions(&problem);
+            let has_primary = problem.constraints.iter().any(|cons| !cons.optional);
+            if config.infeasible && has_primary {
+                assert!(solutions.is_empty(), "seed {seed}");
+                infeasible += 1;
+            }
+            if config.planted_solution && !config.infeasible && has_primary {
+                assert!(!solutions.is_empty(), "seed {seed}");
+            }
+            if !solutions.is_empty() {
+                with_solutions += 1;
+            }
+            if problem.constraints.iter().any(|cons| cons.optional) {
+                with_optional += 1;
+            }
+        }
+
+        // Make sure the generator covers the interesting cases
+        assert!(infeasible > 10);
+        assert!(with_solutions > SEEDS / 3);
+        assert!(with_optional > SEEDS / 5);
+    }
+
+    #[test]
+    fn solver_matches_reference() {
+        for seed in 0..SEEDS {
+            let problem = random_problem(seed, &RandomProblemConfig::random(seed));
+            let expected = reference_solutions(&problem);
+
+            for heuristic in [
+                ColumnHeuristic::MinimumRemainingValues,
+                ColumnHeuristic::FirstUncovered,
+            ] {
+                let mut solver = Solver::with_heuristic(&problem, heuristic);
+                let solutions = sorted_solutions(|visit| {
+                    let _ = solver.solve_with(|solution| visit(solution.to_solution()));
+                });
+                assert_eq!(solutions, expected, "seed {seed}, {heuristic:?}");
+                assert_eq!(solver.stats().solutions, expected.len() as u64);
+
+                // The solver gives the same answers after being reset
+                solver.reset();
+                assert_eq!(solver.count(), expected.len(), "seed {seed}, {heuristic:?}");
+
+                let mut bitset = BitsetSolver::<_, 1>::with_heuristic(&problem, heuristic).unwrap();
+                let solutions = sorted_solutions(|visit| {
+                    let _ = bitset.solve_with(|solution| visit(solution.to_solution()));
+                });
+                assert_eq!(solutions, expected, "seed {seed}, {heuristic:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn larger_planted_problems() {
+        for seed in 0..(SEEDS / 10) {
+            let config = RandomProblemConfig {
+                num_constraints: 20,
+                num_possibilities: 18,
+                density: 0.15,
+                optional_probability: 0.2,
+                planted_solution: true,
+                infeasible: false,
+            };
+            let problem = random_problem(seed, &config);
+            let expected = reference_solutions(&problem);
+            assert!(!expected.is_empty());
+
+            let mut solutions: Vec<_> = problem
+                .solver()
+                .map(|solution| Solution::new(solution.iter().map(|poss| poss.index)))
+                .collect();
+            solutions.sort();
+            assert_eq!(solutions, expected, "seed {seed}");
+        }
+    }
+}
@@ -0,0 +1,485 @@
+//! A plain text format for describing arbitrary exact cover problems.
+//!
+//! The format is based on the one used by Knuth's `DLX` programs:
+//!
+//! ```text
+//! a b c d | x
+//! | The first line lists the items (constraints). Items after a lone `|`
+//! | are optional (secondary) items. After it, lines starting with a vertical
+//! | bar are comments, and every other line is an option (possibility),
+//! | listing the items it covers.
+//! a b
+//! c d x
+//! a c
+//! b d
+//! ```
+//!
+//! Item names are separated by whitespace and may contain any character
+//! besides whitespace and `|`. Blank lines are ignored.
+//!
+//! Unlike Knuth's format, the first line which isn't blank is always the items
+//! line, so that a line like `| x y` declares a problem with only optional
+//! items rather than being skipped as a comment. Comments can only follow the
+//! items line. To catch input which starts with comments anyway, an items line
+//! which starts with `|` can't be followed by a line which also starts with
+//! `|`.
+
+use crate::ExactCover;
+use alloc::{borrow::ToOwned, collections::BTreeMap, string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+/// An exact cover problem parsed from the text format.
+///
+/// A deserialized `TextProblem` is checked like a parsed one: the indices must
+/// match the positions of the options and items, and every option must only
+/// cover declared items.
+#[derive(Debug)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "UncheckedTextProblem")
+)]
+pub struct TextProblem {
+    /// The list of options, in the order they appeared in the input.
+    pub possibilities: Vec<Possibility>,
+    /// The list of items, in the order they appeared in the input.
+    pub constraints: Vec<Constraint>,
+}
+
+impl TextProblem {
+    /// Parse a problem from the text format.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut lines = input
+            .lines()
+            .enumerate()
+            .map(|(idx, line)| (idx + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .peekable();
+
+        let (items_line_number, items_line) = lines.next().ok_or(ParseError {
+            line: 0,
+            kind: ParseErrorKind::MissingItems,
+        })?;
+        if items_line.starts_with('|') {
+            if let Some((line_number, _)) = lines.next_if(|(_, line)| line.starts_with('|')) {
+                return Err(ParseError {
+                    line: line_number,
+                    kind: ParseErrorKind::CommentBeforeItems,
+                });
+            }
+        }
+
+        let mut constraints = Vec::new();
+        let mut item_indices = BTreeMap::new();
+        let mut optional = false;
+        for name in items_line.split_whitespace() {
+            if name == "|" {
+                if optional {
+                    return Err(ParseError {
+                        line: items_line_number,
+                        kind: ParseErrorKind::RepeatedSeparator,
+                    });
+                }
+                optional = true;
+                continue;
+            }
+
+            if name.contains('|') {
+                return Err(ParseError {
+                    line: items_line_number,
+                    kind: ParseErrorKind::InvalidItemName(name.to_owned()),
+                });
+            }
+
+            let index = constraints.len();
+            if item_indices.insert(name, index).is_some() {
+                return Err(ParseError {
+                    line: items_line_number,
+                    kind: ParseErrorKind::DuplicateItem(name.to_owned()),
+                });
+            }
+            constraints.push(Constraint {
+                index,
+                name: name.to_owned(),
+                optional,
+            });
+        }
+
+        if constraints.is_empty() {
+            return Err(ParseError {
+                line: items_line_number,
+                kind: ParseErrorKind::MissingItems,
+            });
+        }
+
+        let mut possibilities = Vec::new();
+        for (line_number, line) in lines.filter(|(_, line)| !line.starts_with('|')) {
+            let mut items = Vec::new();
+            for name in line.split_whitespace() {
+                let index = *item_indices.get(name).ok_or_else(|| ParseError {
+                    line: line_number,
+                    kind: ParseErrorKind::UnknownItem(name.to_owned()),
+                })?;
+
+                items.push(index);
+            }
+
+            items.sort_unstable();
+            if items.windows(2).any(|pair| pair[0] == pair[1]) {
+                return Err(ParseError {
+                    line: line_number,
+                    kind: ParseErrorKind::RepeatedItemInOption,
+                });
+            }
+
+            possibilities.push(Possibility {
+                index: possibilities.len(),
+                items,
+            });
+        }
+
+        Ok(Self {
+            possibilities,
+            constraints,
+        })
+    }
+
+    /// Return the names of the items covered by the given option, in the order
+    /// the items were declared.
+    pub fn item_names<'a>(&'a self, poss: &'a Possibility) -> impl Iterator<Item = &'a str> + 'a {
+        poss.items
+            .iter()
+            .map(move |index| self.constraints[*index].name.as_str())
+    }
+}
+
+impl FromStr for TextProblem {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl ExactCover for TextProblem {
+    type Constraint = Constraint;
+    type Possibility = Possibility;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        poss.items.binary_search(&cons.index).is_ok()
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        cons.optional
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        &self.possibilities
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        &self.constraints
+    }
+}
+
+/// An option from the input, which covers a set of items.
+///
+/// The items of a deserialized `Possibility` are sorted, and duplicates are
+/// removed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "UncheckedPossibility")
+)]
+pub struct Possibility {
+    /// The position of this option in the input, starting from 0.
+    pub index: usize,
+    /// The indices of the items covered by this option, in increasing order.
+    pub items: Vec<usize>,
+}
+
+/// The fields of a `TextProblem`, before they are checked.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedTextProblem {
+    possibilities: Vec<Possibility>,
+    constraints: Vec<Constraint>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UncheckedTextProblem> for TextProblem {
+    type Error = &'static str;
+
+    fn try_from(problem: UncheckedTextProblem) -> Result<Self, Self::Error> {
+        let UncheckedTextProblem {
+            possibilities,
+            constraints,
+        } = problem;
+
+        if constraints
+            .iter()
+            .enumerate()
+            .any(|(index, cons)| cons.index != index)
+        {
+            return Err("the index of an item doesn't match its position");
+        }
+        for (index, poss) in possibilities.iter().enumerate() {
+            if poss.index != index {
+                return Err("the index of an option doesn't match its position");
+            }
+            // The items are sorted, so only the last one needs to be checked
+            if poss
+                .items
+                .last()
+                .is_some_and(|item| *item >= constraints.len())
+            {
+                return Err("an option covers an item which wasn't declared");
+            }
+        }
+
+        Ok(Self {
+            possibilities,
+            constraints,
+        })
+    }
+}
+
+/// The fields of a `Possibility`, before its items are sorted.
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+struct UncheckedPossibility {
+    index: usize,
+    items: Vec<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl From<UncheckedPossibility> for Possibility {
+    fn from(poss: UncheckedPossibility) -> Self {
+        let UncheckedPossibility { index, mut items } = poss;
+        items.sort_unstable();
+        items.dedup();
+
+        Self { index, items }
+    }
+}
+
+/// An item from the input, which must be covered exactly once (or at most
+/// once, if it is optional).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Constraint {
+    /// The position of this item in the input, starting from 0.
+    pub index: usize,
+    /// The name of the item.
+    pub name: String,
+    /// True if this item was listed after the `|` separator.
+    pub optional: bool,
+}
+
+/// An error encountered while parsing the text format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line number (starting from 1) where the error occurred, or 0 if the
+    /// error is not associated with any line.
+    pub line: usize,
+    /// The kind of error.
+    pub kind: ParseErrorKind,
+}
+
+/// The different kinds of `ParseError`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// The input did not contain a line declaring the items.
+    MissingItems,
+    /// The items line started with `|` and was followed by a comment, so it
+    /// was most likely a comment itself.
+    CommentBeforeItems,
+    /// The item declaration contained more than one `|` separator.
+    RepeatedSeparator,
+    /// The same item name was declared more than once.
+    DuplicateItem(String),
+    /// An item name contained a `|`.
+    InvalidItemName(String),
+    /// An option referred to an item which was not declared.
+    UnknownItem(String),
+    /// An option listed the same item more than once.
+    RepeatedItemInOption,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::MissingItems => write!(f, "no items were declared")?,
+            ParseErrorKind::CommentBeforeItems => {
+                write!(f, "comments must follow the line declaring the items")?
+            }
+            ParseErrorKind::RepeatedSeparator => {
+                write!(f, "the item declaration has more than one `|` separator")?
+            }
+            ParseErrorKind::DuplicateItem(name) => write!(f, "item [{name}] declared twice")?,
+            ParseErrorKind::InvalidItemName(name) => {
+                write!(f, "item name [{name}] contains a `|`")?
+            }
+            ParseErrorKind::UnknownItem(name) => write!(f, "unknown item [{name}]")?,
+            ParseErrorKind::RepeatedItemInOption => {
+                write!(f, "option lists the same item more than once")?
+            }
+        }
+
+        if self.line != 0 {
+            write!(f, " (line {})", self.line)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SMALL_INPUT: &str = "\
+a b c d | x
+| A small problem with one secondary item
+
+a b
+c d x
+a c
+| not an option
+b d
+";
+
+    #[test]
+    fn parse_small_problem() {
+        let problem = TextProblem::parse(SMALL_INPUT).unwrap();
+
+        assert_eq!(
+            problem
+                .constraints
+                .iter()
+                .map(|cons| (cons.name.as_str(), cons.optional))
+                .collect::<Vec<_>>(),
+            vec![
+                ("a", false),
+                ("b", false),
+                ("c", false),
+                ("d", false),
+                ("x", true)
+            ]
+        );
+        assert_eq!(
+            problem
+                .possibilities
+                .iter()
+                .map(|poss| poss.items.clone())
+                .collect::<Vec<_>>(),
+            vec![vec![0, 1], vec![2, 3, 4], vec![0, 2], vec![1, 3]]
+        );
+        assert_eq!(
+            problem
+                .item_names(&problem.possibilities[1])
+                .collect::<Vec<_>>(),
+            vec!["c", "d", "x"]
+        );
+    }
+
+    #[test]
+    fn solve_small_problem() {
+        let problem: TextProblem = SMALL_INPUT.parse().unwrap();
+        let solutions = problem.solver().all_solutions();
+
+        assert_eq!(solutions.len(), 2);
+        assert_eq!(
+            solutions
+                .iter()
+                .map(|solution| solution.iter().map(|poss| poss.index).collect::<Vec<_>>())
+                .collect::<Vec<_>>(),
+            vec![vec![0, 1], vec![2, 3]]
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            TextProblem::parse("\n  \n").unwrap_err(),
+            ParseError {
+                line: 0,
+                kind: ParseErrorKind::MissingItems
+            }
+        );
+        assert_eq!(
+            TextProblem::parse("| A comment\n| in Knuth's format\na b\na\n").unwrap_err(),
+            ParseError {
+                line: 2,
+                kind: ParseErrorKind::CommentBeforeItems
+            }
+        );
+        assert_eq!(
+            TextProblem::parse("a | b | c\n").unwrap_err(),
+            ParseError {
+                line: 1,
+                kind: ParseErrorKind::RepeatedSeparator
+            }
+        );
+        assert_eq!(
+            TextProblem::parse("a b a\n").unwrap_err(),
+            ParseError {
+                line: 1,
+                kind: ParseErrorKind::DuplicateItem("a".into())
+            }
+        );
+        for items in ["a|b c", "a b |c", "a b c|"] {
+            assert_eq!(
+                TextProblem::parse(items).unwrap_err(),
+                ParseError {
+                    line: 1,
+                    kind: ParseErrorKind::InvalidItemName(
+                        items
+                            .split_whitespace()
+                            .find(|name| name.contains('|'))
+                            .unwrap()
+                            .into()
+                    )
+                }
+            );
+        }
+        assert_eq!(
+            TextProblem::parse("a b\na\n\nb c\n").unwrap_err(),
+            ParseError {
+                line: 4,
+                kind: ParseErrorKind::UnknownItem("c".into())
+            }
+        );
+        assert_eq!(
+            TextProblem::parse("a b\na b a\n").unwrap_err(),
+            ParseError {
+                line: 2,
+                kind: ParseErrorKind::RepeatedItemInOption
+            }
+        );
+        assert_eq!(
+            TextProblem::parse("a b\na c\n").unwrap_err().to_string(),
+            "unknown item [c] (line 2)"
+        );
+    }
+
+    #[test]
+    fn only_optional_items() {
+        let problem = TextProblem::parse("\n| x y\nx\n| a comment\ny\n").unwrap();
+
+        assert_eq!(
+            problem
+                .constraints
+                .iter()
+                .map(|cons| (cons.name.as_str(), cons.optional))
+                .collect::<Vec<_>>(),
+            vec![("x", true), ("y", true)]
+        );
+        assert_eq!(problem.possibilities.len(), 2);
+        assert_eq!(problem.solver().count(), 0);
+    }
+}
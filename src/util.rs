@@ -16,10 +16,83 @@ pub fn two_combination_iter(
         .flat_map(move |first| (start[1]..limits[1]).map(move |second| [first, second]))
 }
 
+/// A small, fast pseudo-random number generator ([SplitMix64]), used where the
+/// crate needs reproducible randomness without depending on an external crate.
+///
+/// [SplitMix64]: https://prng.di.unimi.it/splitmix64.c
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Return a value in `0..bound`, which must not be empty.
+    pub fn below(&mut self, bound: usize) -> usize {
+        assert!(bound > 0, "The bound must be positive.");
+
+        // The modulo bias is negligible for the small bounds used in this crate
+        (self.next_u64() % (bound as u64)) as usize
+    }
+
+    /// Return a value in `[0, 1)`.
+    #[cfg_attr(not(any(test, feature = "testing")), allow(dead_code))]
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Shuffle the given slice, so that every order is about equally likely.
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        for index in (1..slice.len()).rev() {
+            slice.swap(index, self.below(index + 1));
+        }
+    }
+
+    /// Return true with the given probability.
+    #[cfg_attr(not(any(test, feature = "testing")), allow(dead_code))]
+    pub fn chance(&mut self, probability: f64) -> bool {
+        self.next_f64() < probability
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn split_mix_64() {
+        // First outputs for seed 1234567 from the reference implementation
+        let mut rng = SplitMix64::new(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+
+        let mut rng = SplitMix64::new(0);
+        for _ in 0..1000 {
+            assert!(rng.below(7) < 7);
+            let value = rng.next_f64();
+            assert!((0.0..1.0).contains(&value));
+        }
+        assert!(!rng.chance(0.0));
+        assert!(rng.chance(1.0));
+
+        let mut values: Vec<_> = (0..20).collect();
+        rng.shuffle(&mut values);
+        assert_ne!(values, (0..20).collect::<Vec<_>>());
+        values.sort_unstable();
+        assert_eq!(values, (0..20).collect::<Vec<_>>());
+    }
+
     #[test]
     fn three_combo() {
         let it = three_combination_iter([2, 4, 6], [0, 2, 4]);
@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+
+use std::{
+    io::Write,
+    process::{Command, Output, Stdio},
+};
+
+const PROBLEM: &str = "\
+A B C D E F G | x
+| Knuth's example from the Dancing Links paper, with an extra secondary item
+C E F
+A D G
+B C F
+A D
+B G x
+D E G
+";
+
+fn run_dlx(args: &[&str], input: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_dlx"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> &str {
+    std::str::from_utf8(&output.stdout).unwrap()
+}
+
+#[test]
+fn print_solution_indices() {
+    let output = run_dlx(&[], PROBLEM);
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "0 3 4\n");
+}
+
+#[test]
+fn print_solution_names() {
+    let output = run_dlx(&["--names", "-"], PROBLEM);
+
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "C E F, A D, B G x\n");
+}
+
+#[test]
+fn count_and_limit_solutions() {
+    let input = "a b\na\nb\na b\n";
+
+    let output = run_dlx(&["--count"], input);
+    assert_eq!(stdout(&output), "2\n");
+
+    let output = run_dlx(&["-c", "--max", "1", "--heuristic", "first"], input);
+    assert_eq!(stdout(&output), "1\n");
+
+    let output = run_dlx(&["-n", "1"], input);
+    assert_eq!(stdout(&output), "0 1\n");
+}
+
+#[test]
+fn print_stats() {
+    let output = run_dlx(&["--count", "--stats"], PROBLEM);
+    let stderr = std::str::from_utf8(&output.stderr).unwrap();
+
+    assert!(output.status.success());
+    assert!(stderr.contains("solutions: 1\n"), "{stderr}");
+    assert!(stderr.contains("nodes: "), "{stderr}");
+}
+
+#[test]
+fn report_errors() {
+    let output = run_dlx(&[], "a b\na c\n");
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        std::str::from_utf8(&output.stderr).unwrap(),
+        "error: unknown item [c] (line 2)\n"
+    );
+
+    let output = run_dlx(&["--heuristic", "best"], "");
+    assert_eq!(output.status.code(), Some(2));
+}
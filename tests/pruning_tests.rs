@@ -0,0 +1,301 @@
+use dancing_links::{
+    bitset::BitsetSolver,
+    combinators::{Product, Restrict},
+    components::ComponentSolver,
+    dot::{SearchNodeKind, SearchTree},
+    latin_square::{self, LatinSquare},
+    ordered::OrderedSolver,
+    queens::NQueens,
+    ColumnHeuristic, ExactCover, Learning, PartialSolution, Solver,
+};
+
+/// A latin square where the values on both diagonals must also be distinct,
+/// which is checked while solving instead of being expressed as constraints.
+struct DiagonalLatinSquare {
+    latin: LatinSquare,
+    side_length: usize,
+}
+
+impl DiagonalLatinSquare {
+    fn new(side_length: usize) -> Self {
+        Self {
+            latin: LatinSquare::new(side_length, []),
+            side_length,
+        }
+    }
+
+    fn is_diagonal_latin(&self, solution: &[&latin_square::Possibility]) -> bool {
+        let n = self.side_length;
+        let main = solution.iter().filter(|poss| poss.row == poss.column);
+        let anti = solution
+            .iter()
+            .filter(|poss| poss.row + poss.column == n - 1);
+
+        [main.collect::<Vec<_>>(), anti.collect::<Vec<_>>()]
+            .iter()
+            .all(|diagonal| {
+                (1..=n).all(|value| diagonal.iter().filter(|poss| poss.value == value).count() <= 1)
+            })
+    }
+}
+
+impl ExactCover for DiagonalLatinSquare {
+    type Constraint = latin_square::Constraint;
+    type Possibility = latin_square::Possibility;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        self.latin.satisfies(poss, cons)
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        self.latin.is_optional(cons)
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        self.latin.possibilities()
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        self.latin.constraints()
+    }
+
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+        // Only the last possibility is new, and the rest were already checked
+        let last = partial.possibilities().last().unwrap();
+        let n = self.side_length;
+        let same_diagonal = |poss: &latin_square::Possibility| {
+            (last.row == last.column && poss.row == poss.column)
+                || (last.row + last.column == n - 1 && poss.row + poss.column == n - 1)
+        };
+
+        partial
+            .possibilities()
+            .take(partial.depth() - 1)
+            .all(|poss| poss.value != last.value || !same_diagonal(poss))
+    }
+}
+
+/// A latin square which rejects every partial solution that leaves a
+/// constraint without any remaining possibilities.
+struct LookaheadLatinSquare(LatinSquare);
+
+impl ExactCover for LookaheadLatinSquare {
+    type Constraint = latin_square::Constraint;
+    type Possibility = latin_square::Possibility;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        self.0.satisfies(poss, cons)
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        self.0.is_optional(cons)
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        self.0.possibilities()
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        self.0.constraints()
+    }
+
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+        (0..self.constraints().len()).all(|constraint| partial.remaining(constraint) != Some(0))
+    }
+}
+
+/// A problem which checks that every partial solution it is given is
+/// consistent with its own possibilities and constraints.
+struct Checked<E>(E);
+
+impl<E: ExactCover> ExactCover for Checked<E> {
+    type Constraint = E::Constraint;
+    type Possibility = E::Possibility;
+
+    fn satisfies(&self, poss: &Self::Possibility, cons: &Self::Constraint) -> bool {
+        self.0.satisfies(poss, cons)
+    }
+
+    fn is_optional(&self, cons: &Self::Constraint) -> bool {
+        self.0.is_optional(cons)
+    }
+
+    fn possibilities(&self) -> &[Self::Possibility] {
+        self.0.possibilities()
+    }
+
+    fn constraints(&self) -> &[Self::Constraint] {
+        self.0.constraints()
+    }
+
+    fn is_viable(&self, partial: &PartialSolution<'_, '_, Self>) -> bool {
+        assert!(partial.depth() > 0);
+        for (index, cons) in self.constraints().iter().enumerate() {
+            let satisfied = partial
+                .possibilities()
+                .any(|poss| self.satisfies(poss, cons));
+            assert_eq!(partial.remaining(index).is_none(), satisfied);
+        }
+
+        true
+    }
+}
+
+fn sorted(
+    solutions: impl Iterator<Item = Vec<latin_square::Possibility>>,
+) -> Vec<Vec<latin_square::Possibility>> {
+    let mut solutions: Vec<_> = solutions
+        .map(|mut solution| {
+            solution.sort();
+            solution
+        })
+        .collect();
+    solutions.sort();
+
+    solutions
+}
+
+fn owned(solution: Vec<&latin_square::Possibility>) -> Vec<latin_square::Possibility> {
+    solution.into_iter().copied().collect()
+}
+
+#[test]
+fn prune_instead_of_filtering() {
+    let problem = DiagonalLatinSquare::new(4);
+
+    let filtered = sorted(
+        problem
+            .latin
+            .solver()
+            .filter(|solution| problem.is_diagonal_latin(solution))
+            .map(owned),
+    );
+    assert_eq!(filtered.len(), 48);
+
+    let mut solver = problem.solver();
+    let solutions = sorted(solver.by_ref().map(owned));
+    assert_eq!(solutions, filtered);
+
+    let stats = solver.stats();
+    assert!(stats.pruned > 0);
+    let mut unpruned = problem.latin.solver();
+    unpruned.by_ref().for_each(drop);
+    assert!(stats.nodes < unpruned.stats().nodes);
+
+    let mut bitset = BitsetSolver::<_, 1>::new(&problem).unwrap();
+    assert_eq!(sorted(bitset.by_ref().map(owned)), filtered);
+    assert_eq!(bitset.stats(), stats);
+
+    assert_eq!(sorted(OrderedSolver::new(&problem).map(owned)), filtered);
+
+    // Branches cut by `is_viable` don't produce nogoods, so no solution is lost
+    let learning = problem.solver().with_learning(Learning::default());
+    assert_eq!(sorted(learning.map(owned)), filtered);
+}
+
+#[test]
+fn prune_with_remaining_possibilities() {
+    let filled = latin_square::Possibility {
+        row: 0,
+        column: 0,
+        value: 2,
+    };
+    let problem = LookaheadLatinSquare(LatinSquare::new(4, [filled]));
+    let expected = sorted(problem.0.solver().map(owned));
+
+    for heuristic in [
+        ColumnHeuristic::MinimumRemainingValues,
+        ColumnHeuristic::FirstUncovered,
+    ] {
+        let mut solver = Solver::with_heuristic(&problem, heuristic);
+        assert_eq!(sorted(solver.by_ref().map(owned)), expected);
+
+        // Every branch that would reach a dead end is pruned one step earlier
+        assert_eq!(solver.stats().dead_ends, 0);
+    }
+}
+
+#[test]
+fn pruned_nodes_in_search_tree() {
+    let problem = DiagonalLatinSquare::new(4);
+    let tree = SearchTree::record(&problem, ColumnHeuristic::default(), usize::MAX);
+
+    let count = |kind: SearchNodeKind| tree.nodes().iter().filter(|node| node.kind == kind).count();
+    assert_eq!(count(SearchNodeKind::Solution), 48);
+    assert!(count(SearchNodeKind::Pruned) > 0);
+    assert!(tree.to_dot(&problem).contains("label=\"pruned\""));
+}
+
+#[test]
+fn combinators_forward_pruning() {
+    let problem = DiagonalLatinSquare::new(4);
+
+    // By symmetry, a quarter of the solutions have a 1 in the corner
+    let restricted = Restrict::new(&problem, |poss| {
+        poss.value != 1 || (poss.row, poss.column) != (0, 0)
+    });
+    let mut solver = restricted.solver();
+    assert_eq!(solver.by_ref().count(), 48 - 12);
+    assert!(solver.stats().pruned > 0);
+
+    // The latin square comes second, so its possibilities and constraints are
+    // offset by the ones of the queens
+    let queens = NQueens::new(4, []);
+    let product = Product::new(&queens, &problem);
+    let mut solver = product.solver();
+    assert_eq!(solver.by_ref().count(), 2 * 48);
+    assert!(solver.stats().pruned > 0);
+
+    // Each component of the product is checked on its own
+    assert_eq!(ComponentSolver::new(&product).count(), 2 * 48);
+
+    let mut bitset = BitsetSolver::<_, 2>::new(&product).unwrap();
+    assert_eq!(bitset.by_ref().count(), 2 * 48);
+    assert_eq!(bitset.stats(), solver.stats());
+
+    // Each side sees its own part of the partial solution
+    let product = Product::new(
+        Checked(NQueens::new(5, [])),
+        Checked(LatinSquare::new(3, [])),
+    );
+    assert_eq!(product.solver().count(), 10 * 12);
+}
+
+#[test]
+fn forced_rows_are_checked_one_at_a_time() {
+    let problem = DiagonalLatinSquare::new(4);
+    let index = |row, column, value| {
+        let poss = latin_square::Possibility { row, column, value };
+        problem
+            .possibilities()
+            .iter()
+            .position(|other| *other == poss)
+            .unwrap()
+    };
+
+    // The first two rows put a 1 twice on the main diagonal, and the last one
+    // doesn't conflict with either, so a hook which only looks at the newest
+    // possibility needs each of them to be checked as it is added
+    let forced = [index(0, 0, 1), index(1, 1, 1), index(0, 3, 2)];
+    let mut solver =
+        Solver::with_forced_rows(&problem, ColumnHeuristic::default(), &forced).unwrap();
+    assert_eq!(solver.by_ref().count(), 0);
+    assert_eq!(solver.stats().pruned, 1);
+
+    solver.reset();
+    assert_eq!(solver.count(), 0);
+
+    // Without the conflict, the forced rows are kept in every solution
+    let forced = [index(0, 0, 1), index(1, 1, 2), index(0, 3, 2)];
+    let solver = Solver::with_forced_rows(&problem, ColumnHeuristic::default(), &forced).unwrap();
+    let expected = problem
+        .solver()
+        .filter(|solution| {
+            forced
+                .iter()
+                .all(|row| solution.contains(&&problem.possibilities()[*row]))
+        })
+        .count();
+    assert!(expected > 0);
+    assert_eq!(solver.count(), expected);
+}
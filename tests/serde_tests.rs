@@ -0,0 +1,139 @@
+#![cfg(feature = "serde")]
+
+use dancing_links::{
+    latin_square::{self, LatinSquare},
+    polyomino::{self, PShape, Polyomino, ShapeTransform},
+    queens::NQueens,
+    sudoku::{self, Sudoku},
+    text::TextProblem,
+    Checkpoint, ExactCover, Solution, Solver,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+    let json = serde_json::to_string(value).unwrap();
+
+    serde_json::from_str(&json).unwrap()
+}
+
+/// Check that a problem survives serialization, and still has the same
+/// solutions.
+fn assert_problem_round_trip<E>(problem: &E)
+where
+    E: ExactCover + Serialize + DeserializeOwned,
+    E::Possibility: PartialEq,
+    E::Constraint: PartialEq,
+{
+    let copy = round_trip(problem);
+
+    assert!(copy.possibilities() == problem.possibilities());
+    assert!(copy.constraints() == problem.constraints());
+    assert!(copy.solver().eq(problem.solver()));
+}
+
+#[test]
+fn puzzle_round_trips() {
+    assert_problem_round_trip(&Sudoku::new(
+        2,
+        [latin_square::Possibility {
+            row: 0,
+            column: 0,
+            value: 1,
+        }],
+    ));
+    assert_problem_round_trip(&LatinSquare::new(3, []));
+    assert_problem_round_trip(&NQueens::new(5, []));
+    assert_problem_round_trip(&TextProblem::parse("a b | x\na x\nb\na b\n").unwrap());
+    assert_problem_round_trip(&Polyomino::new(
+        (2, 3),
+        vec![
+            PShape::from([[1, 1], [1, 0]]),
+            PShape::from([[1], [1], [1]]),
+        ],
+        ShapeTransform::FullSymmetry,
+    ));
+
+    let cell = sudoku::Constraint::SquareNumber {
+        square: 1,
+        value: 2,
+    };
+    assert_eq!(round_trip(&cell), cell);
+    let placement = polyomino::Possibility {
+        shape_index: 1,
+        occupied_cells: vec![(0, 0), (0, 1)],
+    };
+    assert_eq!(round_trip(&placement), placement);
+}
+
+#[test]
+fn solution_round_trip() {
+    let queens = NQueens::new(6, []);
+    let mut solutions = Vec::new();
+    let _ = queens.solver().solve_with(|solution| {
+        solutions.push(solution.to_solution());
+        core::ops::ControlFlow::Continue(())
+    });
+
+    let json = serde_json::to_string(&solutions[0]).unwrap();
+    assert_eq!(json, r#"{"possibilities":[3,6,16,19,29,32]}"#);
+
+    let copy: Solution = serde_json::from_str(&json).unwrap();
+    let mut resolved = copy.resolve(&queens).unwrap();
+    resolved.sort();
+    let mut expected = queens.solver().next().unwrap();
+    expected.sort();
+    assert_eq!(resolved, expected);
+
+    assert!(Solution::new([0, 36]).resolve(&queens).is_none());
+}
+
+#[test]
+fn checkpoint_round_trip() {
+    let queens = NQueens::new(6, []);
+    let mut solver = queens.solver();
+    let first = solver.next().unwrap();
+
+    let checkpoint: Checkpoint = round_trip(&solver.checkpoint());
+    assert_eq!(checkpoint, solver.checkpoint());
+
+    let resumed = Solver::from_checkpoint(&queens, &checkpoint).unwrap();
+    let remaining: Vec<_> = resumed.collect();
+    assert_eq!(remaining.len(), 3);
+    assert!(!remaining.contains(&first));
+    assert!(solver.eq(remaining));
+}
+
+#[test]
+fn corrupted_checkpoint() {
+    let queens = NQueens::new(6, []);
+    let solver = Solver::with_forced_rows(&queens, Default::default(), &[1]).unwrap();
+    let mut json = serde_json::to_value(solver.checkpoint()).unwrap();
+    assert_eq!(json["forced_rows"], serde_json::json!([1]));
+
+    for row in [36, usize::MAX] {
+        json["forced_rows"] = serde_json::json!([row]);
+        let checkpoint: Checkpoint = serde_json::from_value(json.clone()).unwrap();
+        assert!(Solver::from_checkpoint(&queens, &checkpoint).is_none());
+    }
+}
+
+#[test]
+fn text_problem_is_checked() {
+    let json = r#"{
+        "possibilities": [{"index": 0, "items": [1, 0, 1]}, {"index": 1, "items": []}],
+        "constraints": [
+            {"index": 0, "name": "a", "optional": false},
+            {"index": 1, "name": "b", "optional": false}
+        ]
+    }"#;
+    let problem: TextProblem = serde_json::from_str(json).unwrap();
+    assert_eq!(problem.possibilities[0].items, [0, 1]);
+    assert_eq!(problem.solver().count(), 1);
+
+    let out_of_range = json.replace("[1, 0, 1]", "[0, 2]");
+    assert!(serde_json::from_str::<TextProblem>(&out_of_range).is_err());
+    let wrong_index = json.replace(r#""index": 1, "items""#, r#""index": 0, "items""#);
+    assert!(serde_json::from_str::<TextProblem>(&wrong_index).is_err());
+    let wrong_item_index = json.replace(r#""index": 1, "name""#, r#""index": 5, "name""#);
+    assert!(serde_json::from_str::<TextProblem>(&wrong_item_index).is_err());
+}